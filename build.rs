@@ -3,8 +3,11 @@
 //! This script handles compilation of whisper.cpp when the `whisper` feature is enabled.
 //! Priority: 1) System installation (Homebrew), 2) Git submodule
 
+#[cfg(feature = "whisper")]
 use std::env;
+#[cfg(feature = "whisper")]
 use std::path::PathBuf;
+#[cfg(feature = "whisper")]
 use std::process::Command;
 
 fn main() {
@@ -1,9 +1,19 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use log::info;
 use std::path::PathBuf;
 
-#[cfg(feature = "whisper")]
+#[cfg(feature = "http")]
+use orangenote_cli::infrastructure::transcription::format::{
+    format_result, format_stream_segment, validate_format, validate_stream_format,
+};
+#[cfg(feature = "http")]
+use orangenote_cli::infrastructure::transcription::http::unsupported_options;
+#[cfg(feature = "http")]
+use orangenote_cli::infrastructure::transcription::pipeline;
+#[cfg(feature = "http")]
+use orangenote_cli::infrastructure::transcription::review::review_items;
+#[cfg(feature = "http")]
 use orangenote_cli::AudioDecoder;
 
 /// OrangeNote CLI - Offline audio transcription tool
@@ -28,44 +38,16 @@ struct Cli {
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Transcribe an audio file
-    Transcribe {
-        /// Path to audio file (mp3, wav, m4a, flac, etc.)
-        #[arg(value_name = "INPUT")]
-        input: PathBuf,
-
-        /// Whisper model to use (tiny, base, small, medium, large)
-        #[arg(short, long, default_value = "base")]
-        model: String,
-
-        /// Language code (e.g., 'en', 'ru', 'fr'). Auto-detect if not specified
-        #[arg(short, long)]
-        language: Option<String>,
+    Transcribe(Box<TranscribeArgs>),
 
-        /// Output format (json, txt, srt, vtt, tsv)
-        #[arg(short, long, default_value = "json")]
-        format: String,
+    /// Detect the spoken language of an audio file without transcribing it
+    DetectLanguage(DetectLanguageArgs),
 
-        /// Output file path. If not specified, output goes to stdout
-        #[arg(short, long)]
-        output: Option<PathBuf>,
-
-        /// Number of threads for processing
-        #[arg(short, long, default_value = "4")]
-        threads: usize,
-
-        /// Translate to English
-        #[arg(long)]
-        translate: bool,
+    /// Serve OpenAI-compatible transcription endpoints over HTTP
+    Serve(ServeArgs),
 
-        /// Chunk size in minutes for long audio files (0 = no chunking)
-        /// Recommended: 5-10 minutes for optimal transcription quality
-        #[arg(long, default_value = "0", value_name = "MINUTES")]
-        chunk_size: u32,
-
-        /// Overlap between chunks in seconds (helps maintain context at boundaries)
-        #[arg(long, default_value = "5", value_name = "SECONDS")]
-        chunk_overlap: u32,
-    },
+    /// Speak JSON-RPC 2.0 over stdin/stdout, for front-ends driving orangenote
+    Rpc(RpcArgs),
 
     /// Manage transcription models
     #[command(subcommand)]
@@ -75,6 +57,366 @@ enum Commands {
     Info,
 }
 
+/// Print a status line to stdout, or to stderr when stdout carries streamed segments
+#[cfg(feature = "http")]
+macro_rules! status {
+    ($to_stderr:expr, $($arg:tt)*) => {
+        if $to_stderr {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Arguments for the `transcribe` command
+#[derive(Args, Debug)]
+struct TranscribeArgs {
+    /// Path to audio file (mp3, wav, m4a, flac, etc.)
+    #[arg(value_name = "INPUT")]
+    input: PathBuf,
+
+    /// Whisper model to use (tiny, base, small, medium, large, small.en-tdrz)
+    #[arg(short, long, default_value = "base")]
+    model: String,
+
+    /// Transcription backend: whisper (local whisper.cpp) or http (OpenAI-compatible server)
+    #[arg(long, default_value = "whisper", value_name = "BACKEND")]
+    backend: String,
+
+    /// Server URL for the http backend (e.g. http://localhost:8080)
+    #[arg(long, value_name = "URL")]
+    endpoint: Option<String>,
+
+    /// Model name sent to the server by the http backend
+    #[arg(long, default_value = "whisper-1", value_name = "NAME")]
+    http_model: String,
+
+    /// API key for the http backend (default: OPENAI_API_KEY environment variable)
+    #[arg(long, value_name = "KEY")]
+    api_key: Option<String>,
+
+    /// Language code (e.g., 'en', 'ru', 'fr') or 'auto'. Auto-detect if not specified
+    #[arg(short, long)]
+    language: Option<String>,
+
+    /// Re-detect the language for every chunk (for audio switching between languages)
+    #[arg(long, conflicts_with = "language")]
+    language_per_chunk: bool,
+
+    /// Output format (json, txt, srt, vtt, tsv)
+    #[arg(short, long, default_value = "json")]
+    format: String,
+
+    /// Print segments to stdout as they are recognized (jsonl or text)
+    #[arg(long, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "jsonl")]
+    stream: Option<String>,
+
+    /// Include word-level timestamps in JSON output
+    #[arg(long)]
+    word_timestamps: bool,
+
+    /// Maximum segment length in characters, for short subtitle segments (0 = no limit)
+    #[arg(long, default_value = "0", value_name = "CHARS")]
+    max_len: usize,
+
+    /// Split segments on word boundaries instead of tokens (used with --max-len)
+    #[arg(long)]
+    split_on_word: bool,
+
+    /// Mark speaker turns (requires the small.en-tdrz tinydiarize model)
+    #[arg(long)]
+    speaker_turns: bool,
+
+    /// Flag words and segments below this probability (0.0 - 1.0) for review
+    #[arg(long, value_name = "PROB")]
+    review_threshold: Option<f32>,
+
+    /// Align token timestamps with DTW (more accurate word timing, slightly slower)
+    #[arg(long)]
+    dtw: bool,
+
+    /// Use DTW timestamps for tokens and words instead of whisper's heuristic ones
+    #[arg(long, requires = "dtw")]
+    prefer_dtw: bool,
+
+    /// Output file path. If not specified, output goes to stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Number of threads for processing
+    #[arg(short, long, default_value = "4")]
+    threads: usize,
+
+    /// Translate to English
+    #[arg(long)]
+    translate: bool,
+
+    /// Chunk size in minutes for long audio files (0 = no chunking)
+    /// Recommended: 5-10 minutes for optimal transcription quality
+    #[arg(long, default_value = "0", value_name = "MINUTES")]
+    chunk_size: u32,
+
+    /// Overlap between chunks in seconds (helps maintain context at boundaries)
+    #[arg(long, default_value = "5", value_name = "SECONDS")]
+    chunk_overlap: u32,
+
+    /// Number of trailing words from each chunk passed as context to the next one (0 = disabled)
+    #[arg(long, default_value = "0", value_name = "WORDS")]
+    chunk_context: usize,
+
+    /// Number of chunks transcribed at the same time (threads are split between them)
+    #[arg(long, default_value = "1", value_name = "N")]
+    parallel_chunks: usize,
+
+    /// Use beam search with the given beam size (greedy sampling if not specified)
+    #[arg(long, value_name = "N")]
+    beam_size: Option<usize>,
+
+    /// Number of candidates to sample when decoding with temperature > 0
+    #[arg(long, default_value = "5", value_name = "N")]
+    best_of: usize,
+
+    /// Initial decoding temperature (0.0 = deterministic)
+    #[arg(long, default_value = "0.0", value_name = "TEMPERATURE")]
+    temperature: f32,
+
+    /// Disable temperature fallback when a decode fails the quality thresholds
+    #[arg(long)]
+    no_fallback: bool,
+
+    /// Initial prompt to guide style and spelling (e.g., "Meeting notes about OrangeNote.")
+    #[arg(long, value_name = "TEXT")]
+    prompt: Option<String>,
+
+    /// File with vocabulary terms (one per line) added to the initial prompt
+    #[arg(long, value_name = "FILE")]
+    vocab_file: Option<PathBuf>,
+
+    /// Regex of tokens whisper must never emit (e.g., '♪+|Subtitles by.*')
+    #[arg(long, value_name = "REGEX")]
+    suppress_regex: Option<String>,
+
+    /// File with words (one per line) whose tokens are boosted while decoding
+    #[arg(long, value_name = "FILE")]
+    boost_words: Option<PathBuf>,
+
+    /// Logit bias added to the tokens of boosted words
+    #[arg(
+        long,
+        default_value = "2.0",
+        value_name = "BIAS",
+        requires = "boost_words"
+    )]
+    boost_weight: f32,
+
+    /// GBNF grammar file constraining the transcribed text (e.g., voice commands)
+    #[arg(long, value_name = "FILE")]
+    grammar: Option<PathBuf>,
+
+    /// Grammar rule to start decoding from
+    #[arg(
+        long,
+        default_value = "root",
+        value_name = "RULE",
+        requires = "grammar"
+    )]
+    grammar_rule: String,
+
+    /// Penalty for tokens the grammar does not allow (higher = stricter)
+    #[arg(
+        long,
+        default_value = "100",
+        value_name = "PENALTY",
+        requires = "grammar"
+    )]
+    grammar_penalty: f32,
+
+    /// Do not re-decode chunks that look hallucinated (repetition loops, text over silence)
+    #[arg(long)]
+    no_retry: bool,
+
+    /// Maximum re-decodes of a chunk that looks hallucinated (with --chunk-size)
+    #[arg(
+        long,
+        default_value = "2",
+        value_name = "N",
+        conflicts_with = "no_retry"
+    )]
+    max_retries: usize,
+
+    /// Keep the initial prompt in front of every decode window, so every chunk sees the vocabulary
+    #[arg(long)]
+    carry_prompt: bool,
+
+    /// Skip silence and non-speech using voice activity detection (Silero VAD)
+    #[arg(long)]
+    vad: bool,
+
+    /// Path to a ggml Silero VAD model (downloaded to the model cache if not specified)
+    #[arg(long, value_name = "FILE", requires = "vad")]
+    vad_model: Option<PathBuf>,
+
+    /// VAD speech probability threshold (0.0 - 1.0)
+    #[arg(long, default_value = "0.5", value_name = "THRESHOLD")]
+    vad_threshold: f32,
+
+    /// Minimum speech duration in milliseconds for VAD
+    #[arg(long, default_value = "250", value_name = "MS")]
+    vad_min_speech: u32,
+
+    /// Minimum silence duration in milliseconds that ends a speech region
+    #[arg(long, default_value = "100", value_name = "MS")]
+    vad_min_silence: u32,
+
+    /// Padding in milliseconds added around each speech region
+    #[arg(long, default_value = "30", value_name = "MS")]
+    vad_padding: u32,
+}
+
+#[cfg(feature = "http")]
+impl TranscribeArgs {
+    /// Build whisper decoding options from the command line arguments
+    fn transcribe_options(&self) -> Result<orangenote_cli::TranscribeOptions> {
+        use orangenote_cli::infrastructure::transcription::prompt::{
+            build_initial_prompt, load_vocab_file,
+        };
+
+        let mut options = orangenote_cli::TranscribeOptions::new()
+            .translate(self.translate)
+            .threads(self.threads)
+            .best_of(self.best_of)
+            .temperature(self.temperature)
+            .carry_initial_prompt(self.carry_prompt)
+            .chunk_context_words(self.chunk_context)
+            .language_per_chunk(self.language_per_chunk)
+            .max_len(self.max_len)
+            .split_on_word(self.split_on_word)
+            .prefer_dtw_timestamps(self.prefer_dtw)
+            .speaker_turns(self.speaker_turns);
+
+        options.language = self.language.clone().filter(|lang| lang != "auto");
+        options.beam_size = self.beam_size;
+
+        if self.no_fallback {
+            options = options.no_fallback();
+        }
+
+        let vocab = match &self.vocab_file {
+            Some(path) => load_vocab_file(path)?,
+            None => Vec::new(),
+        };
+        options.initial_prompt = build_initial_prompt(self.prompt.as_deref(), &vocab);
+
+        options.suppress_regex = self.suppress_regex.clone();
+        if let Some(path) = &self.boost_words {
+            options = options
+                .boost_words(load_vocab_file(path)?)
+                .boost_weight(self.boost_weight);
+        }
+
+        if let Some(path) = &self.grammar {
+            options = options
+                .grammar(orangenote_cli::Grammar::from_file(path)?)
+                .grammar_rule(self.grammar_rule.clone())
+                .grammar_penalty(self.grammar_penalty);
+        }
+
+        if !self.no_retry {
+            options = options
+                .quality(orangenote_cli::QualityConfig::default().max_retries(self.max_retries));
+        }
+
+        if self.vad {
+            // An empty model path is resolved from the model cache in `handle_transcribe`
+            let vad = orangenote_cli::VadOptions::new(self.vad_model.clone().unwrap_or_default())
+                .threshold(self.vad_threshold)
+                .min_speech_duration_ms(self.vad_min_speech)
+                .min_silence_duration_ms(self.vad_min_silence)
+                .speech_pad_ms(self.vad_padding);
+            options = options.vad(vad);
+        }
+
+        Ok(options)
+    }
+}
+
+/// Arguments for the `detect-language` command
+#[derive(Args, Debug)]
+struct DetectLanguageArgs {
+    /// Path to audio file (mp3, wav, m4a, flac, etc.)
+    #[arg(value_name = "INPUT")]
+    input: PathBuf,
+
+    /// Whisper model to use (must be multilingual, not an .en model)
+    #[arg(short, long, default_value = "base")]
+    model: String,
+
+    /// Duration of each analyzed window in seconds (whisper uses at most 30)
+    #[arg(long, default_value = "30", value_name = "SECONDS")]
+    duration: u32,
+
+    /// Number of windows sampled evenly across the file (1 = only the beginning)
+    #[arg(long, default_value = "1", value_name = "N")]
+    windows: usize,
+
+    /// Number of most probable languages to report
+    #[arg(long, default_value = "5", value_name = "N")]
+    top: usize,
+
+    /// Output file path. If not specified, output goes to stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Number of threads for processing
+    #[arg(short, long, default_value = "4")]
+    threads: usize,
+}
+
+/// Arguments for the `serve` command
+#[derive(Args, Debug)]
+struct ServeArgs {
+    /// Whisper model loaded once and used for all requests
+    #[arg(short, long, default_value = "base")]
+    model: String,
+
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Port to listen on
+    #[arg(short, long, default_value = "8080")]
+    port: u16,
+
+    /// Number of threads per request
+    #[arg(short, long, default_value = "4")]
+    threads: usize,
+
+    /// Maximum number of requests transcribed at the same time (more get 429)
+    #[arg(long, default_value = "1", value_name = "N")]
+    max_concurrent: usize,
+
+    /// Maximum upload size in megabytes
+    #[arg(long, default_value = "25", value_name = "MB")]
+    max_upload_mb: usize,
+
+    /// Chunk size in minutes for long uploads (0 = no chunking)
+    #[arg(long, default_value = "0", value_name = "MINUTES")]
+    chunk_size: u32,
+
+    /// Overlap between chunks in seconds
+    #[arg(long, default_value = "5", value_name = "SECONDS")]
+    chunk_overlap: u32,
+}
+
+/// Arguments for the `rpc` command
+#[derive(Args, Debug)]
+struct RpcArgs {
+    /// Default number of threads for transcriptions
+    #[arg(short, long, default_value = "4")]
+    threads: usize,
+}
+
 #[derive(Subcommand, Debug)]
 enum ModelCommands {
     /// List available models
@@ -117,8 +459,8 @@ fn init_logging(verbose: bool, log_level: Option<String>) {
         .init();
 }
 
-#[cfg(feature = "whisper")]
-fn validate_input_file(path: &PathBuf) -> Result<()> {
+#[cfg(feature = "http")]
+fn validate_input_file(path: &std::path::Path) -> Result<()> {
     if !path.exists() {
         anyhow::bail!("Input file does not exist: {}", path.display());
     }
@@ -132,7 +474,7 @@ fn validate_input_file(path: &PathBuf) -> Result<()> {
         .and_then(|ext| ext.to_str())
         .map(|s| s.to_lowercase());
 
-    let supported_formats = vec!["mp3", "wav", "m4a", "flac", "ogg", "wma"];
+    let supported_formats = ["mp3", "wav", "m4a", "flac", "ogg", "wma"];
     if let Some(ext) = extension {
         if !supported_formats.contains(&ext.as_str()) {
             anyhow::bail!(
@@ -149,7 +491,7 @@ fn validate_input_file(path: &PathBuf) -> Result<()> {
 }
 
 fn validate_model(model: &str) -> Result<()> {
-    let valid_models = vec!["tiny", "base", "small", "medium", "large"];
+    let valid_models = ["tiny", "base", "small", "medium", "large", "small.en-tdrz"];
     if !valid_models.contains(&model) {
         anyhow::bail!(
             "Invalid model: '{}'. Valid models: {}",
@@ -161,7 +503,7 @@ fn validate_model(model: &str) -> Result<()> {
 }
 
 /// Validate chunk configuration parameters
-#[cfg(feature = "whisper")]
+#[cfg(feature = "http")]
 fn validate_chunk_config(chunk_size: u32, chunk_overlap: u32) -> Result<()> {
     // chunk_size = 0 means no chunking, which is valid
     if chunk_size == 0 {
@@ -202,33 +544,60 @@ fn validate_chunk_config(chunk_size: u32, chunk_overlap: u32) -> Result<()> {
     Ok(())
 }
 
-#[cfg(feature = "whisper")]
-fn validate_format(format: &str) -> Result<()> {
-    let valid_formats = vec!["json", "txt", "srt", "vtt", "tsv"];
-    if !valid_formats.contains(&format) {
-        anyhow::bail!(
-            "Invalid format: '{}'. Valid formats: {}",
-            format,
-            valid_formats.join(", ")
-        );
+/// Resolution of the transcription progress bar
+#[cfg(feature = "http")]
+const PROGRESS_BAR_STEPS: u64 = 1000;
+
+/// Create the transcription progress bar
+///
+/// Returns `None` when stderr is not a terminal (e.g., redirected to a log file),
+/// where a redrawing bar would only produce noise.
+#[cfg(feature = "http")]
+fn transcription_progress_bar(audio_secs: f64) -> Result<Option<indicatif::ProgressBar>> {
+    use indicatif::{ProgressBar, ProgressStyle};
+    use std::io::IsTerminal;
+
+    if !std::io::stderr().is_terminal() {
+        return Ok(None);
     }
-    Ok(())
+
+    let pb = ProgressBar::new(PROGRESS_BAR_STEPS);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(
+                "{spinner:.green} {prefix} [{bar:40.cyan/blue}] {percent}% {elapsed_precise} (ETA {eta}) {msg}",
+            )
+            .context("Failed to set progress bar style")?
+            .progress_chars("#>-"),
+    );
+    pb.set_prefix(format!("{:.0}s audio", audio_secs));
+    pb.enable_steady_tick(std::time::Duration::from_millis(200));
+
+    Ok(Some(pb))
 }
 
 #[cfg(feature = "whisper")]
-/// Format transcription result as JSON
-fn format_json(result: &orangenote_cli::TranscriptionResult) -> Result<String> {
+fn format_language_detection(detection: &orangenote_cli::LanguageDetection) -> Result<String> {
+    let languages = |languages: &[orangenote_cli::LanguageProbability]| {
+        languages
+            .iter()
+            .map(|lang| {
+                serde_json::json!({
+                    "language": lang.language,
+                    "probability": lang.probability,
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+
     serde_json::to_string_pretty(&serde_json::json!({
-        "language": result.language,
-        "segments": result.segments.iter().map(|seg| {
+        "language": detection.best().map(|lang| lang.language.as_str()),
+        "languages": languages(&detection.languages),
+        "windows": detection.windows.iter().map(|window| {
             serde_json::json!({
-                "id": seg.id,
-                "start": seg.start_time_formatted(),
-                "end": seg.end_time_formatted(),
-                "start_ms": seg.start_ms,
-                "end_ms": seg.end_ms,
-                "text": seg.text,
-                "confidence": seg.confidence,
+                "start_ms": window.start_ms,
+                "duration_ms": window.duration_ms,
+                "languages": languages(&window.languages),
             })
         }).collect::<Vec<_>>()
     }))
@@ -236,134 +605,290 @@ fn format_json(result: &orangenote_cli::TranscriptionResult) -> Result<String> {
 }
 
 #[cfg(feature = "whisper")]
-/// Format transcription result as plain text
-fn format_txt(result: &orangenote_cli::TranscriptionResult) -> String {
-    result
-        .segments
-        .iter()
-        .map(|seg| format!("[{}] {}", seg.start_time_formatted(), seg.text))
-        .collect::<Vec<_>>()
-        .join("\n")
+async fn handle_detect_language(args: DetectLanguageArgs) -> Result<()> {
+    use orangenote_cli::{ModelSize, WhisperModelManager};
+
+    let DetectLanguageArgs {
+        input,
+        model,
+        duration,
+        windows,
+        top,
+        output,
+        threads,
+    } = args;
+
+    validate_input_file(&input).context("Input file validation failed")?;
+    validate_model(&model).context("Model validation failed")?;
+    if duration == 0 || windows == 0 || top == 0 {
+        anyhow::bail!("--duration, --windows and --top must be at least 1");
+    }
+
+    info!("Detecting language: {}", input.display());
+    info!("Model: {}", model);
+    info!("Windows: {} x {}s", windows, duration);
+
+    let model_manager = WhisperModelManager::new().context("Failed to initialize model manager")?;
+    let model_size =
+        ModelSize::from_str(&model).context(format!("Invalid model name: {}", model))?;
+    let transcriber =
+        orangenote_cli::WhisperTranscriber::from_model_manager(&model_manager, model_size, threads)
+            .await
+            .context("Failed to initialize transcriber")?;
+
+    let detection = transcriber
+        .detect_language_file(&input, duration, windows, top)
+        .context("Language detection failed")?;
+
+    let formatted_output = format_language_detection(&detection)?;
+
+    if let Some(output_path) = output {
+        std::fs::write(&output_path, &formatted_output).context("Failed to write output file")?;
+        info!("Output written to: {}", output_path.display());
+    } else {
+        println!("{}", formatted_output);
+    }
+
+    Ok(())
 }
 
-#[cfg(feature = "whisper")]
-/// Format transcription result as SRT (SubRip)
-fn format_srt(result: &orangenote_cli::TranscriptionResult) -> String {
-    result
-        .segments
-        .iter()
-        .map(|seg| {
-            format!(
-                "{}\n{} --> {}\n{}\n",
-                seg.id + 1,
-                format_srt_time(seg.start_ms),
-                format_srt_time(seg.end_ms),
-                seg.text
-            )
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
+#[cfg(not(feature = "whisper"))]
+async fn handle_detect_language(_args: DetectLanguageArgs) -> Result<()> {
+    anyhow::bail!("Whisper feature not enabled. Rebuild with: cargo build --features whisper")
 }
 
-#[cfg(feature = "whisper")]
-/// Format transcription result as VTT (WebVTT)
-fn format_vtt(result: &orangenote_cli::TranscriptionResult) -> String {
-    let mut output = "WEBVTT\n\n".to_string();
-    output.push_str(
-        &result
-            .segments
-            .iter()
-            .map(|seg| {
-                format!(
-                    "{} --> {}\n{}\n",
-                    format_srt_time(seg.start_ms),
-                    format_srt_time(seg.end_ms),
-                    seg.text
-                )
-            })
-            .collect::<Vec<_>>()
-            .join("\n"),
-    );
-    output
+/// Backend selected with `--backend`
+#[cfg(feature = "http")]
+enum Engine {
+    #[cfg(feature = "whisper")]
+    Whisper(orangenote_cli::WhisperTranscriber),
+    Http(orangenote_cli::HttpBackend),
 }
 
+/// Load the whisper model for `--backend whisper`, downloading it if needed
 #[cfg(feature = "whisper")]
-/// Format transcription result as TSV (tab-separated values)
-fn format_tsv(result: &orangenote_cli::TranscriptionResult) -> String {
-    let header = "ID\tStart\tEnd\tStartMS\tEndMS\tConfidence\tText\n";
-    let rows = result
-        .segments
-        .iter()
-        .map(|seg| {
-            format!(
-                "{}\t{}\t{}\t{}\t{}\t{:.3}\t{}",
-                seg.id,
-                seg.start_time_formatted(),
-                seg.end_time_formatted(),
-                seg.start_ms,
-                seg.end_ms,
-                seg.confidence,
-                seg.text
-            )
-        })
-        .collect::<Vec<_>>()
-        .join("\n");
-    format!("{}{}\n", header, rows)
+async fn whisper_engine(
+    model: &str,
+    dtw: bool,
+    threads: usize,
+    options: &mut orangenote_cli::TranscribeOptions,
+    streaming: bool,
+) -> Result<Engine> {
+    use orangenote_cli::{ModelSize, WhisperModelManager};
+
+    // Initialize model manager
+    let model_manager = WhisperModelManager::new().context("Failed to initialize model manager")?;
+
+    if let Some(vad) = options.vad.as_mut() {
+        if vad.model_path.as_os_str().is_empty() {
+            vad.model_path = model_manager
+                .get_or_download_vad()
+                .await
+                .context("Failed to get VAD model")?;
+        }
+        info!("VAD model: {}", vad.model_path.display());
+    }
+
+    status!(streaming, "\n🤖 Initializing transcriber...");
+
+    // Parse model name to ModelSize enum
+    let model_size =
+        ModelSize::from_str(model).context(format!("Invalid model name: {}", model))?;
+    if options.speaker_turns && !model_size.is_tdrz() {
+        anyhow::bail!("--speaker-turns requires a tinydiarize model (use --model small.en-tdrz)");
+    }
+
+    // Create transcriber (will download model if needed)
+    let transcriber = if dtw {
+        orangenote_cli::WhisperTranscriber::from_model_manager_with_dtw(
+            &model_manager,
+            model_size,
+            threads,
+        )
+        .await
+    } else {
+        orangenote_cli::WhisperTranscriber::from_model_manager(&model_manager, model_size, threads)
+            .await
+    }
+    .context("Failed to initialize transcriber")?;
+
+    status!(streaming, "✓ Transcriber ready (model: {})", model);
+
+    Ok(Engine::Whisper(transcriber))
 }
 
-#[cfg(feature = "whisper")]
-/// Format time for SRT/VTT format (HH:MM:SS,mmm)
-fn format_srt_time(ms: i64) -> String {
-    let total_seconds = ms / 1000;
-    let milliseconds = ms % 1000;
-    let seconds = total_seconds % 60;
-    let minutes = (total_seconds / 60) % 60;
-    let hours = total_seconds / 3600;
-
-    format!(
-        "{:02}:{:02}:{:02},{:03}",
-        hours, minutes, seconds, milliseconds
+#[cfg(all(feature = "http", not(feature = "whisper")))]
+async fn whisper_engine(
+    _model: &str,
+    _dtw: bool,
+    _threads: usize,
+    _options: &mut orangenote_cli::TranscribeOptions,
+    _streaming: bool,
+) -> Result<Engine> {
+    anyhow::bail!(
+        "Whisper backend not enabled. Rebuild with: cargo build --features whisper, or use --backend http"
     )
 }
 
-#[cfg(feature = "whisper")]
-#[allow(clippy::too_many_arguments)]
-async fn handle_transcribe(
-    input: PathBuf,
-    model: String,
-    language: Option<String>,
-    format: String,
-    output: Option<PathBuf>,
-    threads: usize,
-    translate: bool,
-    chunk_size: u32,
-    chunk_overlap: u32,
-) -> Result<()> {
+#[cfg(feature = "http")]
+async fn handle_transcribe(args: TranscribeArgs) -> Result<()> {
+    let mut options = args
+        .transcribe_options()
+        .context("Failed to build decoding options")?;
+    let TranscribeArgs {
+        input,
+        model,
+        backend,
+        endpoint,
+        http_model,
+        api_key,
+        format,
+        output,
+        threads,
+        translate,
+        chunk_size,
+        chunk_overlap,
+        parallel_chunks,
+        word_timestamps,
+        dtw,
+        speaker_turns,
+        review_threshold,
+        stream,
+        ..
+    } = args;
+
     validate_input_file(&input).context("Input file validation failed")?;
-    validate_model(&model).context("Model validation failed")?;
+    let use_http = match backend.as_str() {
+        "whisper" => {
+            validate_model(&model).context("Model validation failed")?;
+            false
+        }
+        "http" => {
+            if endpoint.is_none() {
+                anyhow::bail!("--backend http requires --endpoint");
+            }
+            true
+        }
+        other => anyhow::bail!(
+            "Invalid backend: '{}'. Valid backends: whisper, http",
+            other
+        ),
+    };
     validate_format(&format).context("Output format validation failed")?;
+    if let Some(stream_format) = &stream {
+        validate_stream_format(stream_format).context("Stream format validation failed")?;
+    }
+    // Streamed segments own stdout, so status messages go to stderr
+    let streaming = stream.is_some();
     validate_chunk_config(chunk_size, chunk_overlap).context("Chunk config validation failed")?;
+    if parallel_chunks == 0 {
+        anyhow::bail!("--parallel-chunks must be at least 1");
+    }
+    if let Some(threshold) = review_threshold {
+        if !(0.0..=1.0).contains(&threshold) {
+            anyhow::bail!("--review-threshold must be between 0.0 and 1.0");
+        }
+    }
+    if parallel_chunks > 1 && chunk_size == 0 {
+        log::warn!("--parallel-chunks has no effect without --chunk-size");
+    }
+    options
+        .validate()
+        .context("Decoding options validation failed")?;
+    if use_http {
+        let mut ignored = unsupported_options(&options);
+        if dtw {
+            ignored.push("--dtw");
+        }
+        if parallel_chunks > 1 {
+            ignored.push("--parallel-chunks");
+        }
+        if !ignored.is_empty() {
+            log::warn!("Not supported by the http backend: {}", ignored.join(", "));
+        }
+    }
 
     info!("Starting transcription...");
     info!("Input file: {}", input.display());
-    info!("Model: {}", model);
-    if let Some(lang) = &language {
+    if use_http {
+        info!("Backend: http (model: {})", http_model);
+    } else {
+        info!("Model: {}", model);
+    }
+    if let Some(lang) = &options.language {
         info!("Language: {}", lang);
+    } else if options.language_per_chunk {
+        info!("Language: auto-detect per chunk");
     } else {
         info!("Language: auto-detect");
     }
     info!("Output format: {}", format);
     info!("Threads: {}", threads);
     info!("Translate: {}", translate);
+    if dtw {
+        info!(
+            "Token timestamps: DTW alignment (used for words: {})",
+            options.prefer_dtw_timestamps
+        );
+    }
+    match options.beam_size {
+        Some(beam_size) => info!("Sampling: beam search (beam size {})", beam_size),
+        None => info!("Sampling: greedy (best of {})", options.best_of),
+    }
+    info!(
+        "Temperature: {} (fallback increment {})",
+        options.temperature, options.temperature_inc
+    );
+    if let Some(prompt) = &options.initial_prompt {
+        info!(
+            "Initial prompt: {} characters (carry across windows: {})",
+            prompt.chars().count(),
+            options.carry_initial_prompt
+        );
+    }
 
     if chunk_size > 0 {
         info!(
             "Chunking: {} minute chunks with {} second overlap",
             chunk_size, chunk_overlap
         );
+        if parallel_chunks > 1 {
+            info!("Parallel chunks: {}", parallel_chunks);
+        }
+        if options.chunk_context_words > 0 {
+            info!(
+                "Chunk context: last {} words of each chunk",
+                options.chunk_context_words
+            );
+        }
     } else {
         info!("Chunking: disabled");
     }
+    if let Some(regex) = &options.suppress_regex {
+        info!("Suppressed tokens: {}", regex);
+    }
+    if !options.boost_words.is_empty() {
+        info!(
+            "Boosted words: {} (bias {})",
+            options.boost_words.len(),
+            options.boost_weight
+        );
+    }
+    if options.grammar.is_some() {
+        info!(
+            "Grammar: start rule '{}', penalty {}",
+            options.grammar_rule, options.grammar_penalty
+        );
+    }
+    if let Some(vad) = &options.vad {
+        info!(
+            "VAD: threshold {}, min speech {}ms, min silence {}ms, padding {}ms",
+            vad.threshold,
+            vad.min_speech_duration_ms,
+            vad.min_silence_duration_ms,
+            vad.speech_pad_ms
+        );
+    }
 
     // Step A2: Extract audio metadata using AudioDecoder
     let decoder = AudioDecoder::new(&input).context("Failed to create audio decoder")?;
@@ -372,124 +897,283 @@ async fn handle_transcribe(
         .context("Failed to extract audio metadata")?;
 
     // Display audio information
-    println!("\n📄 Audio File Information:");
-    println!("  File: {}", input.display());
-    println!("  Format: {}", metadata.format.as_str());
-    println!("  Size: {}", metadata.file_size_human());
-    println!("  {}", metadata.format_info());
-
-    #[cfg(feature = "whisper")]
-    {
-        use orangenote_cli::{ModelSize, WhisperModelManager};
-
-        // Initialize model manager
-        let model_manager =
-            WhisperModelManager::new().context("Failed to initialize model manager")?;
+    status!(streaming, "\n📄 Audio File Information:");
+    status!(streaming, "  File: {}", input.display());
+    status!(streaming, "  Format: {}", metadata.format.as_str());
+    status!(streaming, "  Size: {}", metadata.file_size_human());
+    status!(streaming, "  {}", metadata.format_info());
+
+    // Set up the selected backend
+    let engine = if use_http {
+        let endpoint = endpoint.unwrap_or_default();
+        let api_key = api_key.or_else(|| std::env::var("OPENAI_API_KEY").ok());
+        // The blocking HTTP client must not be created or used on a runtime thread
+        let backend = tokio::task::block_in_place(|| {
+            orangenote_cli::HttpBackend::new(endpoint)
+                .map(|backend| backend.model(http_model).api_key(api_key))
+        })
+        .context("Failed to initialize HTTP backend")?;
+        status!(
+            streaming,
+            "\n🌐 Transcription server: {}",
+            backend.url(translate)
+        );
+        Engine::Http(backend)
+    } else {
+        whisper_engine(&model, dtw, threads, &mut options, streaming).await?
+    };
 
-        println!("\n🤖 Initializing transcriber...");
+    // Ctrl-C stops transcription early and keeps the completed segments;
+    // a second Ctrl-C exits immediately
+    let cancellation = orangenote_cli::CancellationToken::new();
+    options = options.cancellation(cancellation.clone());
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("\n⚠ Interrupted, finishing with partial results (Ctrl-C again to abort)");
+            cancellation.cancel();
+        }
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
 
-        // Parse model name to ModelSize enum
-        let model_size =
-            ModelSize::from_str(&model).context(format!("Invalid model name: {}", model))?;
+    status!(streaming, "\n🎵 Processing audio...");
 
-        // Create transcriber (will download model if needed)
-        let transcriber = orangenote_cli::WhisperTranscriber::from_model_manager(
-            &model_manager,
-            model_size,
-            threads,
-        )
-        .await
-        .context("Failed to initialize transcriber")?;
+    if let Some(stream_format) = stream {
+        options = options.on_segment(orangenote_cli::SegmentCallback::new(move |segment| {
+            use std::io::Write;
 
-        println!("✓ Transcriber ready (model: {})", model);
+            let mut stdout = std::io::stdout().lock();
+            let _ = writeln!(stdout, "{}", format_stream_segment(segment, &stream_format));
+            let _ = stdout.flush();
+        }));
+    }
 
-        println!("\n🎵 Processing audio...");
+    let progress_bar = transcription_progress_bar(metadata.duration_seconds)?;
+    if let Some(pb) = &progress_bar {
+        let pb = pb.clone();
+        let audio_secs = metadata.duration_seconds;
+        options = options.progress(orangenote_cli::ProgressCallback::new(move |fraction| {
+            pb.set_position((fraction * PROGRESS_BAR_STEPS as f32) as u64);
+            let elapsed = pb.elapsed().as_secs_f64();
+            if elapsed > 0.0 && fraction > 0.0 {
+                let realtime_factor = audio_secs * fraction as f64 / elapsed;
+                pb.set_message(format!("{:.1}x realtime", realtime_factor));
+            }
+        }));
+    }
 
-        // Transcribe - with or without chunking
-        let result = if chunk_size > 0 {
-            use orangenote_cli::ChunkConfig;
+    // Transcribe - with or without chunking
+    let chunk_config = (chunk_size > 0).then(|| orangenote_cli::ChunkConfig {
+        chunk_duration_secs: chunk_size * 60,
+        overlap_secs: chunk_overlap,
+    });
+    if chunk_config.is_some() {
+        status!(
+            streaming,
+            "  📦 Using chunked transcription ({} min chunks, {}s overlap)",
+            chunk_size,
+            chunk_overlap
+        );
+    }
 
-            let config = ChunkConfig {
-                chunk_duration_secs: chunk_size * 60,
-                overlap_secs: chunk_overlap,
-            };
+    let on_chunk = |current: usize, total: usize| match &progress_bar {
+        Some(pb) => pb.set_prefix(format!("chunk {}/{}", current + 1, total)),
+        None => status!(streaming, "  Processing chunk {}/{}...", current + 1, total),
+    };
 
-            println!(
-                "  📦 Using chunked transcription ({} min chunks, {}s overlap)",
-                chunk_size, chunk_overlap
+    let result = match (&engine, &chunk_config) {
+        #[cfg(feature = "whisper")]
+        (Engine::Whisper(transcriber), Some(config)) if parallel_chunks > 1 => {
+            status!(
+                streaming,
+                "  ⚡ Transcribing up to {} chunks in parallel",
+                parallel_chunks
             );
-
             transcriber
-                .transcribe_file_chunked(
+                .transcribe_file_chunked_parallel(
                     &input,
-                    language.as_deref(),
-                    translate,
-                    &config,
-                    |current, total| {
-                        println!("  Processing chunk {}/{}...", current + 1, total);
-                    },
+                    &options,
+                    config,
+                    parallel_chunks,
+                    on_chunk,
                 )
-                .context("Chunked transcription failed")?
-        } else {
-            transcriber
-                .transcribe_file(&input, language.as_deref(), translate)
-                .context("Transcription failed")?
-        };
+                .context("Parallel chunked transcription failed")?
+        }
+        #[cfg(feature = "whisper")]
+        (Engine::Whisper(transcriber), Some(config)) => transcriber
+            .transcribe_file_chunked(&input, &options, config, on_chunk)
+            .context("Chunked transcription failed")?,
+        #[cfg(feature = "whisper")]
+        (Engine::Whisper(transcriber), None) => transcriber
+            .transcribe_file(&input, &options)
+            .context("Transcription failed")?,
+        (Engine::Http(backend), _) => tokio::task::block_in_place(|| {
+            let audio = orangenote_cli::AudioProcessor::process(&input)
+                .context("Failed to process audio file")?;
+            match &chunk_config {
+                Some(config) => {
+                    pipeline::transcribe_chunked(backend, &audio, &options, config, on_chunk)
+                        .context("Chunked transcription failed")
+                }
+                None => pipeline::transcribe_samples(backend, &audio.samples, &options)
+                    .context("Transcription failed"),
+            }
+        })?,
+    };
+
+    if let Some(pb) = &progress_bar {
+        pb.finish_and_clear();
+    }
 
-        println!("✓ Transcription complete!");
-        println!("  Detected language: {}", result.language);
-        println!("  Segments: {}", result.segments.len());
-        println!(
-            "  Average confidence: {:.2}%",
-            result.average_confidence() * 100.0
+    if result.partial {
+        status!(streaming, "⚠ Transcription cancelled, output is partial");
+    } else {
+        status!(streaming, "✓ Transcription complete!");
+    }
+    status!(streaming, "  Detected language: {}", result.language);
+    status!(streaming, "  Segments: {}", result.segments.len());
+    status!(
+        streaming,
+        "  Average confidence: {:.2}% (avg logprob {:.3}, lowest token probability {:.3})",
+        result.average_confidence() * 100.0,
+        result.average_logprob(),
+        result.min_token_prob()
+    );
+    if !result.quality_retries.is_empty() {
+        let resolved = result
+            .quality_retries
+            .iter()
+            .filter(|retry| retry.resolved)
+            .count();
+        status!(
+            streaming,
+            "  Re-decoded chunks: {} ({} resolved)",
+            result.quality_retries.len(),
+            resolved
         );
+    }
+    if let Some(threshold) = review_threshold {
+        status!(
+            streaming,
+            "  Needs review: {} segments",
+            review_items(&result, threshold).len()
+        );
+    }
 
+    // Format the output
+    let format_options = orangenote_cli::FormatOptions::new()
+        .word_timestamps(word_timestamps)
+        .speaker_turns(speaker_turns)
+        .review_threshold(review_threshold);
+    let formatted_output = format_result(&result, &format, &format_options)
+        .with_context(|| format!("Failed to format {} output", format))?;
+
+    // Write output
+    if let Some(output_path) = output {
+        std::fs::write(&output_path, &formatted_output).context("Failed to write output file")?;
+        status!(streaming, "✓ Output written to: {}", output_path.display());
+    } else if !streaming {
         println!("\n📝 Transcription Results:\n");
+        println!("{}", formatted_output);
+    }
 
-        // Format the output
-        let formatted_output = match format.as_str() {
-            "json" => format_json(&result).context("Failed to format JSON")?,
-            "txt" => format_txt(&result),
-            "srt" => format_srt(&result),
-            "vtt" => format_vtt(&result),
-            "tsv" => format_tsv(&result),
-            _ => unreachable!(),
-        };
+    if !result.partial {
+        status!(streaming, "\n✓ Transcription complete!\n");
+    }
 
-        // Write output
-        if let Some(output_path) = output {
-            std::fs::write(&output_path, &formatted_output)
-                .context("Failed to write output file")?;
-            println!("✓ Output written to: {}", output_path.display());
-        } else {
-            println!("{}", formatted_output);
-        }
+    Ok(())
+}
+
+#[cfg(not(feature = "http"))]
+async fn handle_transcribe(_args: TranscribeArgs) -> Result<()> {
+    anyhow::bail!(
+        "Transcription not enabled. Rebuild with: cargo build --features whisper (or --features http)"
+    )
+}
+
+#[cfg(feature = "whisper")]
+async fn handle_serve(args: ServeArgs) -> Result<()> {
+    use orangenote_cli::infrastructure::server;
+    use orangenote_cli::{ModelSize, ServerConfig, WhisperModelManager};
+    use std::sync::Arc;
+
+    let ServeArgs {
+        model,
+        host,
+        port,
+        threads,
+        max_concurrent,
+        max_upload_mb,
+        chunk_size,
+        chunk_overlap,
+    } = args;
 
-        println!("\n✓ Transcription complete!\n");
+    validate_model(&model).context("Model validation failed")?;
+    validate_chunk_config(chunk_size, chunk_overlap).context("Chunk configuration invalid")?;
+    let addr: std::net::SocketAddr = format!("{}:{}", host, port)
+        .parse()
+        .with_context(|| format!("Invalid listen address: {}:{}", host, port))?;
+
+    let mut config = ServerConfig::new(model.clone())
+        .max_concurrent(max_concurrent)
+        .max_upload_bytes(max_upload_mb.saturating_mul(1024 * 1024))
+        .options(orangenote_cli::TranscribeOptions::new().threads(threads));
+    if chunk_size > 0 {
+        config = config.chunk_config(orangenote_cli::ChunkConfig::from_minutes(
+            chunk_size,
+            chunk_overlap,
+        ));
     }
+    config.validate().context("Invalid server configuration")?;
 
-    #[cfg(not(feature = "whisper"))]
-    {
-        println!("\n❌ Error: Whisper feature not enabled!");
-        println!("Please rebuild with: cargo build --features whisper");
-        anyhow::bail!("Whisper feature not enabled");
+    let model_manager = WhisperModelManager::new().context("Failed to initialize model manager")?;
+    let model_size =
+        ModelSize::from_str(&model).context(format!("Invalid model name: {}", model))?;
+    let mut transcriber =
+        orangenote_cli::WhisperTranscriber::from_model_manager(&model_manager, model_size, threads)
+            .await
+            .context("Failed to initialize transcriber")?;
+    if max_concurrent > 1 {
+        transcriber = transcriber.with_state_pool(max_concurrent);
     }
 
-    Ok(())
+    println!("✓ Model loaded: {}", model);
+    println!("🌐 Serving on http://{} (Ctrl-C to stop)", addr);
+    println!("  POST /v1/audio/transcriptions");
+    println!("  POST /v1/audio/translations");
+    println!("  GET  /health");
+
+    server::serve(Arc::new(transcriber), config, addr).await
 }
 
 #[cfg(not(feature = "whisper"))]
-async fn handle_transcribe(
-    _input: PathBuf,
-    _model: String,
-    _language: Option<String>,
-    _format: String,
-    _output: Option<PathBuf>,
-    _threads: usize,
-    _translate: bool,
-    _chunk_size: u32,
-    _chunk_overlap: u32,
-) -> Result<()> {
+async fn handle_serve(_args: ServeArgs) -> Result<()> {
+    anyhow::bail!("Whisper feature not enabled. Rebuild with: cargo build --features whisper")
+}
+
+#[cfg(feature = "whisper")]
+async fn handle_rpc(args: RpcArgs) -> Result<()> {
+    use orangenote_cli::infrastructure::rpc::{self, WhisperModelStore};
+    use orangenote_cli::WhisperModelManager;
+    use std::sync::Arc;
+
+    // stdout carries the protocol; everything else goes to stderr
+    let model_manager = WhisperModelManager::new().context("Failed to initialize model manager")?;
+    let store = Arc::new(WhisperModelStore::new(
+        model_manager,
+        tokio::runtime::Handle::current(),
+        args.threads,
+    ));
+    info!("Serving JSON-RPC on stdin/stdout");
+
+    tokio::task::spawn_blocking(move || rpc::run(store, std::io::stdin().lock(), std::io::stdout()))
+        .await
+        .context("RPC server failed")?
+}
+
+#[cfg(not(feature = "whisper"))]
+async fn handle_rpc(_args: RpcArgs) -> Result<()> {
     anyhow::bail!("Whisper feature not enabled. Rebuild with: cargo build --features whisper")
 }
 
@@ -565,7 +1249,7 @@ async fn handle_model_download(model: String, _force: bool) -> Result<()> {
     #[cfg(not(feature = "whisper"))]
     {
         let _ = _force; // Suppress unused warning
-        return Err(anyhow::anyhow!("Whisper feature not enabled"));
+        Err(anyhow::anyhow!("Whisper feature not enabled"))
     }
 
     #[cfg(feature = "whisper")]
@@ -617,6 +1301,15 @@ async fn handle_model_status() -> Result<()> {
     println!("  Cache directory: {}", model_manager.cache_dir().display());
     println!("  Downloaded models: {}", cached.len());
 
+    println!(
+        "  VAD model: {}",
+        if model_manager.is_vad_cached() {
+            "downloaded"
+        } else {
+            "not downloaded"
+        }
+    );
+
     if !cached.is_empty() {
         let total_size = model_manager.get_cache_size().unwrap_or(0);
         println!("  Total size: {:.2} MB", total_size as f64);
@@ -656,29 +1349,17 @@ async fn main() -> Result<()> {
     info!("OrangeNote CLI started");
 
     match cli.command {
-        Some(Commands::Transcribe {
-            input,
-            model,
-            language,
-            format,
-            output,
-            threads,
-            translate,
-            chunk_size,
-            chunk_overlap,
-        }) => {
-            handle_transcribe(
-                input,
-                model,
-                language,
-                format,
-                output,
-                threads,
-                translate,
-                chunk_size,
-                chunk_overlap,
-            )
-            .await?;
+        Some(Commands::Transcribe(args)) => {
+            handle_transcribe(*args).await?;
+        }
+        Some(Commands::DetectLanguage(args)) => {
+            handle_detect_language(args).await?;
+        }
+        Some(Commands::Serve(args)) => {
+            handle_serve(args).await?;
+        }
+        Some(Commands::Rpc(args)) => {
+            handle_rpc(args).await?;
         }
         Some(Commands::Model(ModelCommands::List)) => {
             handle_model_list().await?;
@@ -10,7 +10,7 @@ use std::path::Path;
 use symphonia::core::audio::Signal;
 use symphonia::core::codecs::DecoderOptions;
 use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::MediaSourceStream;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
 use symphonia::core::probe::Hint;
 
 use super::chunk::{AudioChunk, ChunkConfig};
@@ -173,11 +173,31 @@ impl AudioProcessor {
 
         let file = std::fs::File::open(path).context("Failed to open audio file")?;
 
-        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+        Self::decode(Box::new(file), path.extension().and_then(|e| e.to_str()))
+    }
+
+    /// Process an in-memory audio file and return PCM samples at 16kHz mono
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Contents of an audio file in any supported format
+    /// * `extension` - File extension used as format hint, if known
+    ///
+    /// # Returns
+    ///
+    /// `AudioSamples` containing normalized PCM samples at 16kHz mono
+    pub fn process_bytes(data: Vec<u8>, extension: Option<&str>) -> Result<AudioSamples> {
+        info!("Processing {} bytes of audio", data.len());
+        Self::decode(Box::new(std::io::Cursor::new(data)), extension)
+    }
+
+    /// Decode an audio source to PCM samples at 16kHz mono
+    fn decode(source: Box<dyn MediaSource>, extension: Option<&str>) -> Result<AudioSamples> {
+        let mss = MediaSourceStream::new(source, Default::default());
 
         // Create a probe to detect the format
         let mut hint = Hint::new();
-        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        if let Some(ext) = extension {
             hint.with_extension(ext);
         }
 
@@ -379,6 +399,37 @@ impl AudioProcessor {
         })
     }
 
+    /// Encode 16kHz mono PCM samples as a 16-bit WAV file
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - PCM samples at 16kHz, f32 normalized to [-1.0, 1.0]
+    ///
+    /// # Returns
+    ///
+    /// The complete WAV file contents
+    pub fn encode_wav(samples: &[f32]) -> Result<Vec<u8>> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: WHISPER_SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+
+        let mut wav = std::io::Cursor::new(Vec::with_capacity(44 + samples.len() * 2));
+        let mut writer =
+            hound::WavWriter::new(&mut wav, spec).context("Failed to create WAV writer")?;
+        for &sample in samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            writer
+                .write_sample(value)
+                .context("Failed to write WAV sample")?;
+        }
+        writer.finalize().context("Failed to finalize WAV file")?;
+
+        Ok(wav.into_inner())
+    }
+
     /// Extract f32 samples from buffer as interleaved multi-channel data
     fn extract_f32_samples(
         buf: &symphonia::core::audio::AudioBuffer<f32>,
@@ -497,7 +548,7 @@ mod tests {
 
     #[test]
     fn test_s16_conversion() {
-        let converted: Vec<f32> = vec![0i16, 16384, -16384, 32767, -32768]
+        let converted: Vec<f32> = [0i16, 16384, -16384, 32767, -32768]
             .iter()
             .map(|&s| s as f32 / 32767.0)
             .collect();
@@ -506,6 +557,35 @@ mod tests {
         assert!((converted[3] - 1.0).abs() < 0.0001);
     }
 
+    #[test]
+    fn test_encode_wav_round_trip() {
+        let samples = vec![0.0, 0.5, -0.5, 1.0, -1.5];
+        let wav = AudioProcessor::encode_wav(&samples).unwrap();
+        assert_eq!(&wav[..4], b"RIFF");
+
+        let mut reader = hound::WavReader::new(std::io::Cursor::new(wav)).unwrap();
+        assert_eq!(reader.spec().sample_rate, WHISPER_SAMPLE_RATE);
+        assert_eq!(reader.spec().channels, 1);
+        let decoded: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap()).collect();
+        assert_eq!(decoded, vec![0, 16383, -16383, 32767, -32767]);
+    }
+
+    #[test]
+    fn test_process_bytes_decodes_wav() {
+        let samples: Vec<f32> = (0..WHISPER_SAMPLE_RATE)
+            .map(|i| (i as f32 * 0.05).sin() * 0.5)
+            .collect();
+        let wav = AudioProcessor::encode_wav(&samples).unwrap();
+
+        let decoded = AudioProcessor::process_bytes(wav, Some("wav")).unwrap();
+        assert_eq!(decoded.len(), samples.len());
+        assert_eq!(decoded.original_sample_rate, WHISPER_SAMPLE_RATE);
+        assert!((decoded.duration_seconds - 1.0).abs() < 0.01);
+        assert!((decoded.samples[100] - samples[100]).abs() < 0.001);
+
+        assert!(AudioProcessor::process_bytes(b"not audio".to_vec(), None).is_err());
+    }
+
     #[test]
     fn test_split_into_chunks_single_chunk() {
         // Audio shorter than chunk size -> single chunk
@@ -697,8 +777,8 @@ mod tests {
     fn test_chunk_samples_content() {
         // Create samples with distinct pattern
         let mut data = vec![0.0; 16000 * 10];
-        for i in 0..data.len() {
-            data[i] = (i as f32) / 1000.0;
+        for (i, sample) in data.iter_mut().enumerate() {
+            *sample = (i as f32) / 1000.0;
         }
 
         let samples = AudioSamples {
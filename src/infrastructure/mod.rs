@@ -5,11 +5,27 @@
 
 pub mod audio;
 
-#[cfg(feature = "whisper")]
+pub mod rpc;
+
+#[cfg(feature = "serve")]
+pub mod server;
+
 pub mod transcription;
 
+pub use transcription::{
+    CancellationToken, FormatOptions, Grammar, MockBackend, ProgressCallback, QualityConfig,
+    ReviewItem, Segment, SegmentCallback, Token, TranscribeOptions, TranscriptionBackend,
+    TranscriptionResult, VadOptions, Word,
+};
+
+#[cfg(feature = "http")]
+pub use transcription::HttpBackend;
+
+#[cfg(feature = "serve")]
+pub use server::ServerConfig;
+
 #[cfg(feature = "whisper")]
 pub use transcription::{
-    ModelSize, ModelSource, Segment, Token, TranscriptionResult, WhisperContextWrapper,
-    WhisperModelManager, WhisperTranscriber,
+    LanguageDetection, LanguageProbability, ModelSize, ModelSource, PooledState,
+    WhisperContextWrapper, WhisperModelManager, WhisperState, WhisperStatePool, WhisperTranscriber,
 };
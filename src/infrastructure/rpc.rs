@@ -0,0 +1,835 @@
+//! JSON-RPC 2.0 interface over stdin/stdout
+//!
+//! Machine-readable front-end for programs driving orangenote, such as the desktop
+//! UI: one JSON-RPC message per line in both directions. Long-running methods
+//! (`transcribe`, `model.download`) run on their own threads, so `cancel` and other
+//! requests are answered while they are busy. A transcription reports
+//! `transcribe.progress` and `transcribe.segment` notifications carrying the id of
+//! its request.
+//!
+//! Methods:
+//!
+//! | Method | Params | Result |
+//! |--------|--------|--------|
+//! | `probe` | `path` | Audio metadata |
+//! | `transcribe` | `path`, `model`, `language`, `translate`, `prompt`, `threads`, `chunk_size`, `chunk_overlap`, `word_timestamps`, `format` | JSON transcription |
+//! | `cancel` | `id` of a running request | `{"cancelled": bool}` |
+//! | `model.list` | - | Known models |
+//! | `model.download` | `model`, `force` | Downloaded model |
+//! | `model.remove` | `model` | `{"removed": model}` |
+
+use crate::infrastructure::audio::{AudioDecoder, AudioProcessor, ChunkConfig};
+use crate::infrastructure::transcription::format::{format_json, format_result, validate_format};
+use crate::infrastructure::transcription::{
+    pipeline, CancellationToken, FormatOptions, ProgressCallback, SegmentCallback,
+    TranscribeOptions, TranscriptionBackend,
+};
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// Invalid JSON was received
+pub const PARSE_ERROR: i64 = -32700;
+/// The message is not a valid request object
+pub const INVALID_REQUEST: i64 = -32600;
+/// The method does not exist
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// Invalid method parameters
+pub const INVALID_PARAMS: i64 = -32602;
+/// The method failed (missing file, decode error, download error, ...)
+pub const OPERATION_FAILED: i64 = -32000;
+
+/// Model used by `transcribe` unless the request names another one
+pub const DEFAULT_RPC_MODEL: &str = "base";
+
+/// A model known to a `ModelStore`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelInfo {
+    /// Model name, as accepted by the `model` params
+    pub name: String,
+    /// Approximate download size in MB
+    pub size_mb: u32,
+    /// Whether the model is in the local cache
+    pub downloaded: bool,
+    /// Path of the cached model file
+    pub path: Option<PathBuf>,
+}
+
+/// Models served over RPC
+///
+/// Methods are called from worker threads and may block.
+pub trait ModelStore: Send + Sync {
+    /// List the known models
+    fn list(&self) -> Result<Vec<ModelInfo>>;
+
+    /// Download a model unless it is cached (or always, with `force`)
+    fn download(&self, name: &str, force: bool) -> Result<ModelInfo>;
+
+    /// Remove a model from the cache
+    fn remove(&self, name: &str) -> Result<()>;
+
+    /// Get a backend transcribing with a model, downloading and loading it if needed
+    fn load(&self, name: &str) -> Result<Arc<dyn TranscriptionBackend>>;
+}
+
+/// Error returned to the client
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn failed(err: anyhow::Error) -> Self {
+        Self::new(OPERATION_FAILED, format!("{:#}", err))
+    }
+}
+
+/// Line-delimited writer shared by the request threads
+#[derive(Clone)]
+struct Output(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl Output {
+    fn send(&self, message: &Value) {
+        let mut writer = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let written = writeln!(writer, "{}", message).and_then(|_| writer.flush());
+        if let Err(e) = written {
+            warn!("Failed to write RPC message: {}", e);
+        }
+    }
+
+    fn respond(&self, id: &Value, result: Result<Value, RpcError>) {
+        let message = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(err) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": err.code, "message": err.message },
+            }),
+        };
+        self.send(&message);
+    }
+
+    fn notify(&self, method: &str, params: Value) {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+}
+
+/// Request as received; validated in `Server::handle_line`
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: Option<String>,
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// State of a running server
+struct Server {
+    store: Arc<dyn ModelStore>,
+    output: Output,
+    /// Cancellation tokens of running transcriptions, by request id
+    jobs: Arc<Mutex<HashMap<String, CancellationToken>>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+/// Serve JSON-RPC requests until the input is closed
+///
+/// When the input ends, running transcriptions are cancelled and their (partial)
+/// results are written before returning.
+///
+/// # Arguments
+///
+/// * `store` - Models used by `transcribe` and the `model.*` methods
+/// * `input` - Requests, one per line (usually stdin)
+/// * `output` - Responses and notifications, one per line (usually stdout)
+///
+/// # Returns
+///
+/// Ok when the input is closed, or an error if reading the input fails
+pub fn run<R, W>(store: Arc<dyn ModelStore>, input: R, output: W) -> Result<()>
+where
+    R: BufRead,
+    W: Write + Send + 'static,
+{
+    let mut server = Server {
+        store,
+        output: Output(Arc::new(Mutex::new(Box::new(output)))),
+        jobs: Arc::new(Mutex::new(HashMap::new())),
+        workers: Vec::new(),
+    };
+
+    let mut read_result = Ok(());
+    for line in input.lines() {
+        match line {
+            Ok(line) if line.trim().is_empty() => {}
+            Ok(line) => server.handle_line(&line),
+            Err(e) => {
+                read_result = Err(e).context("Failed to read RPC input");
+                break;
+            }
+        }
+    }
+
+    for token in server.lock_jobs().values() {
+        token.cancel();
+    }
+    for worker in server.workers.drain(..) {
+        let _ = worker.join();
+    }
+    read_result
+}
+
+impl Server {
+    fn lock_jobs(&self) -> std::sync::MutexGuard<'_, HashMap<String, CancellationToken>> {
+        self.jobs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn handle_line(&mut self, line: &str) {
+        let value: Value = match serde_json::from_str(line) {
+            Ok(value) => value,
+            Err(e) => {
+                let err = RpcError::new(PARSE_ERROR, format!("Parse error: {}", e));
+                return self.output.respond(&Value::Null, Err(err));
+            }
+        };
+        if value.is_array() {
+            let err = RpcError::new(INVALID_REQUEST, "Batch requests are not supported");
+            return self.output.respond(&Value::Null, Err(err));
+        }
+
+        let id = value.get("id").cloned();
+        let request = match serde_json::from_value::<Request>(value) {
+            Ok(request) if request.jsonrpc.as_deref() == Some("2.0") => request,
+            _ => {
+                let err = RpcError::new(INVALID_REQUEST, "Invalid JSON-RPC 2.0 request");
+                return self.output.respond(&id.unwrap_or(Value::Null), Err(err));
+            }
+        };
+        debug!("RPC request: {} (id {:?})", request.method, request.id);
+
+        // Notifications get no response; methods reporting progress need an id
+        let Some(id) = request.id else {
+            match request.method.as_str() {
+                "transcribe" | "model.download" => {
+                    warn!("Ignoring {} notification without id", request.method)
+                }
+                _ => {
+                    let _ = self.call(&request.method, request.params);
+                }
+            }
+            return;
+        };
+
+        match request.method.as_str() {
+            "transcribe" => self.spawn_transcribe(id, request.params),
+            "model.download" => self.spawn_download(id, request.params),
+            method => {
+                let result = self.call(method, request.params);
+                self.output.respond(&id, result);
+            }
+        }
+    }
+
+    /// Run a method that answers right away
+    fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "probe" => {
+                let params: PathParams = parse_params(params)?;
+                probe(&params.path)
+            }
+            "cancel" => {
+                let params: CancelParams = parse_params(params)?;
+                let token = self.lock_jobs().get(&params.id.to_string()).cloned();
+                if let Some(token) = &token {
+                    token.cancel();
+                }
+                Ok(json!({ "cancelled": token.is_some() }))
+            }
+            "model.list" => self
+                .store
+                .list()
+                .map(|models| json!(models))
+                .map_err(RpcError::failed),
+            "model.remove" => {
+                let params: ModelParams = parse_params(params)?;
+                self.store
+                    .remove(&params.model)
+                    .map(|_| json!({ "removed": params.model }))
+                    .map_err(RpcError::failed)
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Method not found: {}", method),
+            )),
+        }
+    }
+
+    fn spawn_transcribe(&mut self, id: Value, params: Value) {
+        let params: TranscribeParams = match parse_params(params) {
+            Ok(params) => params,
+            Err(err) => return self.output.respond(&id, Err(err)),
+        };
+        let key = id.to_string();
+        let cancellation = CancellationToken::new();
+        {
+            let mut jobs = self.lock_jobs();
+            if jobs.contains_key(&key) {
+                let err = RpcError::new(
+                    INVALID_REQUEST,
+                    format!("Request {} is already running", id),
+                );
+                drop(jobs);
+                return self.output.respond(&id, Err(err));
+            }
+            jobs.insert(key.clone(), cancellation.clone());
+        }
+
+        let store = Arc::clone(&self.store);
+        let output = self.output.clone();
+        let jobs = Arc::clone(&self.jobs);
+        self.workers.push(std::thread::spawn(move || {
+            let result = transcribe(store.as_ref(), &output, &id, params, cancellation);
+            jobs.lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .remove(&key);
+            output.respond(&id, result);
+        }));
+    }
+
+    fn spawn_download(&mut self, id: Value, params: Value) {
+        let params: DownloadParams = match parse_params(params) {
+            Ok(params) => params,
+            Err(err) => return self.output.respond(&id, Err(err)),
+        };
+        let store = Arc::clone(&self.store);
+        let output = self.output.clone();
+        self.workers.push(std::thread::spawn(move || {
+            let result = store
+                .download(&params.model, params.force)
+                .map(|model| json!(model))
+                .map_err(RpcError::failed);
+            output.respond(&id, result);
+        }));
+    }
+}
+
+#[derive(Deserialize)]
+struct PathParams {
+    path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct CancelParams {
+    id: Value,
+}
+
+#[derive(Deserialize)]
+struct ModelParams {
+    model: String,
+}
+
+#[derive(Deserialize)]
+struct DownloadParams {
+    model: String,
+    #[serde(default)]
+    force: bool,
+}
+
+#[derive(Deserialize)]
+struct TranscribeParams {
+    path: PathBuf,
+    #[serde(default = "default_model")]
+    model: String,
+    language: Option<String>,
+    #[serde(default)]
+    translate: bool,
+    prompt: Option<String>,
+    threads: Option<usize>,
+    /// Chunk size in minutes (0 = no chunking)
+    #[serde(default)]
+    chunk_size: u32,
+    #[serde(default = "default_chunk_overlap")]
+    chunk_overlap: u32,
+    #[serde(default)]
+    word_timestamps: bool,
+    /// Also render the result in this output format
+    format: Option<String>,
+}
+
+fn default_model() -> String {
+    DEFAULT_RPC_MODEL.to_string()
+}
+
+fn default_chunk_overlap() -> u32 {
+    5
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    // Methods without required params also accept omitted params
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid params: {}", e)))
+}
+
+fn probe(path: &std::path::Path) -> Result<Value, RpcError> {
+    let metadata = AudioDecoder::new(path)
+        .and_then(|decoder| decoder.get_metadata())
+        .map_err(RpcError::failed)?;
+    Ok(json!({
+        "path": metadata.path,
+        "format": metadata.format.as_str(),
+        "duration_seconds": metadata.duration_seconds,
+        "sample_rate": metadata.sample_rate,
+        "channels": metadata.channels,
+        "bitrate_kbps": metadata.bitrate_kbps,
+        "file_size_bytes": metadata.file_size_bytes,
+    }))
+}
+
+fn transcribe(
+    store: &dyn ModelStore,
+    output: &Output,
+    id: &Value,
+    params: TranscribeParams,
+    cancellation: CancellationToken,
+) -> Result<Value, RpcError> {
+    if let Some(format) = &params.format {
+        validate_format(format).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+    }
+
+    let mut options = TranscribeOptions::new()
+        .translate(params.translate)
+        .cancellation(cancellation);
+    if let Some(language) = &params.language {
+        options = options.language(language.as_str());
+    }
+    if let Some(prompt) = &params.prompt {
+        options = options.initial_prompt(prompt.as_str());
+    }
+    if let Some(threads) = params.threads {
+        options = options.threads(threads);
+    }
+    options
+        .validate()
+        .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+
+    let notify_output = output.clone();
+    let notify_id = id.clone();
+    options = options.progress(ProgressCallback::new(move |fraction| {
+        notify_output.notify(
+            "transcribe.progress",
+            json!({ "id": notify_id, "progress": fraction }),
+        );
+    }));
+    let notify_output = output.clone();
+    let notify_id = id.clone();
+    options = options.on_segment(SegmentCallback::new(move |segment| {
+        notify_output.notify(
+            "transcribe.segment",
+            json!({
+                "id": notify_id,
+                "segment": {
+                    "id": segment.id,
+                    "start_ms": segment.start_ms,
+                    "end_ms": segment.end_ms,
+                    "text": segment.text,
+                    "confidence": segment.confidence,
+                    "language": segment.language,
+                },
+            }),
+        );
+    }));
+
+    let audio = AudioProcessor::process(&params.path).map_err(RpcError::failed)?;
+    let backend = store.load(&params.model).map_err(RpcError::failed)?;
+    let result = if params.chunk_size > 0 {
+        let chunk_config = ChunkConfig::from_minutes(params.chunk_size, params.chunk_overlap);
+        pipeline::transcribe_chunked(backend.as_ref(), &audio, &options, &chunk_config, |_, _| {})
+    } else {
+        pipeline::transcribe_samples(backend.as_ref(), &audio.samples, &options)
+    }
+    .map_err(RpcError::failed)?;
+
+    let json =
+        format_json(&result, params.word_timestamps, false, None).map_err(RpcError::failed)?;
+    let mut response: Value =
+        serde_json::from_str(&json).map_err(|e| RpcError::new(OPERATION_FAILED, e.to_string()))?;
+    if let Some(format) = &params.format {
+        let format_options = FormatOptions::new().word_timestamps(params.word_timestamps);
+        response["output"] = format_result(&result, format, &format_options)
+            .map_err(RpcError::failed)?
+            .into();
+    }
+    Ok(response)
+}
+
+/// Model size and transcriber kept loaded by `WhisperModelStore`
+#[cfg(feature = "whisper")]
+type LoadedModel = (
+    crate::infrastructure::transcription::ModelSize,
+    Arc<crate::infrastructure::transcription::WhisperTranscriber>,
+);
+
+/// Model store backed by the whisper model cache
+///
+/// Keeps the most recently used model loaded, so consecutive transcriptions with
+/// the same model do not reload it.
+#[cfg(feature = "whisper")]
+pub struct WhisperModelStore {
+    manager: crate::infrastructure::transcription::WhisperModelManager,
+    runtime: tokio::runtime::Handle,
+    threads: usize,
+    /// Held while a model downloads and loads, so only one model loads at a time.
+    /// `loaded` is only locked briefly, so `remove` never waits for a download
+    loading: Mutex<()>,
+    loaded: Mutex<Option<LoadedModel>>,
+}
+
+#[cfg(feature = "whisper")]
+impl WhisperModelStore {
+    /// Create a store
+    ///
+    /// # Arguments
+    ///
+    /// * `manager` - Model cache to list, download and load models from
+    /// * `runtime` - Runtime running downloads; store methods must not be called on it
+    /// * `threads` - Default number of threads of loaded models
+    pub fn new(
+        manager: crate::infrastructure::transcription::WhisperModelManager,
+        runtime: tokio::runtime::Handle,
+        threads: usize,
+    ) -> Self {
+        WhisperModelStore {
+            manager,
+            runtime,
+            threads,
+            loading: Mutex::new(()),
+            loaded: Mutex::new(None),
+        }
+    }
+
+    fn lock_loaded(&self) -> std::sync::MutexGuard<'_, Option<LoadedModel>> {
+        self.loaded
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn info(&self, model: crate::infrastructure::transcription::ModelSize) -> ModelInfo {
+        let downloaded = self.manager.is_cached(model);
+        ModelInfo {
+            name: model.display_name().to_string(),
+            size_mb: model.size_mb(),
+            downloaded,
+            path: downloaded.then(|| self.manager.get_model_path(model)),
+        }
+    }
+}
+
+#[cfg(feature = "whisper")]
+impl ModelStore for WhisperModelStore {
+    fn list(&self) -> Result<Vec<ModelInfo>> {
+        Ok(
+            crate::infrastructure::transcription::WhisperModelManager::list_available_models()
+                .into_iter()
+                .map(|(model, _)| self.info(model))
+                .collect(),
+        )
+    }
+
+    fn download(&self, name: &str, force: bool) -> Result<ModelInfo> {
+        let model = crate::infrastructure::transcription::ModelSize::from_str(name)?;
+        if force || !self.manager.is_cached(model) {
+            self.runtime
+                .block_on(self.manager.download_model(model))
+                .context("Failed to download model")?;
+        }
+        Ok(self.info(model))
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        let model = crate::infrastructure::transcription::ModelSize::from_str(name)?;
+        let mut loaded = self.lock_loaded();
+        if loaded.as_ref().is_some_and(|(size, _)| *size == model) {
+            *loaded = None;
+        }
+        self.manager.remove_model(model)
+    }
+
+    fn load(&self, name: &str) -> Result<Arc<dyn TranscriptionBackend>> {
+        let model = crate::infrastructure::transcription::ModelSize::from_str(name)?;
+        let _loading = self
+            .loading
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        // Checked after waiting, since the previous load may have been the same model
+        {
+            let mut loaded = self.lock_loaded();
+            if let Some((size, transcriber)) = loaded.as_ref() {
+                if *size == model {
+                    return Ok(transcriber.clone());
+                }
+            }
+
+            // Release the previous model before loading the next one
+            *loaded = None;
+        }
+
+        let transcriber = Arc::new(
+            self.runtime
+                .block_on(
+                    crate::infrastructure::transcription::WhisperTranscriber::from_model_manager(
+                        &self.manager,
+                        model,
+                        self.threads,
+                    ),
+                )
+                .context("Failed to initialize transcriber")?,
+        );
+        *self.lock_loaded() = Some((model, transcriber.clone()));
+        Ok(transcriber)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::transcription::{Segment, TranscriptionResult};
+
+    /// Backend reporting progress and segments like whisper does, and waiting for
+    /// cancellation when asked to transcribe in Klingon
+    struct TestBackend;
+
+    impl TranscriptionBackend for TestBackend {
+        fn transcribe(
+            &self,
+            _samples: &[f32],
+            options: &TranscribeOptions,
+        ) -> Result<TranscriptionResult> {
+            if options.language.as_deref() == Some("tlh") {
+                while !options.is_cancelled() {
+                    std::thread::sleep(std::time::Duration::from_millis(5));
+                }
+                return Ok(TranscriptionResult {
+                    partial: true,
+                    ..Default::default()
+                });
+            }
+
+            let segment = Segment {
+                id: 0,
+                start_ms: 0,
+                end_ms: 1000,
+                text: " Hello".to_string(),
+                language: "en".to_string(),
+                ..Default::default()
+            };
+            if let Some(progress) = &options.progress {
+                progress.report(0.5);
+            }
+            if let Some(on_segment) = &options.on_segment {
+                on_segment.emit(segment.clone());
+            }
+            Ok(TranscriptionResult {
+                language: "en".to_string(),
+                segments: vec![segment],
+                ..Default::default()
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct TestStore {
+        removed: Mutex<Vec<String>>,
+    }
+
+    impl ModelStore for TestStore {
+        fn list(&self) -> Result<Vec<ModelInfo>> {
+            Ok(vec![ModelInfo {
+                name: "base".to_string(),
+                size_mb: 140,
+                downloaded: false,
+                path: None,
+            }])
+        }
+
+        fn download(&self, name: &str, _force: bool) -> Result<ModelInfo> {
+            anyhow::bail!("No network for {}", name)
+        }
+
+        fn remove(&self, name: &str) -> Result<()> {
+            self.removed.lock().unwrap().push(name.to_string());
+            Ok(())
+        }
+
+        fn load(&self, _name: &str) -> Result<Arc<dyn TranscriptionBackend>> {
+            Ok(Arc::new(TestBackend))
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Run the server on the given input lines and return the output messages
+    fn run_lines(store: Arc<dyn ModelStore>, lines: &[String]) -> Vec<Value> {
+        let output = SharedBuffer::default();
+        let input = lines.join("\n");
+        run(store, input.as_bytes(), output.clone()).unwrap();
+
+        let bytes = output.0.lock().unwrap().clone();
+        String::from_utf8(bytes)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn response<'a>(messages: &'a [Value], id: &Value) -> &'a Value {
+        messages
+            .iter()
+            .find(|message| message.get("id") == Some(id) && message.get("method").is_none())
+            .unwrap_or_else(|| panic!("no response for {}", id))
+    }
+
+    fn temp_wav(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "orangenote-rpc-{}-{}.wav",
+            std::process::id(),
+            name
+        ));
+        let wav = AudioProcessor::encode_wav(&vec![0.0; 16000]).unwrap();
+        std::fs::write(&path, wav).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_probe_models_and_errors() {
+        let path = temp_wav("probe");
+        let store = Arc::new(TestStore::default());
+        let messages = run_lines(
+            store.clone(),
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "probe", "params": {"path": path}})
+                    .to_string(),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "model.list"}).to_string(),
+                json!({"jsonrpc": "2.0", "id": 3, "method": "model.remove", "params": {"model": "tiny"}})
+                    .to_string(),
+                json!({"jsonrpc": "2.0", "id": 4, "method": "model.download", "params": {"model": "tiny"}})
+                    .to_string(),
+                json!({"jsonrpc": "2.0", "id": 5, "method": "shutdown"}).to_string(),
+                json!({"jsonrpc": "2.0", "id": 6, "method": "probe", "params": {}}).to_string(),
+                json!({"jsonrpc": "2.0", "method": "model.remove", "params": {"model": "base"}})
+                    .to_string(),
+                "{not json".to_string(),
+            ],
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        let probe = &response(&messages, &json!(1))["result"];
+        assert_eq!(probe["format"], "WAV");
+        assert_eq!(probe["sample_rate"], 16000);
+        assert_eq!(probe["channels"], 1);
+
+        assert_eq!(response(&messages, &json!(2))["result"][0]["name"], "base");
+        assert_eq!(response(&messages, &json!(3))["result"]["removed"], "tiny");
+        let download = &response(&messages, &json!(4))["error"];
+        assert_eq!(download["code"], OPERATION_FAILED);
+        assert_eq!(download["message"], "No network for tiny");
+        assert_eq!(
+            response(&messages, &json!(5))["error"]["code"],
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            response(&messages, &json!(6))["error"]["code"],
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            response(&messages, &Value::Null)["error"]["code"],
+            PARSE_ERROR
+        );
+
+        // The notification was executed without a response
+        assert_eq!(*store.removed.lock().unwrap(), vec!["tiny", "base"]);
+        assert_eq!(messages.len(), 7);
+    }
+
+    #[test]
+    fn test_transcribe_notifications_and_cancel() {
+        let path = temp_wav("transcribe");
+        let messages = run_lines(
+            Arc::new(TestStore::default()),
+            &[
+                json!({"jsonrpc": "2.0", "id": "a", "method": "transcribe",
+                       "params": {"path": path, "format": "srt"}})
+                .to_string(),
+                json!({"jsonrpc": "2.0", "id": "b", "method": "transcribe",
+                       "params": {"path": path, "language": "tlh"}})
+                .to_string(),
+                json!({"jsonrpc": "2.0", "id": "c", "method": "cancel", "params": {"id": "b"}})
+                    .to_string(),
+                json!({"jsonrpc": "2.0", "id": "d", "method": "cancel", "params": {"id": "x"}})
+                    .to_string(),
+            ],
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        let result = &response(&messages, &json!("a"))["result"];
+        assert_eq!(result["language"], "en");
+        assert_eq!(result["segments"][0]["text"], " Hello");
+        assert_eq!(
+            result["output"],
+            "1\n00:00:00,000 --> 00:00:01,000\n Hello\n"
+        );
+
+        let notifications: Vec<&Value> = messages
+            .iter()
+            .filter(|message| message["params"]["id"] == "a")
+            .collect();
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(notifications[0]["method"], "transcribe.progress");
+        assert_eq!(notifications[0]["params"]["progress"], 0.5);
+        assert_eq!(notifications[1]["method"], "transcribe.segment");
+        assert_eq!(notifications[1]["params"]["segment"]["end_ms"], 1000);
+
+        assert_eq!(
+            response(&messages, &json!("c"))["result"]["cancelled"],
+            true
+        );
+        assert_eq!(
+            response(&messages, &json!("d"))["result"]["cancelled"],
+            false
+        );
+        assert_eq!(response(&messages, &json!("b"))["result"]["partial"], true);
+    }
+}
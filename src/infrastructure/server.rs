@@ -0,0 +1,582 @@
+//! OpenAI-compatible transcription server
+//!
+//! Serves `POST /v1/audio/transcriptions` and `POST /v1/audio/translations` with
+//! multipart uploads, plus `GET /health`, on top of any `TranscriptionBackend`.
+//! Uploads are decoded with `AudioProcessor` and transcribed through `pipeline` on
+//! blocking threads. Requests beyond the concurrency limit are rejected with 429
+//! instead of queueing behind long transcriptions.
+
+use crate::infrastructure::audio::{AudioProcessor, ChunkConfig};
+use crate::infrastructure::transcription::format::{
+    format_plain_text, format_srt, format_verbose_json, format_vtt,
+};
+use crate::infrastructure::transcription::{pipeline, TranscribeOptions, TranscriptionBackend};
+use anyhow::{Context, Result};
+use axum::extract::{DefaultBodyLimit, Multipart, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use log::{info, warn};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Supported values of the `response_format` field
+pub const RESPONSE_FORMATS: &[&str] = &["json", "text", "srt", "vtt", "verbose_json"];
+
+/// Default upload size limit (25 MB, as in the OpenAI API)
+pub const DEFAULT_MAX_UPLOAD_BYTES: usize = 25 * 1024 * 1024;
+
+/// Server settings
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Model name reported by the health endpoint
+    pub model: String,
+    /// Maximum number of requests transcribed at the same time
+    pub max_concurrent: usize,
+    /// Maximum size of a request body in bytes
+    pub max_upload_bytes: usize,
+    /// Decoding options that request fields are applied on top of
+    pub options: TranscribeOptions,
+    /// Split uploads into chunks, or decode them in one piece if `None`
+    pub chunk_config: Option<ChunkConfig>,
+}
+
+impl ServerConfig {
+    /// Create a config serving one request at a time with default options
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - Model name reported by the health endpoint
+    pub fn new<S: Into<String>>(model: S) -> Self {
+        ServerConfig {
+            model: model.into(),
+            max_concurrent: 1,
+            max_upload_bytes: DEFAULT_MAX_UPLOAD_BYTES,
+            options: TranscribeOptions::default(),
+            chunk_config: None,
+        }
+    }
+
+    /// Set the maximum number of concurrent requests
+    pub fn max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = max_concurrent;
+        self
+    }
+
+    /// Set the maximum size of a request body in bytes
+    pub fn max_upload_bytes(mut self, max_upload_bytes: usize) -> Self {
+        self.max_upload_bytes = max_upload_bytes;
+        self
+    }
+
+    /// Set the base decoding options
+    pub fn options(mut self, options: TranscribeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Transcribe uploads in chunks
+    pub fn chunk_config(mut self, chunk_config: ChunkConfig) -> Self {
+        self.chunk_config = Some(chunk_config);
+        self
+    }
+
+    /// Validate the config
+    pub fn validate(&self) -> Result<()> {
+        if self.max_concurrent == 0 {
+            anyhow::bail!("Maximum concurrent requests must be at least 1");
+        }
+        if self.max_upload_bytes == 0 {
+            anyhow::bail!("Maximum upload size must be at least 1 byte");
+        }
+        self.options.validate()
+    }
+}
+
+/// State shared by the request handlers
+struct ServerState {
+    backend: Arc<dyn TranscriptionBackend>,
+    config: ServerConfig,
+    permits: Arc<Semaphore>,
+}
+
+impl ServerState {
+    fn active_requests(&self) -> usize {
+        self.config.max_concurrent - self.permits.available_permits()
+    }
+}
+
+/// Build the router serving the transcription endpoints
+///
+/// # Arguments
+///
+/// * `backend` - Backend transcribing the uploads
+/// * `config` - Server settings
+///
+/// # Returns
+///
+/// Result containing the router, or an error if the config is invalid
+pub fn router(backend: Arc<dyn TranscriptionBackend>, config: ServerConfig) -> Result<Router> {
+    config.validate()?;
+    Ok(app(Arc::new(ServerState {
+        backend,
+        permits: Arc::new(Semaphore::new(config.max_concurrent)),
+        config,
+    })))
+}
+
+fn app(state: Arc<ServerState>) -> Router {
+    let max_upload_bytes = state.config.max_upload_bytes;
+    Router::new()
+        .route("/health", get(health))
+        .route("/v1/audio/transcriptions", post(transcriptions))
+        .route("/v1/audio/translations", post(translations))
+        .layer(DefaultBodyLimit::max(max_upload_bytes))
+        .with_state(state)
+}
+
+/// Serve the transcription endpoints until Ctrl-C
+///
+/// # Arguments
+///
+/// * `backend` - Backend transcribing the uploads
+/// * `config` - Server settings
+/// * `addr` - Address to listen on
+pub async fn serve(
+    backend: Arc<dyn TranscriptionBackend>,
+    config: ServerConfig,
+    addr: SocketAddr,
+) -> Result<()> {
+    let app = router(backend, config)?;
+    let server = axum::Server::try_bind(&addr)
+        .with_context(|| format!("Failed to listen on {}", addr))?
+        .serve(app.into_make_service());
+    info!("Listening on http://{}", server.local_addr());
+
+    server
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .context("Server failed")
+}
+
+/// Error response in the OpenAI error layout
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    kind: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: impl Into<String>) -> Self {
+        ApiError {
+            status: StatusCode::BAD_REQUEST,
+            kind: "invalid_request_error",
+            message: message.into(),
+        }
+    }
+
+    fn internal(message: impl Into<String>) -> Self {
+        ApiError {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            kind: "server_error",
+            message: message.into(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({
+            "error": {
+                "message": self.message,
+                "type": self.kind,
+            }
+        });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+async fn health(State(state): State<Arc<ServerState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "status": "ok",
+        "model": state.config.model,
+        "active_requests": state.active_requests(),
+        "max_concurrent": state.config.max_concurrent,
+    }))
+}
+
+async fn transcriptions(
+    State(state): State<Arc<ServerState>>,
+    multipart: Multipart,
+) -> Result<Response, ApiError> {
+    handle_request(state, multipart, false).await
+}
+
+async fn translations(
+    State(state): State<Arc<ServerState>>,
+    multipart: Multipart,
+) -> Result<Response, ApiError> {
+    handle_request(state, multipart, true).await
+}
+
+/// Fields of a transcription request
+#[derive(Debug, Default)]
+struct TranscriptionRequest {
+    audio: Option<Vec<u8>>,
+    file_name: Option<String>,
+    language: Option<String>,
+    prompt: Option<String>,
+    response_format: Option<String>,
+    temperature: Option<f32>,
+    word_timestamps: bool,
+}
+
+impl TranscriptionRequest {
+    /// Read the multipart fields; unknown fields (such as `model`) are ignored
+    async fn read(mut multipart: Multipart) -> Result<Self, ApiError> {
+        let mut request = TranscriptionRequest::default();
+        while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+            let name = field.name().unwrap_or_default().to_string();
+            if name == "file" {
+                request.file_name = field.file_name().map(str::to_string);
+                request.audio = Some(field.bytes().await.map_err(multipart_error)?.to_vec());
+                continue;
+            }
+
+            let value = field.text().await.map_err(multipart_error)?;
+            let value = value.trim();
+            match name.as_str() {
+                "language" if !value.is_empty() => request.language = Some(value.to_string()),
+                "prompt" if !value.is_empty() => request.prompt = Some(value.to_string()),
+                "response_format" => request.response_format = Some(value.to_string()),
+                "temperature" => {
+                    request.temperature = Some(value.parse().map_err(|_| {
+                        ApiError::bad_request(format!("Invalid temperature: {}", value))
+                    })?)
+                }
+                "timestamp_granularities[]" | "timestamp_granularities" => {
+                    request.word_timestamps |= value == "word";
+                }
+                _ => {}
+            }
+        }
+        Ok(request)
+    }
+
+    /// Apply the request fields to the base options
+    fn apply(&self, mut options: TranscribeOptions) -> TranscribeOptions {
+        if let Some(language) = &self.language {
+            options = options.language(language.as_str());
+        }
+        if let Some(prompt) = &self.prompt {
+            options = options.initial_prompt(prompt.as_str());
+        }
+        if let Some(temperature) = self.temperature {
+            options = options.temperature(temperature);
+        }
+        options
+    }
+}
+
+fn multipart_error(err: axum::extract::multipart::MultipartError) -> ApiError {
+    ApiError {
+        status: err.status(),
+        kind: "invalid_request_error",
+        message: err.body_text(),
+    }
+}
+
+async fn handle_request(
+    state: Arc<ServerState>,
+    multipart: Multipart,
+    translate: bool,
+) -> Result<Response, ApiError> {
+    let _permit = state
+        .permits
+        .clone()
+        .try_acquire_owned()
+        .map_err(|_| ApiError {
+            status: StatusCode::TOO_MANY_REQUESTS,
+            kind: "server_busy",
+            message: format!(
+                "Server is busy ({} concurrent requests), try again later",
+                state.config.max_concurrent
+            ),
+        })?;
+
+    let mut request = TranscriptionRequest::read(multipart).await?;
+    let format = request
+        .response_format
+        .clone()
+        .unwrap_or_else(|| "json".to_string());
+    if !RESPONSE_FORMATS.contains(&format.as_str()) {
+        return Err(ApiError::bad_request(format!(
+            "Unsupported response_format '{}', expected one of: {}",
+            format,
+            RESPONSE_FORMATS.join(", ")
+        )));
+    }
+    let audio = request
+        .audio
+        .take()
+        .ok_or_else(|| ApiError::bad_request("Missing 'file' field"))?;
+    let extension = request
+        .file_name
+        .as_deref()
+        .and_then(|name| Path::new(name).extension())
+        .and_then(|ext| ext.to_str())
+        .map(str::to_string);
+    let options = request
+        .apply(state.config.options.clone())
+        .translate(translate);
+    options
+        .validate()
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+
+    let backend = state.backend.clone();
+    let chunk_config = state.config.chunk_config.clone();
+    let (result, duration_ms) = tokio::task::spawn_blocking(move || {
+        let audio = AudioProcessor::process_bytes(audio, extension.as_deref())
+            .map_err(|e| ApiError::bad_request(format!("Failed to decode audio: {:#}", e)))?;
+        let duration_ms = (audio.duration_seconds * 1000.0).round() as i64;
+        let result = match &chunk_config {
+            Some(chunk_config) => pipeline::transcribe_chunked(
+                backend.as_ref(),
+                &audio,
+                &options,
+                chunk_config,
+                |_, _| {},
+            ),
+            None => pipeline::transcribe_samples(backend.as_ref(), &audio.samples, &options),
+        };
+        result.map(|result| (result, duration_ms)).map_err(|e| {
+            warn!("Transcription failed: {:#}", e);
+            ApiError::internal(format!("Transcription failed: {:#}", e))
+        })
+    })
+    .await
+    .map_err(|e| ApiError::internal(format!("Transcription task failed: {}", e)))??;
+
+    let task = if translate { "translate" } else { "transcribe" };
+    let response = match format.as_str() {
+        "json" => Json(serde_json::json!({ "text": format_plain_text(&result) })).into_response(),
+        "text" => plain_response("text/plain; charset=utf-8", format_plain_text(&result)),
+        "srt" => plain_response("text/plain; charset=utf-8", format_srt(&result, false)),
+        "vtt" => plain_response("text/vtt; charset=utf-8", format_vtt(&result, false, None)),
+        _ => {
+            let body = format_verbose_json(&result, task, duration_ms, request.word_timestamps)
+                .map_err(|e| ApiError::internal(e.to_string()))?;
+            plain_response("application/json", body)
+        }
+    };
+    Ok(response)
+}
+
+fn plain_response(content_type: &'static str, body: String) -> Response {
+    ([(header::CONTENT_TYPE, content_type)], body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::transcription::{MockBackend, Segment, TranscriptionResult};
+    use axum::body::{Body, HttpBody};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    const BOUNDARY: &str = "orangenote-test-boundary";
+
+    fn scripted_result(text: &str) -> TranscriptionResult {
+        TranscriptionResult {
+            language: "de".to_string(),
+            segments: vec![Segment {
+                id: 0,
+                start_ms: 0,
+                end_ms: 1000,
+                text: format!(" {}", text),
+                language: "de".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn test_state(backend: Arc<MockBackend>, max_concurrent: usize) -> Arc<ServerState> {
+        Arc::new(ServerState {
+            backend,
+            config: ServerConfig::new("test").max_concurrent(max_concurrent),
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+        })
+    }
+
+    /// Build a multipart upload of one second of silence plus text fields
+    fn upload(path: &str, fields: &[(&str, &str)]) -> Request<Body> {
+        let wav = AudioProcessor::encode_wav(&[0.0; 16000]).unwrap();
+        let mut body = Vec::new();
+        for (name, value) in fields {
+            body.extend_from_slice(
+                format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                    BOUNDARY, name, value
+                )
+                .as_bytes(),
+            );
+        }
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.wav\"\r\n\
+                 Content-Type: audio/wav\r\n\r\n",
+                BOUNDARY
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&wav);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+
+        Request::post(path)
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", BOUNDARY),
+            )
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    async fn send(state: &Arc<ServerState>, request: Request<Body>) -> (StatusCode, String) {
+        let response = app(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let mut body = response.into_body();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            bytes.extend_from_slice(&chunk.unwrap());
+        }
+        (status, String::from_utf8(bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_response_formats() {
+        let backend = Arc::new(
+            MockBackend::new()
+                .then(scripted_result("Hallo"))
+                .then(scripted_result("Hallo"))
+                .then(scripted_result("Hallo"))
+                .then(scripted_result("Hallo")),
+        );
+        let state = test_state(backend.clone(), 1);
+
+        let (status, body) = send(&state, upload("/v1/audio/transcriptions", &[])).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, r#"{"text":"Hallo"}"#);
+
+        let (_, body) = send(
+            &state,
+            upload("/v1/audio/transcriptions", &[("response_format", "srt")]),
+        )
+        .await;
+        assert_eq!(body, "1\n00:00:00,000 --> 00:00:01,000\n Hallo\n");
+
+        let (_, body) = send(
+            &state,
+            upload("/v1/audio/transcriptions", &[("response_format", "vtt")]),
+        )
+        .await;
+        assert!(body.starts_with("WEBVTT\n\n"));
+
+        let (_, body) = send(
+            &state,
+            upload(
+                "/v1/audio/transcriptions",
+                &[
+                    ("model", "whisper-1"),
+                    ("response_format", "verbose_json"),
+                    ("timestamp_granularities[]", "word"),
+                ],
+            ),
+        )
+        .await;
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["task"], "transcribe");
+        assert_eq!(json["language"], "german");
+        assert_eq!(json["duration"], 1.0);
+        assert!(json["words"].is_array());
+
+        assert_eq!(backend.calls()[0].samples, 16000);
+        assert_eq!(backend.remaining(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_translation_applies_request_fields() {
+        let backend = Arc::new(MockBackend::new().then(scripted_result("Hello")));
+        let state = test_state(backend.clone(), 1);
+
+        let (status, body) = send(
+            &state,
+            upload(
+                "/v1/audio/translations",
+                &[
+                    ("language", "de"),
+                    ("prompt", "Greetings."),
+                    ("temperature", "0.2"),
+                    ("response_format", "text"),
+                ],
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "Hello");
+
+        let options = &backend.calls()[0].options;
+        assert!(options.translate);
+        assert_eq!(options.language.as_deref(), Some("de"));
+        assert_eq!(options.initial_prompt.as_deref(), Some("Greetings."));
+        assert_eq!(options.temperature, 0.2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_requests_and_busy_server() {
+        let backend = Arc::new(MockBackend::new().then_err("decoder crashed"));
+        let state = test_state(backend.clone(), 1);
+
+        let (status, body) = send(
+            &state,
+            upload("/v1/audio/transcriptions", &[("response_format", "docx")]),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("invalid_request_error"));
+
+        let request = Request::post("/v1/audio/transcriptions")
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", BOUNDARY),
+            )
+            .body(Body::from(format!("--{}--\r\n", BOUNDARY)))
+            .unwrap();
+        let (status, body) = send(&state, request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("Missing 'file' field"));
+
+        let (status, body) = send(&state, upload("/v1/audio/transcriptions", &[])).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(body.contains("decoder crashed"));
+
+        // All permits taken: rejected before reading the upload
+        let _permit = state.permits.clone().try_acquire_owned().unwrap();
+        let (status, _) = send(&state, upload("/v1/audio/transcriptions", &[])).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+        let (status, body) =
+            send(&state, Request::get("/health").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["model"], "test");
+        assert_eq!(json["active_requests"], 1);
+        assert_eq!(backend.calls().len(), 1);
+    }
+}
@@ -0,0 +1,186 @@
+//! Transcription backends
+//!
+//! A backend turns 16kHz mono samples into a `TranscriptionResult`. Everything
+//! around a single decode (quality retries, chunking, merging, formatting) is
+//! backend-independent and lives in `pipeline` and `format`.
+
+use super::options::TranscribeOptions;
+use super::result::TranscriptionResult;
+use anyhow::{anyhow, Result};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Engine decoding PCM samples into a transcription
+///
+/// Implementations run a single decode; quality retries are done by the caller.
+/// Timestamps in the result are relative to the first sample.
+pub trait TranscriptionBackend: Send + Sync {
+    /// Decode PCM samples
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - PCM samples at 16kHz, f32 normalized to [-1.0, 1.0]
+    /// * `options` - Decoding options
+    ///
+    /// # Returns
+    ///
+    /// Result containing the transcription result
+    fn transcribe(
+        &self,
+        samples: &[f32],
+        options: &TranscribeOptions,
+    ) -> Result<TranscriptionResult>;
+}
+
+impl<B: TranscriptionBackend + ?Sized> TranscriptionBackend for &B {
+    fn transcribe(
+        &self,
+        samples: &[f32],
+        options: &TranscribeOptions,
+    ) -> Result<TranscriptionResult> {
+        (**self).transcribe(samples, options)
+    }
+}
+
+impl<B: TranscriptionBackend + ?Sized> TranscriptionBackend for Box<B> {
+    fn transcribe(
+        &self,
+        samples: &[f32],
+        options: &TranscribeOptions,
+    ) -> Result<TranscriptionResult> {
+        (**self).transcribe(samples, options)
+    }
+}
+
+/// A decode request recorded by `MockBackend`
+#[derive(Debug, Clone)]
+pub struct MockCall {
+    /// Number of samples passed to the backend
+    pub samples: usize,
+    /// Options passed to the backend
+    pub options: TranscribeOptions,
+}
+
+/// Backend returning scripted results, for testing without a model
+///
+/// Each decode takes the next scripted response; decoding after the script is
+/// exhausted fails. All decode requests are recorded.
+///
+/// # Example
+///
+/// ```ignore
+/// let backend = MockBackend::new()
+///     .then(first_chunk_result)
+///     .then_err("decoder crashed");
+/// let result = pipeline::transcribe_samples(&backend, &samples, &options)?;
+/// assert_eq!(backend.calls().len(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct MockBackend {
+    responses: Mutex<VecDeque<std::result::Result<TranscriptionResult, String>>>,
+    calls: Mutex<Vec<MockCall>>,
+}
+
+impl MockBackend {
+    /// Create a backend with an empty script
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a result to the script
+    pub fn then(self, result: TranscriptionResult) -> Self {
+        self.push(Ok(result));
+        self
+    }
+
+    /// Append a decode failure to the script
+    pub fn then_err(self, message: impl Into<String>) -> Self {
+        self.push(Err(message.into()));
+        self
+    }
+
+    /// Get the decode requests made so far
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.calls
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Number of scripted responses not used yet
+    pub fn remaining(&self) -> usize {
+        self.responses
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .len()
+    }
+
+    fn push(&self, response: std::result::Result<TranscriptionResult, String>) {
+        self.responses
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push_back(response);
+    }
+}
+
+impl TranscriptionBackend for MockBackend {
+    fn transcribe(
+        &self,
+        samples: &[f32],
+        options: &TranscribeOptions,
+    ) -> Result<TranscriptionResult> {
+        self.calls
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(MockCall {
+                samples: samples.len(),
+                options: options.clone(),
+            });
+
+        let response = self
+            .responses
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .pop_front();
+        match response {
+            Some(Ok(result)) => Ok(result),
+            Some(Err(message)) => Err(anyhow!(message)),
+            None => Err(anyhow!("MockBackend script exhausted")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_backend_follows_script() {
+        let backend = MockBackend::new()
+            .then(TranscriptionResult {
+                language: "de".to_string(),
+                ..Default::default()
+            })
+            .then_err("boom");
+
+        let options = TranscribeOptions::new().language("de");
+        assert_eq!(
+            backend.transcribe(&[0.0; 10], &options).unwrap().language,
+            "de"
+        );
+        assert_eq!(
+            backend
+                .transcribe(&[0.0; 5], &options)
+                .unwrap_err()
+                .to_string(),
+            "boom"
+        );
+        assert!(backend.transcribe(&[0.0; 5], &options).is_err());
+
+        let calls = backend.calls();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[0].samples, 10);
+        assert_eq!(calls[1].options.language.as_deref(), Some("de"));
+        assert_eq!(backend.remaining(), 0);
+    }
+}
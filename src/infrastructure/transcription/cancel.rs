@@ -0,0 +1,77 @@
+//! Cancellation support
+//!
+//! A `CancellationToken` is shared between the code that starts a transcription and
+//! the code that wants to stop it (e.g., a Ctrl-C handler). Whisper checks it through
+//! `abort_callback` during inference, and the chunked pipeline checks it between chunks.
+
+#[cfg(feature = "whisper")]
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag used to cancel a running transcription
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a token that is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Check whether cancellation was requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Pointer passed as `abort_callback_user_data`
+    ///
+    /// Valid for as long as any clone of this token is alive.
+    #[cfg(feature = "whisper")]
+    pub(crate) fn as_user_data(&self) -> *mut c_void {
+        Arc::as_ptr(&self.cancelled) as *mut c_void
+    }
+}
+
+/// `abort_callback` for whisper.cpp; `user_data` comes from `CancellationToken::as_user_data`
+#[cfg(feature = "whisper")]
+pub(crate) extern "C" fn abort_callback(user_data: *mut c_void) -> bool {
+    if user_data.is_null() {
+        return false;
+    }
+    let cancelled = unsafe { &*(user_data as *const AtomicBool) };
+    cancelled.load(Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_is_shared_between_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+
+    #[test]
+    #[cfg(feature = "whisper")]
+    fn test_abort_callback_reads_token() {
+        let token = CancellationToken::new();
+        assert!(!abort_callback(token.as_user_data()));
+
+        token.cancel();
+        assert!(abort_callback(token.as_user_data()));
+        assert!(!abort_callback(std::ptr::null_mut()));
+    }
+}
@@ -0,0 +1,720 @@
+//! Output formatting of transcription results
+//!
+//! Renders a `TranscriptionResult` as JSON, plain text, SRT, WebVTT, TSV or the
+//! OpenAI `verbose_json` layout, and streamed segments as JSON Lines or plain text.
+
+use super::result::{Segment, TranscriptionResult};
+use super::review::{mark_uncertain_words, needs_review, review_items, ReviewItem};
+use anyhow::{bail, Context, Result};
+
+/// Supported output formats
+pub const OUTPUT_FORMATS: &[&str] = &["json", "txt", "srt", "vtt", "tsv"];
+
+/// Supported formats for streamed segments
+pub const STREAM_FORMATS: &[&str] = &["jsonl", "text"];
+
+/// Language codes and names used by whisper
+const LANGUAGES: &[(&str, &str)] = &[
+    ("en", "english"),
+    ("zh", "chinese"),
+    ("de", "german"),
+    ("es", "spanish"),
+    ("ru", "russian"),
+    ("ko", "korean"),
+    ("fr", "french"),
+    ("ja", "japanese"),
+    ("pt", "portuguese"),
+    ("tr", "turkish"),
+    ("pl", "polish"),
+    ("ca", "catalan"),
+    ("nl", "dutch"),
+    ("ar", "arabic"),
+    ("sv", "swedish"),
+    ("it", "italian"),
+    ("id", "indonesian"),
+    ("hi", "hindi"),
+    ("fi", "finnish"),
+    ("vi", "vietnamese"),
+    ("he", "hebrew"),
+    ("uk", "ukrainian"),
+    ("el", "greek"),
+    ("ms", "malay"),
+    ("cs", "czech"),
+    ("ro", "romanian"),
+    ("da", "danish"),
+    ("hu", "hungarian"),
+    ("ta", "tamil"),
+    ("no", "norwegian"),
+    ("th", "thai"),
+    ("ur", "urdu"),
+    ("hr", "croatian"),
+    ("bg", "bulgarian"),
+    ("lt", "lithuanian"),
+    ("la", "latin"),
+    ("mi", "maori"),
+    ("ml", "malayalam"),
+    ("cy", "welsh"),
+    ("sk", "slovak"),
+    ("te", "telugu"),
+    ("fa", "persian"),
+    ("lv", "latvian"),
+    ("bn", "bengali"),
+    ("sr", "serbian"),
+    ("az", "azerbaijani"),
+    ("sl", "slovenian"),
+    ("kn", "kannada"),
+    ("et", "estonian"),
+    ("mk", "macedonian"),
+    ("br", "breton"),
+    ("eu", "basque"),
+    ("is", "icelandic"),
+    ("hy", "armenian"),
+    ("ne", "nepali"),
+    ("mn", "mongolian"),
+    ("bs", "bosnian"),
+    ("kk", "kazakh"),
+    ("sq", "albanian"),
+    ("sw", "swahili"),
+    ("gl", "galician"),
+    ("mr", "marathi"),
+    ("pa", "punjabi"),
+    ("si", "sinhala"),
+    ("km", "khmer"),
+    ("sn", "shona"),
+    ("yo", "yoruba"),
+    ("so", "somali"),
+    ("af", "afrikaans"),
+    ("oc", "occitan"),
+    ("ka", "georgian"),
+    ("be", "belarusian"),
+    ("tg", "tajik"),
+    ("sd", "sindhi"),
+    ("gu", "gujarati"),
+    ("am", "amharic"),
+    ("yi", "yiddish"),
+    ("lo", "lao"),
+    ("uz", "uzbek"),
+    ("fo", "faroese"),
+    ("ht", "haitian creole"),
+    ("ps", "pashto"),
+    ("tk", "turkmen"),
+    ("nn", "nynorsk"),
+    ("mt", "maltese"),
+    ("sa", "sanskrit"),
+    ("lb", "luxembourgish"),
+    ("my", "myanmar"),
+    ("bo", "tibetan"),
+    ("tl", "tagalog"),
+    ("mg", "malagasy"),
+    ("as", "assamese"),
+    ("tt", "tatar"),
+    ("haw", "hawaiian"),
+    ("ln", "lingala"),
+    ("ha", "hausa"),
+    ("ba", "bashkir"),
+    ("jw", "javanese"),
+    ("su", "sundanese"),
+    ("yue", "cantonese"),
+];
+
+/// Convert a language name ("english") to its code ("en")
+///
+/// Codes and unknown names are returned lowercased.
+pub fn language_code(language: &str) -> String {
+    let language = language.trim().to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(_, name)| *name == language)
+        .map_or(language, |(code, _)| code.to_string())
+}
+
+/// Convert a language code ("en") to its name ("english")
+///
+/// Unknown codes are returned unchanged.
+pub fn language_name(code: &str) -> String {
+    LANGUAGES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map_or_else(|| code.to_string(), |(_, name)| name.to_string())
+}
+
+/// Options controlling what goes into the formatted output
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    /// Include per-word timestamps (JSON)
+    pub word_timestamps: bool,
+    /// Label speakers and mark speaker turns
+    pub speaker_turns: bool,
+    /// Flag words and segments below this confidence for review
+    pub review_threshold: Option<f32>,
+}
+
+impl FormatOptions {
+    /// Create options with everything disabled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include per-word timestamps
+    pub fn word_timestamps(mut self, enabled: bool) -> Self {
+        self.word_timestamps = enabled;
+        self
+    }
+
+    /// Label speakers and mark speaker turns
+    pub fn speaker_turns(mut self, enabled: bool) -> Self {
+        self.speaker_turns = enabled;
+        self
+    }
+
+    /// Flag words and segments below `threshold` confidence for review
+    pub fn review_threshold(mut self, threshold: Option<f32>) -> Self {
+        self.review_threshold = threshold;
+        self
+    }
+}
+
+/// Check that `format` is a supported output format
+pub fn validate_format(format: &str) -> Result<()> {
+    if !OUTPUT_FORMATS.contains(&format) {
+        bail!(
+            "Invalid format: '{}'. Valid formats: {}",
+            format,
+            OUTPUT_FORMATS.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Check that `format` is a supported stream format
+pub fn validate_stream_format(format: &str) -> Result<()> {
+    if !STREAM_FORMATS.contains(&format) {
+        bail!(
+            "Invalid stream format: '{}'. Valid formats: {}",
+            format,
+            STREAM_FORMATS.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Format a transcription result
+///
+/// # Arguments
+///
+/// * `result` - Transcription result
+/// * `format` - One of `OUTPUT_FORMATS`
+/// * `options` - What to include in the output
+///
+/// # Returns
+///
+/// The formatted output, or an error for unknown formats
+pub fn format_result(
+    result: &TranscriptionResult,
+    format: &str,
+    options: &FormatOptions,
+) -> Result<String> {
+    validate_format(format)?;
+
+    Ok(match format {
+        "json" => format_json(
+            result,
+            options.word_timestamps,
+            options.speaker_turns,
+            options.review_threshold,
+        )?,
+        "txt" => format_txt(result, options.speaker_turns, options.review_threshold),
+        "srt" => format_srt(result, options.speaker_turns),
+        "vtt" => format_vtt(result, options.speaker_turns, options.review_threshold),
+        "tsv" => format_tsv(result),
+        _ => unreachable!(),
+    })
+}
+
+/// Format a streamed segment as a single line (JSON Lines or plain text)
+pub fn format_stream_segment(segment: &Segment, format: &str) -> String {
+    match format {
+        "jsonl" => serde_json::json!({
+            "start": segment.start_time_formatted(),
+            "end": segment.end_time_formatted(),
+            "start_ms": segment.start_ms,
+            "end_ms": segment.end_ms,
+            "text": segment.text,
+            "confidence": segment.confidence,
+            "avg_logprob": segment.avg_logprob,
+            "no_speech_prob": segment.no_speech_prob,
+            "language": segment.language,
+        })
+        .to_string(),
+        _ => format!(
+            "[{} --> {}] {}",
+            segment.start_time_formatted(),
+            segment.end_time_formatted(),
+            segment.text.trim()
+        ),
+    }
+}
+
+/// Marker written to text outputs of cancelled transcriptions
+pub const PARTIAL_NOTE: &str = "[Partial transcript: transcription was cancelled]";
+
+/// Marker written to text output after a segment followed by a speaker turn
+pub const SPEAKER_TURN_MARKER: &str = "[SPEAKER TURN]";
+
+/// Markers around uncertain words in text output
+const UNCERTAIN_OPEN: &str = "[?";
+const UNCERTAIN_CLOSE: &str = "?]";
+
+/// Format the review list, one line per flagged segment
+pub fn format_review_list(items: &[ReviewItem]) -> Vec<String> {
+    items
+        .iter()
+        .map(|item| {
+            let line = format!(
+                "[{} --> {}] {}",
+                item.start_time_formatted(),
+                item.end_time_formatted(),
+                item.text
+            );
+            if item.uncertain_words.is_empty() {
+                format!("{} (confidence {:.2})", line, item.confidence)
+            } else {
+                let words: Vec<&str> = item
+                    .uncertain_words
+                    .iter()
+                    .map(|word| word.text.as_str())
+                    .collect();
+                format!("{} (uncertain: {})", line, words.join(", "))
+            }
+        })
+        .collect()
+}
+
+/// Label for a speaker number from `TranscriptionResult::speaker_numbers`
+pub fn speaker_label(speaker: usize) -> String {
+    format!("Speaker {}", speaker)
+}
+
+/// Format transcription result as JSON
+pub fn format_json(
+    result: &TranscriptionResult,
+    word_timestamps: bool,
+    speaker_turns: bool,
+    review_threshold: Option<f32>,
+) -> Result<String> {
+    let speakers = result.speaker_numbers();
+    let mut json = serde_json::json!({
+        "language": result.language,
+        "partial": result.partial,
+        "language_durations": result.language_durations().iter().map(|(language, duration_ms)| {
+            serde_json::json!({
+                "language": language,
+                "duration_ms": duration_ms,
+            })
+        }).collect::<Vec<_>>(),
+        "segments": result.segments.iter().zip(&speakers).map(|(seg, speaker)| {
+            let mut segment = serde_json::json!({
+                "id": seg.id,
+                "start": seg.start_time_formatted(),
+                "end": seg.end_time_formatted(),
+                "start_ms": seg.start_ms,
+                "end_ms": seg.end_ms,
+                "text": seg.text,
+                "confidence": seg.confidence,
+                "avg_logprob": seg.avg_logprob,
+                "compression_ratio": seg.compression_ratio,
+                "min_token_prob": seg.min_token_prob,
+                "no_speech_prob": seg.no_speech_prob,
+                "language": seg.language,
+            });
+            if speaker_turns {
+                segment["speaker"] = speaker_label(*speaker).into();
+                segment["speaker_turn_next"] = seg.speaker_turn_next.into();
+            }
+            if let Some(threshold) = review_threshold {
+                segment["needs_review"] = needs_review(seg, threshold).into();
+            }
+            if word_timestamps {
+                segment["words"] = seg.words().iter().map(|word| {
+                    let mut json = serde_json::json!({
+                        "text": word.text,
+                        "start_ms": word.start_ms,
+                        "end_ms": word.end_ms,
+                        "probability": word.probability,
+                        "dtw_ms": word.dtw_ms,
+                    });
+                    if let Some(threshold) = review_threshold {
+                        json["needs_review"] = (word.probability < threshold).into();
+                    }
+                    json
+                }).collect();
+            }
+            segment
+        }).collect::<Vec<_>>(),
+        "quality_retries": result.quality_retries.iter().map(|retry| {
+            serde_json::json!({
+                "chunk_index": retry.chunk_index,
+                "start_ms": retry.start_ms,
+                "attempts": retry.attempts,
+                "issues": retry.issues,
+                "resolved": retry.resolved,
+            })
+        }).collect::<Vec<_>>()
+    });
+    if let Some(threshold) = review_threshold {
+        json["review"] = review_items(result, threshold).iter().map(|item| {
+            serde_json::json!({
+                "segment_id": item.segment_id,
+                "start": item.start_time_formatted(),
+                "end": item.end_time_formatted(),
+                "start_ms": item.start_ms,
+                "end_ms": item.end_ms,
+                "text": item.text,
+                "confidence": item.confidence,
+                "uncertain_words": item.uncertain_words.iter().map(|word| word.text.as_str()).collect::<Vec<_>>(),
+            })
+        }).collect();
+    }
+    serde_json::to_string_pretty(&json).context("Failed to serialize JSON")
+}
+
+/// Format the text of all segments as a single line, as in OpenAI `text` responses
+pub fn format_plain_text(result: &TranscriptionResult) -> String {
+    result
+        .segments
+        .iter()
+        .map(|seg| seg.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Format transcription result as OpenAI-style `verbose_json`
+///
+/// # Arguments
+///
+/// * `result` - Transcription result
+/// * `task` - "transcribe" or "translate"
+/// * `duration_ms` - Duration of the transcribed audio in milliseconds
+/// * `word_timestamps` - Include the top-level `words` list
+pub fn format_verbose_json(
+    result: &TranscriptionResult,
+    task: &str,
+    duration_ms: i64,
+    word_timestamps: bool,
+) -> Result<String> {
+    let seconds = |ms: i64| ms as f64 / 1000.0;
+    let mut json = serde_json::json!({
+        "task": task,
+        "language": language_name(&result.language),
+        "duration": seconds(duration_ms),
+        "text": format_plain_text(result),
+        "segments": result.segments.iter().map(|seg| {
+            serde_json::json!({
+                "id": seg.id,
+                "seek": seg.start_ms / 10,
+                "start": seconds(seg.start_ms),
+                "end": seconds(seg.end_ms),
+                "text": seg.text,
+                "tokens": seg.tokens.iter().filter(|t| !t.is_special()).map(|t| t.id).collect::<Vec<_>>(),
+                "avg_logprob": seg.avg_logprob,
+                "compression_ratio": seg.compression_ratio,
+                "no_speech_prob": seg.no_speech_prob,
+            })
+        }).collect::<Vec<_>>(),
+    });
+    if word_timestamps {
+        json["words"] = result
+            .segments
+            .iter()
+            .flat_map(|seg| seg.words())
+            .map(|word| {
+                serde_json::json!({
+                    "word": word.text.trim(),
+                    "start": seconds(word.start_ms),
+                    "end": seconds(word.end_ms),
+                    "probability": word.probability,
+                })
+            })
+            .collect();
+    }
+    serde_json::to_string(&json).context("Failed to serialize JSON")
+}
+
+/// Format transcription result as plain text
+pub fn format_txt(
+    result: &TranscriptionResult,
+    speaker_turns: bool,
+    review_threshold: Option<f32>,
+) -> String {
+    let mut text = result
+        .segments
+        .iter()
+        .map(|seg| {
+            let seg_text = match review_threshold {
+                Some(threshold) => {
+                    mark_uncertain_words(seg, threshold, UNCERTAIN_OPEN, UNCERTAIN_CLOSE)
+                }
+                None => seg.text.clone(),
+            };
+            let line = format!("[{}] {}", seg.start_time_formatted(), seg_text);
+            if speaker_turns && seg.speaker_turn_next {
+                format!("{}\n{}", line, SPEAKER_TURN_MARKER)
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if let Some(threshold) = review_threshold {
+        let review = format_review_list(&review_items(result, threshold));
+        if !review.is_empty() {
+            text.push_str("\n\nNeeds review:\n");
+            text.push_str(&review.join("\n"));
+        }
+    }
+
+    if result.partial {
+        format!("{}\n{}", PARTIAL_NOTE, text)
+    } else {
+        text
+    }
+}
+
+/// Duration of the closing cue that marks a partial SRT transcript
+const PARTIAL_CUE_MS: i64 = 2000;
+
+/// Format transcription result as SRT (SubRip)
+///
+/// SRT has no comments, so partial transcripts end with an extra cue holding
+/// `PARTIAL_NOTE`.
+pub fn format_srt(result: &TranscriptionResult, speaker_turns: bool) -> String {
+    let mut cues: Vec<String> = result
+        .segments
+        .iter()
+        .zip(result.speaker_numbers())
+        .map(|(seg, speaker)| {
+            let text = if speaker_turns {
+                format!("[{}] {}", speaker_label(speaker), seg.text.trim_start())
+            } else {
+                seg.text.clone()
+            };
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                seg.id + 1,
+                format_srt_time(seg.start_ms),
+                format_srt_time(seg.end_ms),
+                text
+            )
+        })
+        .collect();
+
+    if result.partial {
+        let (id, start_ms) = result
+            .segments
+            .last()
+            .map_or((0, 0), |seg| (seg.id + 1, seg.end_ms));
+        cues.push(format!(
+            "{}\n{} --> {}\n{}\n",
+            id + 1,
+            format_srt_time(start_ms),
+            format_srt_time(start_ms + PARTIAL_CUE_MS),
+            PARTIAL_NOTE
+        ));
+    }
+    cues.join("\n")
+}
+
+/// Format transcription result as VTT (WebVTT)
+pub fn format_vtt(
+    result: &TranscriptionResult,
+    speaker_turns: bool,
+    review_threshold: Option<f32>,
+) -> String {
+    let mut output = "WEBVTT\n\n".to_string();
+    if review_threshold.is_some() {
+        output.push_str("STYLE\n::cue(.uncertain) {\n  color: yellow;\n}\n\n");
+    }
+    if result.partial {
+        output.push_str(&format!("NOTE {}\n\n", PARTIAL_NOTE));
+    }
+    output.push_str(
+        &result
+            .segments
+            .iter()
+            .zip(result.speaker_numbers())
+            .map(|(seg, speaker)| {
+                let seg_text = match review_threshold {
+                    Some(threshold) => {
+                        mark_uncertain_words(seg, threshold, "<c.uncertain>", "</c>")
+                    }
+                    None => seg.text.clone(),
+                };
+                let text = if speaker_turns {
+                    format!("<v {}>{}", speaker_label(speaker), seg_text.trim_start())
+                } else {
+                    seg_text
+                };
+                format!(
+                    "{} --> {}\n{}\n",
+                    format_srt_time(seg.start_ms),
+                    format_srt_time(seg.end_ms),
+                    text
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    );
+
+    if let Some(threshold) = review_threshold {
+        let review = format_review_list(&review_items(result, threshold));
+        if !review.is_empty() {
+            output.push_str(&format!("\nNOTE Needs review\n{}\n", review.join("\n")));
+        }
+    }
+    output
+}
+
+/// Format transcription result as TSV (tab-separated values)
+///
+/// Partial transcripts end with a `#` comment line holding `PARTIAL_NOTE`.
+pub fn format_tsv(result: &TranscriptionResult) -> String {
+    let header = "ID\tStart\tEnd\tStartMS\tEndMS\tConfidence\tAvgLogprob\tCompressionRatio\tMinTokenProb\tNoSpeechProb\tText\n";
+    let rows = result
+        .segments
+        .iter()
+        .map(|seg| {
+            format!(
+                "{}\t{}\t{}\t{}\t{}\t{:.3}\t{:.3}\t{:.3}\t{:.3}\t{:.3}\t{}",
+                seg.id,
+                seg.start_time_formatted(),
+                seg.end_time_formatted(),
+                seg.start_ms,
+                seg.end_ms,
+                seg.confidence,
+                seg.avg_logprob,
+                seg.compression_ratio,
+                seg.min_token_prob,
+                seg.no_speech_prob,
+                seg.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut output = format!("{}{}\n", header, rows);
+    if result.partial {
+        output.push_str(&format!("# {}\n", PARTIAL_NOTE));
+    }
+    output
+}
+
+/// Format time for SRT/VTT format (HH:MM:SS,mmm)
+pub fn format_srt_time(ms: i64) -> String {
+    let total_seconds = ms / 1000;
+    let milliseconds = ms % 1000;
+    let seconds = total_seconds % 60;
+    let minutes = (total_seconds / 60) % 60;
+    let hours = total_seconds / 3600;
+
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        hours, minutes, seconds, milliseconds
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result() -> TranscriptionResult {
+        TranscriptionResult {
+            language: "en".to_string(),
+            segments: vec![
+                Segment {
+                    id: 0,
+                    start_ms: 0,
+                    end_ms: 1500,
+                    text: " Hello there.".to_string(),
+                    confidence: 0.9,
+                    language: "en".to_string(),
+                    speaker_turn_next: true,
+                    ..Default::default()
+                },
+                Segment {
+                    id: 1,
+                    start_ms: 61_500,
+                    end_ms: 3_723_004,
+                    text: " General Kenobi.".to_string(),
+                    confidence: 0.8,
+                    language: "en".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format_srt_time() {
+        assert_eq!(format_srt_time(0), "00:00:00,000");
+        assert_eq!(format_srt_time(3_723_004), "01:02:03,004");
+    }
+
+    #[test]
+    fn test_format_result_formats() {
+        let result = sample_result();
+        let options = FormatOptions::new();
+
+        let srt = format_result(&result, "srt", &options).unwrap();
+        assert!(srt.starts_with("1\n00:00:00,000 --> 00:00:01,500\n Hello there.\n"));
+        assert!(srt.contains("2\n00:01:01,500 --> 01:02:03,004\n General Kenobi.\n"));
+
+        let vtt = format_result(&result, "vtt", &options).unwrap();
+        assert!(vtt.starts_with("WEBVTT\n\n"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format_result(&result, "json", &options).unwrap()).unwrap();
+        assert_eq!(json["language"], "en");
+        assert_eq!(json["segments"][1]["end_ms"], 3_723_004);
+        assert!(json["segments"][0].get("words").is_none());
+
+        let tsv = format_result(&result, "tsv", &options).unwrap();
+        assert_eq!(tsv.lines().count(), 3);
+
+        assert!(format_result(&result, "docx", &options).is_err());
+    }
+
+    #[test]
+    fn test_format_txt_speaker_turns_and_partial() {
+        let mut result = sample_result();
+        result.partial = true;
+
+        let text = format_txt(&result, true, None);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], PARTIAL_NOTE);
+        assert_eq!(lines[2], SPEAKER_TURN_MARKER);
+
+        let srt = format_srt(&result, true);
+        assert!(srt.contains("[Speaker 2] General Kenobi."));
+    }
+
+    #[test]
+    fn test_format_srt_and_tsv_partial() {
+        let mut result = sample_result();
+        assert!(!format_srt(&result, false).contains(PARTIAL_NOTE));
+        assert!(!format_tsv(&result).contains(PARTIAL_NOTE));
+
+        result.partial = true;
+        let srt = format_srt(&result, false);
+        assert!(srt.ends_with(&format!(
+            "\n\n3\n01:02:03,004 --> 01:02:05,004\n{}\n",
+            PARTIAL_NOTE
+        )));
+
+        let tsv = format_tsv(&result);
+        let lines: Vec<&str> = tsv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[3], format!("# {}", PARTIAL_NOTE));
+    }
+}
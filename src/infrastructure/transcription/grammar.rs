@@ -0,0 +1,522 @@
+//! GBNF grammars for constrained decoding
+//!
+//! Parses grammars in the GBNF format used by llama.cpp and whisper.cpp into the
+//! rule element arrays whisper expects in `whisper_full_params.grammar_rules`.
+//! The parser follows whisper.cpp's `examples/grammar-parser.cpp`, so the same
+//! grammar files produce the same rules, symbol ids included.
+//!
+//! ```text
+//! # Voice commands
+//! root   ::= " " action " " object "."
+//! action ::= "Turn on" | "Turn off"
+//! object ::= "the " ("light" | "fan")
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::os::raw::c_int;
+use std::path::Path;
+
+/// Grammar element types (`whisper_gretype`)
+/// End of rule definition
+pub const WHISPER_GRETYPE_END: c_int = 0;
+/// Start of alternate definition for rule
+pub const WHISPER_GRETYPE_ALT: c_int = 1;
+/// Non-terminal element: reference to rule
+pub const WHISPER_GRETYPE_RULE_REF: c_int = 2;
+/// Terminal element: character (code point)
+pub const WHISPER_GRETYPE_CHAR: c_int = 3;
+/// Inverse char(s) ([^a], [^a-b] [^abc])
+pub const WHISPER_GRETYPE_CHAR_NOT: c_int = 4;
+/// Modifies a preceding CHAR or CHAR_ALT to be an inclusive range ([a-z])
+pub const WHISPER_GRETYPE_CHAR_RNG_UPPER: c_int = 5;
+/// Modifies a preceding CHAR or CHAR_RNG_UPPER to add an alternate char to match ([ab], [a-zA])
+pub const WHISPER_GRETYPE_CHAR_ALT: c_int = 6;
+
+/// Grammar element, laid out like whisper.cpp's `whisper_grammar_element`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhisperGrammarElement {
+    pub type_: c_int,
+    pub value: u32,
+}
+
+/// Name of the rule decoding starts from unless another one is chosen
+pub const DEFAULT_START_RULE: &str = "root";
+
+/// Parsed GBNF grammar
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grammar {
+    /// Rule elements indexed by symbol id, each terminated by an END element
+    rules: Vec<Vec<WhisperGrammarElement>>,
+    /// Symbol ids by rule name, including generated rules (`name_<id>`)
+    symbol_ids: BTreeMap<String, u32>,
+}
+
+impl Grammar {
+    /// Parse a grammar from GBNF source
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Grammar in GBNF format
+    ///
+    /// # Returns
+    ///
+    /// Result containing the grammar, or an error describing where parsing failed
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parser = Parser {
+            src: source,
+            symbol_ids: BTreeMap::new(),
+            rules: Vec::new(),
+        };
+        parser.parse_grammar()?;
+
+        let grammar = Grammar {
+            rules: parser.rules,
+            symbol_ids: parser.symbol_ids,
+        };
+        grammar.check_rules_defined()?;
+        Ok(grammar)
+    }
+
+    /// Load and parse a grammar file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read grammar file: {}", path.display()))?;
+
+        Self::parse(&source)
+            .with_context(|| format!("Failed to parse grammar file: {}", path.display()))
+    }
+
+    /// Rule elements indexed by symbol id
+    pub fn rules(&self) -> &[Vec<WhisperGrammarElement>] {
+        &self.rules
+    }
+
+    /// Get the symbol id of a rule by name
+    pub fn rule_index(&self, name: &str) -> Option<usize> {
+        self.symbol_ids.get(name).map(|&id| id as usize)
+    }
+
+    /// Every rule reference must point to a rule with a definition
+    fn check_rules_defined(&self) -> Result<()> {
+        for rule in &self.rules {
+            for element in rule {
+                if element.type_ != WHISPER_GRETYPE_RULE_REF {
+                    continue;
+                }
+                let id = element.value as usize;
+                if self.rules.get(id).is_none_or(|r| r.is_empty()) {
+                    let name = self
+                        .symbol_ids
+                        .iter()
+                        .find(|(_, &symbol)| symbol as usize == id)
+                        .map_or("?", |(name, _)| name.as_str());
+                    return Err(anyhow!("Undefined rule identifier '{}'", name));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Recursive descent parser over the grammar source
+struct Parser<'a> {
+    src: &'a str,
+    symbol_ids: BTreeMap<String, u32>,
+    rules: Vec<Vec<WhisperGrammarElement>>,
+}
+
+impl Parser<'_> {
+    fn parse_grammar(&mut self) -> Result<()> {
+        let mut pos = self.skip_space(0, true);
+        while pos < self.src.len() {
+            pos = self.parse_rule(pos)?;
+        }
+        Ok(())
+    }
+
+    fn parse_rule(&mut self, pos: usize) -> Result<usize> {
+        let name_end = self.parse_name(pos)?;
+        let name = &self.src[pos..name_end];
+        let rule_id = self.symbol_id(name);
+
+        let mut pos = self.skip_space(name_end, false);
+        if !self.src[pos..].starts_with("::=") {
+            return Err(self.error("expecting ::=", pos));
+        }
+        pos = self.skip_space(pos + 3, true);
+
+        pos = self.parse_alternates(pos, name, rule_id, false)?;
+
+        match self.byte(pos) {
+            Some(b'\r') if self.byte(pos + 1) == Some(b'\n') => pos += 2,
+            Some(b'\n') | Some(b'\r') => pos += 1,
+            Some(_) => return Err(self.error("expecting newline or end", pos)),
+            None => {}
+        }
+        Ok(self.skip_space(pos, true))
+    }
+
+    fn parse_alternates(
+        &mut self,
+        pos: usize,
+        rule_name: &str,
+        rule_id: u32,
+        is_nested: bool,
+    ) -> Result<usize> {
+        let mut rule = Vec::new();
+        let mut pos = self.parse_sequence(pos, rule_name, &mut rule, is_nested)?;
+        while self.byte(pos) == Some(b'|') {
+            rule.push(element(WHISPER_GRETYPE_ALT, 0));
+            pos = self.skip_space(pos + 1, true);
+            pos = self.parse_sequence(pos, rule_name, &mut rule, is_nested)?;
+        }
+        rule.push(element(WHISPER_GRETYPE_END, 0));
+        self.add_rule(rule_id, rule);
+        Ok(pos)
+    }
+
+    fn parse_sequence(
+        &mut self,
+        mut pos: usize,
+        rule_name: &str,
+        out: &mut Vec<WhisperGrammarElement>,
+        is_nested: bool,
+    ) -> Result<usize> {
+        // Start of the last symbol, for applying repetition operators
+        let mut last_sym_start = out.len();
+
+        while let Some(c) = self.byte(pos) {
+            match c {
+                b'"' => {
+                    // Literal string
+                    pos += 1;
+                    last_sym_start = out.len();
+                    while self.byte(pos) != Some(b'"') {
+                        if self.byte(pos).is_none() {
+                            return Err(self.error("unexpected end of input", pos));
+                        }
+                        let (ch, next) = self.parse_char(pos)?;
+                        out.push(element(WHISPER_GRETYPE_CHAR, ch));
+                        pos = next;
+                    }
+                    pos = self.skip_space(pos + 1, is_nested);
+                }
+                b'[' => {
+                    // Character class
+                    pos += 1;
+                    let mut start_type = WHISPER_GRETYPE_CHAR;
+                    if self.byte(pos) == Some(b'^') {
+                        pos += 1;
+                        start_type = WHISPER_GRETYPE_CHAR_NOT;
+                    }
+                    last_sym_start = out.len();
+                    while self.byte(pos) != Some(b']') {
+                        if self.byte(pos).is_none() {
+                            return Err(self.error("unexpected end of input", pos));
+                        }
+                        let (ch, next) = self.parse_char(pos)?;
+                        pos = next;
+                        let type_ = if last_sym_start < out.len() {
+                            WHISPER_GRETYPE_CHAR_ALT
+                        } else {
+                            start_type
+                        };
+                        out.push(element(type_, ch));
+
+                        if self.byte(pos) == Some(b'-')
+                            && !matches!(self.byte(pos + 1), Some(b']') | None)
+                        {
+                            let (end, next) = self.parse_char(pos + 1)?;
+                            pos = next;
+                            out.push(element(WHISPER_GRETYPE_CHAR_RNG_UPPER, end));
+                        }
+                    }
+                    pos = self.skip_space(pos + 1, is_nested);
+                }
+                c if is_word_char(c) => {
+                    // Rule reference
+                    let name_end = self.parse_name(pos)?;
+                    let ref_rule_id = self.symbol_id(&self.src[pos..name_end]);
+                    pos = self.skip_space(name_end, is_nested);
+                    last_sym_start = out.len();
+                    out.push(element(WHISPER_GRETYPE_RULE_REF, ref_rule_id));
+                }
+                b'(' => {
+                    // Grouping, parsed into a synthesized rule
+                    pos = self.skip_space(pos + 1, true);
+                    let sub_rule_id = self.generate_symbol_id(rule_name);
+                    pos = self.parse_alternates(pos, rule_name, sub_rule_id, true)?;
+                    last_sym_start = out.len();
+                    out.push(element(WHISPER_GRETYPE_RULE_REF, sub_rule_id));
+                    if self.byte(pos) != Some(b')') {
+                        return Err(self.error("expecting ')'", pos));
+                    }
+                    pos = self.skip_space(pos + 1, is_nested);
+                }
+                b'*' | b'+' | b'?' => {
+                    if last_sym_start == out.len() {
+                        return Err(self.error("expecting preceding item to */+/?", pos));
+                    }
+
+                    // Apply the operator by rewriting the last symbol S into a new rule:
+                    //   S* --> S' ::= S S' |
+                    //   S+ --> S' ::= S S' | S
+                    //   S? --> S' ::= S |
+                    let sub_rule_id = self.generate_symbol_id(rule_name);
+                    let symbol = out.split_off(last_sym_start);
+                    let mut sub_rule = symbol.clone();
+                    if c == b'*' || c == b'+' {
+                        sub_rule.push(element(WHISPER_GRETYPE_RULE_REF, sub_rule_id));
+                    }
+                    sub_rule.push(element(WHISPER_GRETYPE_ALT, 0));
+                    if c == b'+' {
+                        sub_rule.extend_from_slice(&symbol);
+                    }
+                    sub_rule.push(element(WHISPER_GRETYPE_END, 0));
+                    self.add_rule(sub_rule_id, sub_rule);
+
+                    out.push(element(WHISPER_GRETYPE_RULE_REF, sub_rule_id));
+                    pos = self.skip_space(pos + 1, is_nested);
+                }
+                _ => break,
+            }
+        }
+
+        Ok(pos)
+    }
+
+    /// Parse a possibly escaped character, returning its code point and the next position
+    fn parse_char(&self, pos: usize) -> Result<(u32, usize)> {
+        if self.byte(pos) == Some(b'\\') {
+            return match self.byte(pos + 1) {
+                Some(b'x') => self.parse_hex(pos + 2, 2),
+                Some(b'u') => self.parse_hex(pos + 2, 4),
+                Some(b'U') => self.parse_hex(pos + 2, 8),
+                Some(b't') => Ok(('\t' as u32, pos + 2)),
+                Some(b'r') => Ok(('\r' as u32, pos + 2)),
+                Some(b'n') => Ok(('\n' as u32, pos + 2)),
+                Some(c @ (b'\\' | b'"' | b'[' | b']')) => Ok((c as u32, pos + 2)),
+                _ => Err(self.error("unknown escape", pos)),
+            };
+        }
+
+        match self.src[pos..].chars().next() {
+            Some(ch) => Ok((ch as u32, pos + ch.len_utf8())),
+            None => Err(self.error("unexpected end of input", pos)),
+        }
+    }
+
+    fn parse_hex(&self, pos: usize, size: usize) -> Result<(u32, usize)> {
+        let digits = self
+            .src
+            .get(pos..pos + size)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error(&format!("expecting {} hex chars", size), pos))?;
+        let value = u32::from_str_radix(digits, 16)
+            .map_err(|_| self.error(&format!("expecting {} hex chars", size), pos))?;
+        Ok((value, pos + size))
+    }
+
+    fn parse_name(&self, pos: usize) -> Result<usize> {
+        let end = self.src[pos..]
+            .bytes()
+            .position(|b| !is_word_char(b))
+            .map_or(self.src.len(), |len| pos + len);
+        if end == pos {
+            return Err(self.error("expecting name", pos));
+        }
+        Ok(end)
+    }
+
+    /// Skip spaces, tabs and comments, and newlines if `newline_ok`
+    fn skip_space(&self, mut pos: usize, newline_ok: bool) -> usize {
+        while let Some(c) = self.byte(pos) {
+            match c {
+                b'#' => {
+                    while !matches!(self.byte(pos), Some(b'\r') | Some(b'\n') | None) {
+                        pos += 1;
+                    }
+                }
+                b' ' | b'\t' => pos += 1,
+                b'\r' | b'\n' if newline_ok => pos += 1,
+                _ => break,
+            }
+        }
+        pos
+    }
+
+    fn symbol_id(&mut self, name: &str) -> u32 {
+        let next_id = self.symbol_ids.len() as u32;
+        *self.symbol_ids.entry(name.to_string()).or_insert(next_id)
+    }
+
+    fn generate_symbol_id(&mut self, base_name: &str) -> u32 {
+        let next_id = self.symbol_ids.len() as u32;
+        self.symbol_ids
+            .insert(format!("{}_{}", base_name, next_id), next_id);
+        next_id
+    }
+
+    fn add_rule(&mut self, rule_id: u32, rule: Vec<WhisperGrammarElement>) {
+        let rule_id = rule_id as usize;
+        if self.rules.len() <= rule_id {
+            self.rules.resize(rule_id + 1, Vec::new());
+        }
+        self.rules[rule_id] = rule;
+    }
+
+    fn byte(&self, pos: usize) -> Option<u8> {
+        self.src.as_bytes().get(pos).copied()
+    }
+
+    fn error(&self, message: &str, pos: usize) -> anyhow::Error {
+        let line = self.src[..pos.min(self.src.len())].matches('\n').count() + 1;
+        let context: String = self.src[pos.min(self.src.len())..]
+            .chars()
+            .take(20)
+            .collect();
+        anyhow!(
+            "Grammar parse error at line {}: {} at '{}'",
+            line,
+            message,
+            context
+        )
+    }
+}
+
+fn element(type_: c_int, value: u32) -> WhisperGrammarElement {
+    WhisperGrammarElement { type_, value }
+}
+
+fn is_word_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'-'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<WhisperGrammarElement> {
+        text.chars()
+            .map(|c| element(WHISPER_GRETYPE_CHAR, c as u32))
+            .collect()
+    }
+
+    fn end() -> WhisperGrammarElement {
+        element(WHISPER_GRETYPE_END, 0)
+    }
+
+    fn alt() -> WhisperGrammarElement {
+        element(WHISPER_GRETYPE_ALT, 0)
+    }
+
+    fn rule_ref(id: u32) -> WhisperGrammarElement {
+        element(WHISPER_GRETYPE_RULE_REF, id)
+    }
+
+    #[test]
+    fn test_parse_alternate_literals() {
+        let grammar = Grammar::parse("root ::= \"yes\" | \"no\"\n").unwrap();
+
+        let expected = [chars("yes"), vec![alt()], chars("no"), vec![end()]].concat();
+        assert_eq!(grammar.rules(), &[expected]);
+        assert_eq!(grammar.rule_index("root"), Some(0));
+    }
+
+    #[test]
+    fn test_parse_rule_references_and_comments() {
+        let source = "# Voice commands\n\
+                      root ::= action \" \" object # trailing comment\n\
+                      action ::= \"on\" | \"off\"\n\
+                      object ::= \"light\"\n";
+        let grammar = Grammar::parse(source).unwrap();
+
+        assert_eq!(grammar.rule_index("action"), Some(1));
+        assert_eq!(grammar.rule_index("object"), Some(2));
+        assert_eq!(
+            grammar.rules()[0],
+            [vec![rule_ref(1)], chars(" "), vec![rule_ref(2), end()]].concat()
+        );
+        assert_eq!(
+            grammar.rules()[1],
+            [chars("on"), vec![alt()], chars("off"), vec![end()]].concat()
+        );
+    }
+
+    #[test]
+    fn test_parse_character_classes() {
+        let grammar = Grammar::parse("root ::= [a-z0-9_] [^\\n]").unwrap();
+
+        assert_eq!(
+            grammar.rules()[0],
+            vec![
+                element(WHISPER_GRETYPE_CHAR, 'a' as u32),
+                element(WHISPER_GRETYPE_CHAR_RNG_UPPER, 'z' as u32),
+                element(WHISPER_GRETYPE_CHAR_ALT, '0' as u32),
+                element(WHISPER_GRETYPE_CHAR_RNG_UPPER, '9' as u32),
+                element(WHISPER_GRETYPE_CHAR_ALT, '_' as u32),
+                element(WHISPER_GRETYPE_CHAR_NOT, '\n' as u32),
+                end(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_repetition_generates_rules() {
+        let grammar = Grammar::parse("root ::= digit+ \"!\"?\ndigit ::= [0-9]\n").unwrap();
+
+        // root = 0, digit = 1, generated: root_2 (digit+), root_3 ("!"?)
+        assert_eq!(grammar.rule_index("root_2"), Some(2));
+        assert_eq!(grammar.rules()[0], vec![rule_ref(2), rule_ref(3), end()]);
+        assert_eq!(
+            grammar.rules()[2],
+            vec![rule_ref(1), rule_ref(2), alt(), rule_ref(1), end()]
+        );
+        assert_eq!(
+            grammar.rules()[3],
+            [chars("!"), vec![alt(), end()]].concat()
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_group() {
+        let grammar = Grammar::parse("root ::= (\"a\" | \"b\")*").unwrap();
+
+        // root_1 is the group, root_2 its repetition
+        assert_eq!(grammar.rules()[0], vec![rule_ref(2), end()]);
+        assert_eq!(
+            grammar.rules()[1],
+            [chars("a"), vec![alt()], chars("b"), vec![end()]].concat()
+        );
+        assert_eq!(
+            grammar.rules()[2],
+            vec![rule_ref(1), rule_ref(2), alt(), end()]
+        );
+    }
+
+    #[test]
+    fn test_parse_escapes_and_utf8() {
+        let grammar = Grammar::parse("root ::= \"\\x41\\u00e9\\\"\\n\" \"ж\"").unwrap();
+
+        assert_eq!(
+            grammar.rules()[0],
+            [chars("Aé\"\n"), chars("ж"), vec![end()]].concat()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let undefined = Grammar::parse("root ::= missing").unwrap_err();
+        assert!(undefined.to_string().contains("missing"));
+
+        assert!(Grammar::parse("root = \"a\"").is_err());
+        assert!(Grammar::parse("root ::= \"unterminated").is_err());
+        assert!(Grammar::parse("root ::= *").is_err());
+        assert!(Grammar::parse("root ::= (\"a\"").is_err());
+        assert!(Grammar::parse("root ::= \"\\q\"").is_err());
+    }
+}
@@ -0,0 +1,670 @@
+//! HTTP backend for OpenAI-compatible transcription servers
+//!
+//! Sends audio to a `/v1/audio/transcriptions` endpoint (OpenAI, whisper.cpp
+//! server, faster-whisper-server, ...) as a 16-bit WAV upload and converts the
+//! `verbose_json` response into a `TranscriptionResult`. Chunking, merging and
+//! quality retries run locally through `pipeline`, as with the whisper backend.
+
+use super::backend::TranscriptionBackend;
+use super::format::language_code;
+use super::options::TranscribeOptions;
+use super::prompt::prompt_with_context;
+use super::quality::compression_ratio;
+use super::result::{Segment, Token, TranscriptionResult};
+use crate::infrastructure::audio::{AudioProcessor, WHISPER_SAMPLE_RATE};
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use reqwest::blocking::{multipart, Client};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Model name sent to the server unless another one is set
+pub const DEFAULT_HTTP_MODEL: &str = "whisper-1";
+
+/// Timeout for a single transcription request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Backend sending audio to an OpenAI-compatible transcription server
+///
+/// # Example
+///
+/// ```ignore
+/// let backend = HttpBackend::new("http://localhost:8080")?.model("whisper-1");
+/// let result = pipeline::transcribe_chunked(&backend, &audio, &options, &config, |_, _| {})?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpBackend {
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+    client: Client,
+}
+
+impl HttpBackend {
+    /// Create a backend for a server
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - Base URL of the server (e.g. `http://localhost:8080`); a
+    ///   trailing `/v1` or full `/v1/audio/transcriptions` path is accepted too
+    ///
+    /// # Returns
+    ///
+    /// Result containing the backend, or an error if the URL is not http(s)
+    pub fn new(endpoint: impl Into<String>) -> Result<Self> {
+        let endpoint = endpoint.into();
+        if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+            return Err(anyhow!(
+                "Invalid endpoint '{}': expected an http:// or https:// URL",
+                endpoint
+            ));
+        }
+
+        let endpoint = endpoint.trim_end_matches('/');
+        let endpoint = ["/audio/transcriptions", "/audio/translations"]
+            .iter()
+            .find_map(|path| endpoint.strip_suffix(path))
+            .unwrap_or(endpoint);
+        let endpoint = endpoint.strip_suffix("/v1").unwrap_or(endpoint);
+
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        Ok(HttpBackend {
+            endpoint: endpoint.to_string(),
+            model: DEFAULT_HTTP_MODEL.to_string(),
+            api_key: None,
+            client,
+        })
+    }
+
+    /// Set the model name sent with each request
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Set the API key sent as bearer token
+    pub fn api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    /// Get the URL audio is posted to
+    ///
+    /// # Arguments
+    ///
+    /// * `translate` - Use the translation endpoint instead of transcription
+    pub fn url(&self, translate: bool) -> String {
+        let task = if translate {
+            "translations"
+        } else {
+            "transcriptions"
+        };
+        format!("{}/v1/audio/{}", self.endpoint, task)
+    }
+
+    /// Build the multipart form for a request
+    fn form(&self, samples: &[f32], options: &TranscribeOptions) -> Result<multipart::Form> {
+        let wav = AudioProcessor::encode_wav(samples)?;
+        let file = multipart::Part::bytes(wav)
+            .file_name("audio.wav")
+            .mime_str("audio/wav")
+            .context("Failed to build audio upload")?;
+
+        let mut form = multipart::Form::new()
+            .part("file", file)
+            .text("model", self.model.clone())
+            .text("response_format", "verbose_json")
+            .text("temperature", options.temperature.to_string())
+            .text("timestamp_granularities[]", "segment")
+            .text("timestamp_granularities[]", "word");
+
+        // The translation endpoint always translates from the detected language
+        if !options.translate {
+            if let Some(language) = options.language.as_deref().filter(|l| !l.is_empty()) {
+                form = form.text("language", language.to_string());
+            }
+        }
+        if let Some(prompt) = prompt_with_context(
+            options.initial_prompt.as_deref(),
+            options.chunk_context.as_deref(),
+        ) {
+            form = form.text("prompt", prompt);
+        }
+
+        Ok(form)
+    }
+}
+
+impl TranscriptionBackend for HttpBackend {
+    fn transcribe(
+        &self,
+        samples: &[f32],
+        options: &TranscribeOptions,
+    ) -> Result<TranscriptionResult> {
+        let url = self.url(options.translate);
+        debug!("Posting {} samples to {}", samples.len(), url);
+
+        let mut request = self
+            .client
+            .post(&url)
+            .multipart(self.form(samples, options)?);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request
+            .send()
+            .with_context(|| format!("Failed to send request to {}", url))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .with_context(|| format!("Failed to read response from {}", url))?;
+        if !status.is_success() {
+            return Err(anyhow!(
+                "Server returned {}: {}",
+                status,
+                body.trim().chars().take(500).collect::<String>()
+            ));
+        }
+
+        let duration_ms = samples.len() as i64 * 1000 / WHISPER_SAMPLE_RATE as i64;
+        let mut result = parse_verbose_json(&body, duration_ms)?;
+        if result.language.is_empty() {
+            result.language = options.language.clone().unwrap_or_default();
+            for segment in &mut result.segments {
+                segment.language = result.language.clone();
+            }
+        }
+
+        if let Some(on_segment) = &options.on_segment {
+            for segment in &result.segments {
+                on_segment.emit(segment.clone());
+            }
+        }
+        if let Some(progress) = &options.progress {
+            progress.report(1.0);
+        }
+
+        Ok(result)
+    }
+}
+
+/// Options that only the whisper backend supports and the HTTP backend ignores
+///
+/// # Returns
+///
+/// Names of the command line options that are set but have no effect
+pub fn unsupported_options(options: &TranscribeOptions) -> Vec<&'static str> {
+    let mut unsupported = Vec::new();
+    if options.beam_size.is_some() {
+        unsupported.push("--beam-size");
+    }
+    if options.suppress_regex.is_some() {
+        unsupported.push("--suppress-regex");
+    }
+    if !options.boost_words.is_empty() {
+        unsupported.push("--boost-words");
+    }
+    if options.grammar.is_some() {
+        unsupported.push("--grammar");
+    }
+    if options.vad.is_some() {
+        unsupported.push("--vad");
+    }
+    if options.max_len > 0 {
+        unsupported.push("--max-len");
+    }
+    if options.speaker_turns {
+        unsupported.push("--speaker-turns");
+    }
+    unsupported
+}
+
+/// `verbose_json` response body
+#[derive(Debug, Deserialize)]
+struct VerboseResponse {
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    segments: Vec<VerboseSegment>,
+    #[serde(default)]
+    words: Vec<VerboseWord>,
+}
+
+/// Segment of a `verbose_json` response
+#[derive(Debug, Deserialize)]
+struct VerboseSegment {
+    start: f64,
+    end: f64,
+    text: String,
+    #[serde(default)]
+    avg_logprob: Option<f32>,
+    #[serde(default)]
+    compression_ratio: Option<f32>,
+    #[serde(default)]
+    no_speech_prob: Option<f32>,
+    #[serde(default)]
+    words: Vec<VerboseWord>,
+}
+
+/// Word of a `verbose_json` response, top-level (OpenAI) or per segment
+#[derive(Debug, Clone, Deserialize)]
+struct VerboseWord {
+    word: String,
+    start: f64,
+    end: f64,
+    #[serde(default)]
+    probability: Option<f32>,
+}
+
+/// Parse a `verbose_json` response into a transcription result
+///
+/// Words, if the server reports them, become the segment's tokens, so word
+/// timestamps and review flags work as with the whisper backend. Missing
+/// statistics are derived from what is there: confidence from word
+/// probabilities or `avg_logprob`, compression ratio from the text.
+///
+/// # Arguments
+///
+/// * `body` - Response body
+/// * `duration_ms` - Duration of the sent audio, used when the server returns text without segments
+///
+/// # Returns
+///
+/// Result containing the transcription result (timestamps relative to the sent audio)
+pub fn parse_verbose_json(body: &str, duration_ms: i64) -> Result<TranscriptionResult> {
+    let response: VerboseResponse =
+        serde_json::from_str(body).context("Failed to parse verbose_json response")?;
+    let language = response
+        .language
+        .as_deref()
+        .map(language_code)
+        .unwrap_or_default();
+
+    let mut raw_segments = response.segments;
+    if raw_segments.is_empty() && !response.text.trim().is_empty() {
+        raw_segments.push(VerboseSegment {
+            start: 0.0,
+            end: duration_ms as f64 / 1000.0,
+            text: response.text.clone(),
+            avg_logprob: None,
+            compression_ratio: None,
+            no_speech_prob: None,
+            words: Vec::new(),
+        });
+    }
+
+    let segments = raw_segments
+        .into_iter()
+        .enumerate()
+        .map(|(id, mut raw)| {
+            if raw.words.is_empty() {
+                raw.words = response
+                    .words
+                    .iter()
+                    .filter(|word| word.start >= raw.start && word.start < raw.end)
+                    .cloned()
+                    .collect();
+            }
+            convert_segment(id as i32, raw, &language)
+        })
+        .collect();
+
+    Ok(TranscriptionResult {
+        language,
+        segments,
+        ..Default::default()
+    })
+}
+
+/// Build a segment from a `verbose_json` segment and its words
+fn convert_segment(id: i32, raw: VerboseSegment, language: &str) -> Segment {
+    let word_probs: Vec<f32> = raw
+        .words
+        .iter()
+        .filter_map(|word| word.probability)
+        .collect();
+    let word_avg_logprob = (!word_probs.is_empty()).then(|| {
+        word_probs
+            .iter()
+            .map(|p| p.max(f32::MIN_POSITIVE).ln())
+            .sum::<f32>()
+            / word_probs.len() as f32
+    });
+
+    let avg_logprob = raw.avg_logprob.or(word_avg_logprob).unwrap_or(0.0);
+    let confidence = word_avg_logprob
+        .or(Some(avg_logprob))
+        .map_or(1.0, |logprob| logprob.exp().clamp(0.0, 1.0));
+    let min_token_prob = word_probs.iter().copied().fold(confidence, f32::min);
+
+    let tokens = raw
+        .words
+        .iter()
+        .map(|word| {
+            let probability = word.probability.unwrap_or(confidence);
+            Token {
+                id: -1,
+                // Whisper tokens starting a word begin with a space
+                text: if word.word.starts_with(' ') {
+                    word.word.clone()
+                } else {
+                    format!(" {}", word.word)
+                },
+                probability,
+                logprob: probability.max(f32::MIN_POSITIVE).ln(),
+                start_ms: seconds_to_ms(word.start),
+                end_ms: seconds_to_ms(word.end),
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    Segment {
+        id,
+        start_ms: seconds_to_ms(raw.start),
+        end_ms: seconds_to_ms(raw.end),
+        compression_ratio: raw
+            .compression_ratio
+            .unwrap_or_else(|| compression_ratio(&raw.text)),
+        text: raw.text,
+        confidence,
+        avg_logprob,
+        min_token_prob,
+        no_speech_prob: raw.no_speech_prob.unwrap_or(0.0),
+        tokens,
+        language: language.to_string(),
+        speaker_turn_next: false,
+    }
+}
+
+/// Convert a timestamp in seconds to milliseconds
+fn seconds_to_ms(seconds: f64) -> i64 {
+    (seconds * 1000.0).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::transcription::format::format_verbose_json;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// Request captured by the stub server
+    struct CapturedRequest {
+        request_line: String,
+        headers: Vec<String>,
+        body: String,
+    }
+
+    /// Serve one canned response per request on a local port
+    ///
+    /// Returns the server URL and a receiver for the captured requests.
+    fn stub_server(responses: Vec<(u16, String)>) -> (String, mpsc::Receiver<CapturedRequest>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut headers = Vec::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end().to_string();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                    headers.push(line);
+                }
+                let mut request_body = vec![0; content_length];
+                reader.read_exact(&mut request_body).unwrap();
+
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+
+                let _ = tx.send(CapturedRequest {
+                    request_line: request_line.trim_end().to_string(),
+                    headers,
+                    body: String::from_utf8_lossy(&request_body).into_owned(),
+                });
+            }
+        });
+
+        (url, rx)
+    }
+
+    const OPENAI_RESPONSE: &str = r#"{
+        "task": "transcribe",
+        "language": "english",
+        "duration": 4.2,
+        "text": "Hello there. General Kenobi.",
+        "segments": [
+            {"id": 0, "seek": 0, "start": 0.0, "end": 1.5, "text": " Hello there.",
+             "tokens": [50364, 2425], "temperature": 0.0, "avg_logprob": -0.2,
+             "compression_ratio": 0.9, "no_speech_prob": 0.01},
+            {"id": 1, "seek": 0, "start": 1.5, "end": 4.2, "text": " General Kenobi.",
+             "tokens": [6996], "temperature": 0.0, "avg_logprob": -0.4,
+             "compression_ratio": 0.8, "no_speech_prob": 0.02}
+        ],
+        "words": [
+            {"word": "Hello", "start": 0.0, "end": 0.6},
+            {"word": "there.", "start": 0.6, "end": 1.5},
+            {"word": "General", "start": 1.5, "end": 2.8},
+            {"word": "Kenobi.", "start": 2.8, "end": 4.2}
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_openai_verbose_json() {
+        let result = parse_verbose_json(OPENAI_RESPONSE, 4200).unwrap();
+
+        assert_eq!(result.language, "en");
+        assert_eq!(result.segments.len(), 2);
+
+        let segment = &result.segments[1];
+        assert_eq!((segment.start_ms, segment.end_ms), (1500, 4200));
+        assert_eq!(segment.text, " General Kenobi.");
+        assert_eq!(segment.avg_logprob, -0.4);
+        assert!((segment.confidence - (-0.4f32).exp()).abs() < 1e-6);
+        assert_eq!(segment.language, "en");
+
+        let words = segment.words();
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[1].text, "Kenobi.");
+        assert_eq!((words[1].start_ms, words[1].end_ms), (2800, 4200));
+    }
+
+    #[test]
+    fn test_parse_segment_words_and_plain_text() {
+        // whisper.cpp server style: codes, per-segment words with probabilities
+        let body = r#"{"language": "de", "segments": [
+            {"start": 0.0, "end": 1.0, "text": " Guten Tag",
+             "words": [{"word": " Guten", "start": 0.0, "end": 0.4, "probability": 0.5},
+                       {"word": " Tag", "start": 0.4, "end": 1.0, "probability": 0.8}]}
+        ]}"#;
+        let result = parse_verbose_json(body, 1000).unwrap();
+        let segment = &result.segments[0];
+        assert_eq!(result.language, "de");
+        assert!((segment.confidence - (0.5f32 * 0.8).sqrt()).abs() < 1e-5);
+        assert_eq!(segment.min_token_prob, 0.5);
+        assert_eq!(segment.words()[0].text, "Guten");
+
+        // Text without segments covers the whole audio
+        let result = parse_verbose_json(r#"{"text": "hi"}"#, 3000).unwrap();
+        assert_eq!(result.segments.len(), 1);
+        assert_eq!(result.segments[0].end_ms, 3000);
+        assert_eq!(result.language, "");
+
+        assert!(parse_verbose_json("not json", 0).is_err());
+    }
+
+    #[test]
+    fn test_verbose_json_round_trip() {
+        let original = parse_verbose_json(OPENAI_RESPONSE, 4200).unwrap();
+        let body = format_verbose_json(&original, "transcribe", 4200, true).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["language"], "english");
+        assert_eq!(json["text"], "Hello there. General Kenobi.");
+        assert_eq!(json["words"][3]["word"], "Kenobi.");
+
+        let parsed = parse_verbose_json(&body, 4200).unwrap();
+        assert_eq!(parsed.language, "en");
+        assert_eq!(parsed.segments.len(), 2);
+        assert_eq!(parsed.segments[1].start_ms, 1500);
+        assert_eq!(parsed.segments[1].text, " General Kenobi.");
+        assert_eq!(parsed.segments[1].words().len(), 2);
+    }
+
+    #[test]
+    fn test_endpoint_url() {
+        let backend = HttpBackend::new("http://localhost:8080/").unwrap();
+        assert_eq!(
+            backend.url(false),
+            "http://localhost:8080/v1/audio/transcriptions"
+        );
+        assert_eq!(
+            backend.url(true),
+            "http://localhost:8080/v1/audio/translations"
+        );
+
+        for endpoint in [
+            "https://api.example.com/v1",
+            "https://api.example.com/v1/audio/transcriptions",
+        ] {
+            assert_eq!(
+                HttpBackend::new(endpoint).unwrap().url(false),
+                "https://api.example.com/v1/audio/transcriptions"
+            );
+        }
+
+        assert!(HttpBackend::new("localhost:8080").is_err());
+    }
+
+    #[test]
+    fn test_transcribe_posts_wav_multipart() {
+        let (url, requests) = stub_server(vec![(200, OPENAI_RESPONSE.to_string())]);
+        let backend = HttpBackend::new(url)
+            .unwrap()
+            .model("large-v3")
+            .api_key(Some("secret".to_string()));
+        let mut options = TranscribeOptions::new()
+            .language("en")
+            .initial_prompt("Kenobi");
+        options.chunk_context = Some("Hello there.".to_string());
+
+        let result = backend
+            .transcribe(&vec![0.0; WHISPER_SAMPLE_RATE as usize], &options)
+            .unwrap();
+        assert_eq!(result.segments.len(), 2);
+
+        let request = requests.recv().unwrap();
+        assert_eq!(
+            request.request_line,
+            "POST /v1/audio/transcriptions HTTP/1.1"
+        );
+        assert!(request
+            .headers
+            .iter()
+            .any(|h| h.eq_ignore_ascii_case("authorization: Bearer secret")));
+        for expected in [
+            "filename=\"audio.wav\"",
+            "Content-Type: audio/wav",
+            "RIFF",
+            "name=\"model\"\r\n\r\nlarge-v3",
+            "name=\"response_format\"\r\n\r\nverbose_json",
+            "name=\"language\"\r\n\r\nen",
+            "name=\"prompt\"\r\n\r\nKenobi Hello there.",
+        ] {
+            assert!(request.body.contains(expected), "missing {}", expected);
+        }
+    }
+
+    #[test]
+    fn test_server_error_is_reported() {
+        let (url, _requests) = stub_server(vec![(500, r#"{"error": "model not loaded"}"#.into())]);
+        let backend = HttpBackend::new(url).unwrap();
+
+        let error = backend
+            .transcribe(&[0.0; 160], &TranscribeOptions::new())
+            .unwrap_err();
+        assert!(error.to_string().contains("500"));
+        assert!(error.to_string().contains("model not loaded"));
+    }
+
+    #[test]
+    fn test_chunked_transcription_over_http() {
+        use crate::infrastructure::audio::{AudioSamples, ChunkConfig};
+        use crate::infrastructure::transcription::pipeline;
+
+        let first = r#"{"language": "english", "segments": [
+            {"start": 0.0, "end": 5.0, "text": " First chunk."}]}"#;
+        let second = r#"{"language": "english", "segments": [
+            {"start": 2.0, "end": 6.0, "text": " Second chunk."}]}"#;
+        let (url, requests) = stub_server(vec![(200, first.into()), (200, second.into())]);
+        let backend = HttpBackend::new(url).unwrap();
+        let audio = AudioSamples {
+            samples: vec![0.0; WHISPER_SAMPLE_RATE as usize * 20],
+            original_sample_rate: WHISPER_SAMPLE_RATE,
+            original_channels: 1,
+            duration_seconds: 20.0,
+        };
+
+        let result = pipeline::transcribe_chunked(
+            &backend,
+            &audio,
+            &TranscribeOptions::new(),
+            &ChunkConfig::new(12, 2),
+            |_, _| {},
+        )
+        .unwrap();
+
+        let spans: Vec<(i64, i64)> = result
+            .segments
+            .iter()
+            .map(|segment| (segment.start_ms, segment.end_ms))
+            .collect();
+        assert_eq!(spans, vec![(0, 5000), (12000, 16000)]);
+
+        // The language detected on the first chunk is sent with the second
+        requests.recv().unwrap();
+        let second_request = requests.recv().unwrap();
+        assert!(second_request.body.contains("name=\"language\"\r\n\r\nen"));
+    }
+
+    #[test]
+    fn test_unsupported_options() {
+        assert!(unsupported_options(&TranscribeOptions::new()).is_empty());
+        let options = TranscribeOptions::new()
+            .beam_size(5)
+            .boost_words(vec!["OrangeNote".to_string()]);
+        assert_eq!(
+            unsupported_options(&options),
+            vec!["--beam-size", "--boost-words"]
+        );
+    }
+}
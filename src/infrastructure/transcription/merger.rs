@@ -3,7 +3,7 @@
 //! This module handles merging transcription results from multiple audio chunks,
 //! including timestamp adjustment and deduplication of overlapping segments.
 
-use super::context::{Segment, TranscriptionResult};
+use super::result::{Segment, Token, TranscriptionResult};
 use log::{debug, info};
 use std::collections::{HashMap, HashSet};
 
@@ -72,7 +72,7 @@ pub fn merge_transcription_results(
         return MergeResult {
             result: TranscriptionResult {
                 language: "unknown".to_string(),
-                segments: vec![],
+                ..Default::default()
             },
             total_segments_before: 0,
             duplicates_removed: 0,
@@ -81,6 +81,11 @@ pub fn merge_transcription_results(
     }
 
     let chunks_merged = results.len();
+    let partial = results.iter().any(|(result, _)| result.partial);
+    let quality_retries = results
+        .iter()
+        .flat_map(|(result, _)| result.quality_retries.iter().cloned())
+        .collect();
 
     // Step 1: Determine the most common language
     let language = determine_language(&results);
@@ -95,9 +100,17 @@ pub fn merge_transcription_results(
                     id: 0, // Will be reassigned later
                     start_ms: segment.start_ms + start_offset_ms,
                     end_ms: segment.end_ms + start_offset_ms,
-                    text: segment.text,
-                    confidence: segment.confidence,
-                    tokens: segment.tokens,
+                    tokens: segment
+                        .tokens
+                        .into_iter()
+                        .map(|token| Token {
+                            start_ms: token.start_ms + start_offset_ms,
+                            end_ms: token.end_ms + start_offset_ms,
+                            dtw_ms: token.dtw_ms.map(|ms| ms + start_offset_ms),
+                            ..token
+                        })
+                        .collect(),
+                    ..segment
                 },
                 chunk_index: chunk_idx,
                 _original_start_ms: segment.start_ms,
@@ -136,6 +149,8 @@ pub fn merge_transcription_results(
         result: TranscriptionResult {
             language,
             segments: final_segments,
+            partial,
+            quality_retries,
         },
         total_segments_before,
         duplicates_removed,
@@ -322,14 +337,22 @@ mod tests {
             end_ms,
             text: text.to_string(),
             confidence,
-            tokens: vec![],
+            language: "en".to_string(),
+            ..Default::default()
         }
     }
 
     fn make_result(language: &str, segments: Vec<Segment>) -> TranscriptionResult {
         TranscriptionResult {
             language: language.to_string(),
-            segments,
+            segments: segments
+                .into_iter()
+                .map(|segment| Segment {
+                    language: language.to_string(),
+                    ..segment
+                })
+                .collect(),
+            ..Default::default()
         }
     }
 
@@ -530,4 +553,44 @@ mod tests {
         assert_eq!(merged.result.segments.len(), 1);
         assert!(merged.result.segments[0].confidence > 0.9);
     }
+
+    #[test]
+    fn test_merge_keeps_chunk_languages() {
+        let result1 = make_result("ru", vec![make_segment(0, 0, 5000, "Привет всем", 0.9)]);
+        let result2 = make_result("en", vec![make_segment(0, 0, 5000, "Let's switch", 0.9)]);
+        let result3 = make_result("ru", vec![make_segment(0, 0, 5000, "И обратно", 0.9)]);
+
+        let results = vec![(result1, 0), (result2, 10000), (result3, 20000)];
+        let merged = merge_transcription_results(results, MergeConfig::default());
+
+        assert_eq!(merged.result.language, "ru");
+        let languages: Vec<&str> = merged
+            .result
+            .segments
+            .iter()
+            .map(|s| s.language.as_str())
+            .collect();
+        assert_eq!(languages, vec!["ru", "en", "ru"]);
+    }
+
+    #[test]
+    fn test_merge_offsets_token_timestamps() {
+        let mut seg = make_segment(0, 1000, 2000, "Hello", 0.9);
+        seg.tokens = vec![Token {
+            text: " Hello".to_string(),
+            probability: 0.9,
+            start_ms: 1000,
+            end_ms: 2000,
+            dtw_ms: Some(1100),
+            ..Default::default()
+        }];
+        let results = vec![(make_result("en", vec![seg]), 60000)];
+
+        let merged = merge_transcription_results(results, MergeConfig::default());
+        let token = &merged.result.segments[0].tokens[0];
+
+        assert_eq!(token.start_ms, 61000);
+        assert_eq!(token.end_ms, 62000);
+        assert_eq!(token.dtw_ms, Some(61100));
+    }
 }
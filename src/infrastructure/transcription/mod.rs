@@ -1,13 +1,43 @@
 //! Transcription backend module
 //!
-//! This module contains implementations for different transcription backends.
-//! Currently supports whisper.cpp via the whisper-rs crate.
+//! Backend-independent transcription building blocks (options, results, quality
+//! checks, chunk merging, output formatting) and the `TranscriptionBackend` trait
+//! they are driven through. The whisper.cpp backend lives in `whisper`.
+
+pub mod backend;
+pub mod cancel;
+pub mod format;
+pub mod grammar;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod merger;
+pub mod options;
+pub mod pipeline;
+pub mod progress;
+pub mod prompt;
+pub mod quality;
+pub mod result;
+pub mod review;
+pub mod stream;
 
 #[cfg(feature = "whisper")]
 pub mod whisper;
 
+pub use backend::{MockBackend, TranscriptionBackend};
+pub use cancel::CancellationToken;
+pub use format::FormatOptions;
+pub use grammar::Grammar;
+#[cfg(feature = "http")]
+pub use http::HttpBackend;
+pub use options::{TranscribeOptions, VadOptions};
+pub use progress::ProgressCallback;
+pub use quality::QualityConfig;
+pub use result::{Segment, Token, TranscriptionResult, Word};
+pub use review::ReviewItem;
+pub use stream::SegmentCallback;
+
 #[cfg(feature = "whisper")]
 pub use whisper::{
-    ModelSize, ModelSource, Segment, Token, TranscriptionResult, WhisperContextWrapper,
-    WhisperModelManager, WhisperTranscriber,
+    LanguageDetection, LanguageProbability, ModelSize, ModelSource, PooledState,
+    WhisperContextWrapper, WhisperModelManager, WhisperState, WhisperStatePool, WhisperTranscriber,
 };
@@ -0,0 +1,725 @@
+//! Transcription options
+//!
+//! This module provides `TranscribeOptions`, a builder for the decoding parameters
+//! passed to a transcription backend. It replaces the positional `(language, translate)`
+//! arguments of the transcription methods and exposes the sampling and fallback
+//! settings of whisper.cpp's `whisper_full_params`; other backends ignore the settings
+//! they do not support.
+
+use super::cancel::CancellationToken;
+use super::grammar::{Grammar, DEFAULT_START_RULE};
+use super::progress::ProgressCallback;
+use super::quality::QualityConfig;
+use super::stream::SegmentCallback;
+#[cfg(feature = "whisper")]
+use super::whisper::ffi;
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// Decoding options for a transcription run
+///
+/// Defaults match `whisper_full_default_params` from whisper.cpp, so an unmodified
+/// `TranscribeOptions` behaves exactly like the previous hard-coded greedy setup.
+///
+/// # Example
+///
+/// ```ignore
+/// let options = TranscribeOptions::new()
+///     .language("en")
+///     .beam_size(5)
+///     .temperature(0.0)
+///     .no_fallback();
+/// let result = transcriber.transcribe_file("meeting.mp3", &options)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct TranscribeOptions {
+    /// Language code (e.g., "en", "ru"). None for auto-detect
+    pub language: Option<String>,
+    /// Whether to translate to English
+    pub translate: bool,
+    /// Number of threads. None to use the transcriber's setting
+    pub threads: Option<usize>,
+    /// Beam size for beam search. None for greedy sampling
+    pub beam_size: Option<usize>,
+    /// Number of candidates to sample when using greedy sampling with temperature > 0
+    pub best_of: usize,
+    /// Initial decoding temperature
+    pub temperature: f32,
+    /// Temperature increment used when a decode fails the fallback thresholds (0.0 = no fallback)
+    pub temperature_inc: f32,
+    /// Entropy threshold for fallback (similar to OpenAI's compression ratio threshold)
+    pub entropy_thold: f32,
+    /// Average log probability threshold for fallback
+    pub logprob_thold: f32,
+    /// No-speech probability threshold for treating a window as silence
+    pub no_speech_thold: f32,
+    /// Maximum segment length in characters (0 = no limit)
+    pub max_len: usize,
+    /// Split segments on word boundaries rather than tokens (used with `max_len`)
+    pub split_on_word: bool,
+    /// Suppress blank outputs at the beginning of sampling
+    pub suppress_blank: bool,
+    /// Maximum number of tokens from past text to use as decoder prompt
+    pub n_max_text_ctx: usize,
+    /// Initial prompt (e.g., vocabulary hints). Truncated to fit the text context
+    pub initial_prompt: Option<String>,
+    /// Prepend the initial prompt to every decode window, not only the first one
+    pub carry_initial_prompt: bool,
+    /// Number of trailing words from the previous chunk passed as context to the
+    /// next chunk in chunked transcription (0 = chunks are transcribed cold)
+    pub chunk_context_words: usize,
+    /// Text carried over from the previous chunk, set by chunked transcription.
+    /// Placed right after the initial prompt and kept when the prompt is truncated
+    pub chunk_context: Option<String>,
+    /// Voice activity detection settings. None to decode the whole input
+    pub vad: Option<VadOptions>,
+    /// Re-detect the language for every chunk in chunked transcription instead of
+    /// locking it to the first confidently detected language (for code-switched audio)
+    pub language_per_chunk: bool,
+    /// Use DTW token timestamps instead of the heuristic ones (requires a DTW-enabled context)
+    pub prefer_dtw_timestamps: bool,
+    /// Remove special tokens such as `[_BEG_]` and `<|endoftext|>` from segment tokens and text
+    pub drop_special_tokens: bool,
+    /// Predict speaker turns (tinydiarize, requires a tdrz model)
+    pub speaker_turns: bool,
+    /// Regex of tokens whisper must never emit (e.g., "♪+|Subtitles by.*")
+    pub suppress_regex: Option<String>,
+    /// Words whose tokens get a logit bias while decoding
+    pub boost_words: Vec<String>,
+    /// Logit bias added to the tokens of `boost_words`
+    pub boost_weight: f32,
+    /// Hallucination checks; failing chunks are decoded again (disabled if `None`)
+    pub quality: Option<QualityConfig>,
+    /// Grammar the decoded text is constrained to
+    pub grammar: Option<Grammar>,
+    /// Grammar rule decoding starts from
+    pub grammar_rule: String,
+    /// Logit penalty for tokens the grammar does not allow (higher = stricter)
+    pub grammar_penalty: f32,
+    /// Token checked during inference and between chunks to stop early with partial results
+    pub cancellation: Option<CancellationToken>,
+    /// Callback receiving inference progress (0.0 - 1.0) over the whole file
+    pub progress: Option<ProgressCallback>,
+    /// Callback receiving each segment as soon as whisper finalizes it
+    pub on_segment: Option<SegmentCallback>,
+}
+
+impl Default for TranscribeOptions {
+    fn default() -> Self {
+        TranscribeOptions {
+            language: None,
+            translate: false,
+            threads: None,
+            beam_size: None,
+            best_of: 5,
+            temperature: 0.0,
+            temperature_inc: 0.2,
+            entropy_thold: 2.4,
+            logprob_thold: -1.0,
+            no_speech_thold: 0.6,
+            max_len: 0,
+            split_on_word: false,
+            suppress_blank: true,
+            n_max_text_ctx: 16384,
+            initial_prompt: None,
+            carry_initial_prompt: false,
+            chunk_context_words: 0,
+            chunk_context: None,
+            vad: None,
+            language_per_chunk: false,
+            prefer_dtw_timestamps: false,
+            drop_special_tokens: false,
+            speaker_turns: false,
+            suppress_regex: None,
+            boost_words: Vec::new(),
+            boost_weight: 2.0,
+            quality: None,
+            grammar: None,
+            grammar_rule: DEFAULT_START_RULE.to_string(),
+            grammar_penalty: 100.0,
+            cancellation: None,
+            progress: None,
+            on_segment: None,
+        }
+    }
+}
+
+impl TranscribeOptions {
+    /// Create options with whisper.cpp defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the language code (e.g., "en", "ru")
+    pub fn language<S: Into<String>>(mut self, language: S) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Enable or disable translation to English
+    pub fn translate(mut self, translate: bool) -> Self {
+        self.translate = translate;
+        self
+    }
+
+    /// Set the number of threads
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Use beam search with the given beam size
+    pub fn beam_size(mut self, beam_size: usize) -> Self {
+        self.beam_size = Some(beam_size);
+        self
+    }
+
+    /// Set the number of greedy candidates
+    pub fn best_of(mut self, best_of: usize) -> Self {
+        self.best_of = best_of;
+        self
+    }
+
+    /// Set the initial decoding temperature
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Set the temperature increment used for fallback
+    pub fn temperature_inc(mut self, temperature_inc: f32) -> Self {
+        self.temperature_inc = temperature_inc;
+        self
+    }
+
+    /// Disable temperature fallback
+    pub fn no_fallback(mut self) -> Self {
+        self.temperature_inc = 0.0;
+        self
+    }
+
+    /// Set the entropy threshold for fallback
+    pub fn entropy_thold(mut self, entropy_thold: f32) -> Self {
+        self.entropy_thold = entropy_thold;
+        self
+    }
+
+    /// Set the average log probability threshold for fallback
+    pub fn logprob_thold(mut self, logprob_thold: f32) -> Self {
+        self.logprob_thold = logprob_thold;
+        self
+    }
+
+    /// Set the no-speech probability threshold
+    pub fn no_speech_thold(mut self, no_speech_thold: f32) -> Self {
+        self.no_speech_thold = no_speech_thold;
+        self
+    }
+
+    /// Set the maximum segment length in characters
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Split segments on word boundaries
+    pub fn split_on_word(mut self, split_on_word: bool) -> Self {
+        self.split_on_word = split_on_word;
+        self
+    }
+
+    /// Enable or disable blank suppression
+    pub fn suppress_blank(mut self, suppress_blank: bool) -> Self {
+        self.suppress_blank = suppress_blank;
+        self
+    }
+
+    /// Set the maximum number of past text tokens used as decoder prompt
+    pub fn n_max_text_ctx(mut self, n_max_text_ctx: usize) -> Self {
+        self.n_max_text_ctx = n_max_text_ctx;
+        self
+    }
+
+    /// Set the initial prompt
+    pub fn initial_prompt<S: Into<String>>(mut self, initial_prompt: S) -> Self {
+        self.initial_prompt = Some(initial_prompt.into());
+        self
+    }
+
+    /// Keep the initial prompt in front of every decode window
+    pub fn carry_initial_prompt(mut self, carry_initial_prompt: bool) -> Self {
+        self.carry_initial_prompt = carry_initial_prompt;
+        self
+    }
+
+    /// Carry the last `words` words of each chunk into the next chunk's prompt
+    pub fn chunk_context_words(mut self, words: usize) -> Self {
+        self.chunk_context_words = words;
+        self
+    }
+
+    /// Enable voice activity detection with the given settings
+    pub fn vad(mut self, vad: VadOptions) -> Self {
+        self.vad = Some(vad);
+        self
+    }
+
+    /// Re-detect the language for every chunk
+    pub fn language_per_chunk(mut self, language_per_chunk: bool) -> Self {
+        self.language_per_chunk = language_per_chunk;
+        self
+    }
+
+    /// Use DTW token timestamps instead of the heuristic ones
+    pub fn prefer_dtw_timestamps(mut self, prefer_dtw_timestamps: bool) -> Self {
+        self.prefer_dtw_timestamps = prefer_dtw_timestamps;
+        self
+    }
+
+    /// Remove special and timestamp tokens from the result
+    pub fn drop_special_tokens(mut self, drop_special_tokens: bool) -> Self {
+        self.drop_special_tokens = drop_special_tokens;
+        self
+    }
+
+    /// Mark speaker turns on segments (requires a tinydiarize model such as small.en-tdrz)
+    pub fn speaker_turns(mut self, speaker_turns: bool) -> Self {
+        self.speaker_turns = speaker_turns;
+        self
+    }
+
+    /// Never emit tokens matching the regex
+    pub fn suppress_regex<S: Into<String>>(mut self, regex: S) -> Self {
+        self.suppress_regex = Some(regex.into());
+        self
+    }
+
+    /// Bias decoding towards the given words
+    pub fn boost_words(mut self, words: Vec<String>) -> Self {
+        self.boost_words = words;
+        self
+    }
+
+    /// Set the logit bias for boosted words
+    pub fn boost_weight(mut self, weight: f32) -> Self {
+        self.boost_weight = weight;
+        self
+    }
+
+    /// Check results for hallucinations and decode failing audio again
+    pub fn quality(mut self, quality: QualityConfig) -> Self {
+        self.quality = Some(quality);
+        self
+    }
+
+    /// Constrain decoding to a GBNF grammar
+    pub fn grammar(mut self, grammar: Grammar) -> Self {
+        self.grammar = Some(grammar);
+        self
+    }
+
+    /// Set the grammar rule decoding starts from (default "root")
+    pub fn grammar_rule<S: Into<String>>(mut self, rule: S) -> Self {
+        self.grammar_rule = rule.into();
+        self
+    }
+
+    /// Set the logit penalty for tokens outside the grammar
+    pub fn grammar_penalty(mut self, penalty: f32) -> Self {
+        self.grammar_penalty = penalty;
+        self
+    }
+
+    /// Stop the transcription early when the token is cancelled
+    pub fn cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Report inference progress to the given callback
+    pub fn progress(mut self, progress: ProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Stream finalized segments to the given callback
+    pub fn on_segment(mut self, on_segment: SegmentCallback) -> Self {
+        self.on_segment = Some(on_segment);
+        self
+    }
+
+    /// Check whether cancellation was requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Check that the options are within the ranges whisper.cpp accepts
+    pub fn validate(&self) -> Result<()> {
+        if let Some(beam_size) = self.beam_size {
+            if beam_size == 0 {
+                return Err(anyhow!("Beam size must be at least 1"));
+            }
+        }
+        if self.best_of == 0 {
+            return Err(anyhow!("best_of must be at least 1"));
+        }
+        if self.temperature < 0.0 {
+            return Err(anyhow!(
+                "Temperature must be non-negative (got {})",
+                self.temperature
+            ));
+        }
+        if self.temperature_inc < 0.0 {
+            return Err(anyhow!(
+                "Temperature increment must be non-negative (got {})",
+                self.temperature_inc
+            ));
+        }
+        if let Some(0) = self.threads {
+            return Err(anyhow!("Thread count must be at least 1"));
+        }
+        if let Some(vad) = &self.vad {
+            vad.validate()?;
+        }
+        if let Some(regex) = &self.suppress_regex {
+            check_suppress_regex(regex)?;
+        }
+        if !self.boost_weight.is_finite() {
+            return Err(anyhow!("Boost weight must be a finite number"));
+        }
+        if let Some(grammar) = &self.grammar {
+            if grammar.rule_index(&self.grammar_rule).is_none() {
+                return Err(anyhow!("Grammar has no rule named '{}'", self.grammar_rule));
+            }
+        }
+        if self.grammar_penalty < 0.0 {
+            return Err(anyhow!(
+                "Grammar penalty must be non-negative (got {})",
+                self.grammar_penalty
+            ));
+        }
+        if self.language_per_chunk && self.language.is_some() {
+            return Err(anyhow!(
+                "Per-chunk language detection cannot be combined with a fixed language"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sampling strategy implied by these options
+    #[cfg(feature = "whisper")]
+    pub(crate) fn strategy(&self) -> i32 {
+        if self.beam_size.is_some() {
+            ffi::WHISPER_SAMPLING_BEAM_SEARCH
+        } else {
+            ffi::WHISPER_SAMPLING_GREEDY
+        }
+    }
+
+    /// Copy the scalar options into whisper full params
+    ///
+    /// Pointer fields (language, prompts, callbacks) are owned by the caller and must
+    /// be set separately so that they outlive the `whisper_full` call.
+    #[cfg(feature = "whisper")]
+    pub(crate) fn apply_to(&self, params: &mut ffi::WhisperFullParams) {
+        if let Some(threads) = self.threads {
+            params.n_threads = threads as i32;
+        }
+        params.translate = self.translate;
+        params.n_max_text_ctx = self.n_max_text_ctx as i32;
+
+        if let Some(beam_size) = self.beam_size {
+            params.beam_search.beam_size = beam_size as i32;
+        }
+        params.greedy.best_of = self.best_of as i32;
+
+        params.temperature = self.temperature;
+        params.temperature_inc = self.temperature_inc;
+        params.entropy_thold = self.entropy_thold;
+        params.logprob_thold = self.logprob_thold;
+        params.no_speech_thold = self.no_speech_thold;
+
+        params.max_len = self.max_len as i32;
+        params.split_on_word = self.split_on_word;
+        params.suppress_blank = self.suppress_blank;
+        params.carry_initial_prompt = self.carry_initial_prompt;
+        params.tdrz_enable = self.speaker_turns;
+        params.grammar_penalty = self.grammar_penalty;
+        if let Some(start_rule) = self
+            .grammar
+            .as_ref()
+            .and_then(|grammar| grammar.rule_index(&self.grammar_rule))
+        {
+            params.i_start_rule = start_rule;
+        }
+
+        params.vad = self.vad.is_some();
+        if let Some(vad) = &self.vad {
+            vad.apply_to(&mut params.vad_params);
+        }
+    }
+}
+
+/// Voice activity detection settings for whisper.cpp's built-in Silero VAD
+///
+/// With VAD enabled whisper.cpp only decodes the detected speech regions, which
+/// skips long silences and reduces hallucinations on music and noise. Segment
+/// timestamps are still reported relative to the original audio.
+#[derive(Debug, Clone)]
+pub struct VadOptions {
+    /// Path to the ggml Silero VAD model
+    pub model_path: PathBuf,
+    /// Speech probability threshold (0.0 - 1.0)
+    pub threshold: f32,
+    /// Minimum duration of a speech region in milliseconds
+    pub min_speech_duration_ms: u32,
+    /// Minimum duration of silence that ends a speech region in milliseconds
+    pub min_silence_duration_ms: u32,
+    /// Padding added before and after each speech region in milliseconds
+    pub speech_pad_ms: u32,
+}
+
+impl VadOptions {
+    /// Create VAD settings with whisper.cpp defaults
+    pub fn new<P: Into<PathBuf>>(model_path: P) -> Self {
+        VadOptions {
+            model_path: model_path.into(),
+            threshold: 0.5,
+            min_speech_duration_ms: 250,
+            min_silence_duration_ms: 100,
+            speech_pad_ms: 30,
+        }
+    }
+
+    /// Set the speech probability threshold
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Set the minimum speech duration
+    pub fn min_speech_duration_ms(mut self, min_speech_duration_ms: u32) -> Self {
+        self.min_speech_duration_ms = min_speech_duration_ms;
+        self
+    }
+
+    /// Set the minimum silence duration
+    pub fn min_silence_duration_ms(mut self, min_silence_duration_ms: u32) -> Self {
+        self.min_silence_duration_ms = min_silence_duration_ms;
+        self
+    }
+
+    /// Set the padding around speech regions
+    pub fn speech_pad_ms(mut self, speech_pad_ms: u32) -> Self {
+        self.speech_pad_ms = speech_pad_ms;
+        self
+    }
+
+    /// Check that the settings are within the ranges whisper.cpp accepts
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.threshold) {
+            return Err(anyhow!(
+                "VAD threshold must be between 0.0 and 1.0 (got {})",
+                self.threshold
+            ));
+        }
+        Ok(())
+    }
+
+    /// Copy the settings into whisper VAD params
+    ///
+    /// Fields without a command line equivalent keep the values from
+    /// `whisper_full_default_params`.
+    #[cfg(feature = "whisper")]
+    pub(crate) fn apply_to(&self, params: &mut ffi::WhisperVadParams) {
+        params.threshold = self.threshold;
+        params.min_speech_duration_ms = self.min_speech_duration_ms as i32;
+        params.min_silence_duration_ms = self.min_silence_duration_ms as i32;
+        params.speech_pad_ms = self.speech_pad_ms as i32;
+    }
+}
+
+/// Check a suppression regex for mistakes that would abort whisper.cpp
+///
+/// whisper.cpp compiles the pattern with `std::regex` and cannot report errors, so
+/// unbalanced groups, brackets and a trailing escape are rejected up front.
+pub fn check_suppress_regex(pattern: &str) -> Result<()> {
+    if pattern.is_empty() {
+        return Err(anyhow!("Suppression regex must not be empty"));
+    }
+
+    let mut depth = 0usize;
+    let mut in_class = false;
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // The guard also skips the escaped character
+            '\\' if chars.next().is_none() => {
+                return Err(anyhow!("Suppression regex ends with an escape"));
+            }
+            '[' if !in_class => in_class = true,
+            ']' if in_class => in_class = false,
+            '(' if !in_class => depth += 1,
+            ')' if !in_class => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| anyhow!("Suppression regex has an unmatched ')'"))?;
+            }
+            _ => {}
+        }
+    }
+
+    if in_class {
+        return Err(anyhow!("Suppression regex has an unclosed '['"));
+    }
+    if depth > 0 {
+        return Err(anyhow!("Suppression regex has an unclosed '('"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_options() {
+        let options = TranscribeOptions::default();
+        assert!(options.language.is_none());
+        assert!(!options.translate);
+        assert!(options.beam_size.is_none());
+        assert_eq!(options.best_of, 5);
+        assert!((options.temperature_inc - 0.2).abs() < f32::EPSILON);
+        assert!(options.validate().is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "whisper")]
+    fn test_sampling_strategy() {
+        assert_eq!(
+            TranscribeOptions::new().strategy(),
+            ffi::WHISPER_SAMPLING_GREEDY
+        );
+        assert_eq!(
+            TranscribeOptions::new().beam_size(5).strategy(),
+            ffi::WHISPER_SAMPLING_BEAM_SEARCH
+        );
+    }
+
+    #[test]
+    fn test_grammar_options() {
+        let grammar = Grammar::parse("root ::= cmd\ncmd ::= \"stop\" | \"go\"\n").unwrap();
+        let options = TranscribeOptions::new()
+            .grammar(grammar.clone())
+            .grammar_penalty(50.0);
+        assert!(options.validate().is_ok());
+
+        let options = options.grammar_rule("cmd");
+        assert!(options.validate().is_ok());
+        assert!(options.clone().grammar_rule("missing").validate().is_err());
+        assert!(options.grammar_penalty(-1.0).validate().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "whisper")]
+    fn test_grammar_options_apply_to_params() {
+        let grammar = Grammar::parse("root ::= cmd\ncmd ::= \"stop\" | \"go\"\n").unwrap();
+        let options = TranscribeOptions::new()
+            .grammar(grammar)
+            .grammar_rule("cmd")
+            .grammar_penalty(50.0);
+
+        let mut params: ffi::WhisperFullParams = unsafe { std::mem::zeroed() };
+        options.apply_to(&mut params);
+        assert_eq!(params.i_start_rule, 1);
+        assert!((params.grammar_penalty - 50.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_builder_chain() {
+        let options = TranscribeOptions::new()
+            .language("ru")
+            .translate(true)
+            .beam_size(5)
+            .best_of(3)
+            .temperature(0.4)
+            .no_fallback()
+            .initial_prompt("OrangeNote, whisper.cpp.")
+            .carry_initial_prompt(true);
+
+        assert_eq!(options.language.as_deref(), Some("ru"));
+        assert!(options.translate);
+        assert_eq!(options.beam_size, Some(5));
+        assert_eq!(options.best_of, 3);
+        assert!((options.temperature - 0.4).abs() < f32::EPSILON);
+        assert_eq!(options.temperature_inc, 0.0);
+        assert_eq!(
+            options.initial_prompt.as_deref(),
+            Some("OrangeNote, whisper.cpp.")
+        );
+        assert!(options.carry_initial_prompt);
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_values() {
+        assert!(TranscribeOptions::new().beam_size(0).validate().is_err());
+        assert!(TranscribeOptions::new().best_of(0).validate().is_err());
+        assert!(TranscribeOptions::new()
+            .temperature(-0.1)
+            .validate()
+            .is_err());
+        assert!(TranscribeOptions::new().threads(0).validate().is_err());
+        assert!(TranscribeOptions::new()
+            .language("ru")
+            .language_per_chunk(true)
+            .validate()
+            .is_err());
+        assert!(TranscribeOptions::new()
+            .vad(VadOptions::new("vad.bin").threshold(1.5))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "whisper")]
+    fn test_vad_options() {
+        let options = TranscribeOptions::new().vad(
+            VadOptions::new("/models/ggml-silero-v5.1.2.bin")
+                .threshold(0.35)
+                .min_speech_duration_ms(500)
+                .min_silence_duration_ms(300)
+                .speech_pad_ms(100),
+        );
+        assert!(options.validate().is_ok());
+
+        let vad = options.vad.as_ref().unwrap();
+        let mut params = ffi::WhisperVadParams {
+            threshold: 0.5,
+            min_speech_duration_ms: 250,
+            min_silence_duration_ms: 100,
+            max_speech_duration_s: f32::MAX,
+            speech_pad_ms: 30,
+            samples_overlap: 0.1,
+        };
+        vad.apply_to(&mut params);
+
+        assert!((params.threshold - 0.35).abs() < f32::EPSILON);
+        assert_eq!(params.min_speech_duration_ms, 500);
+        assert_eq!(params.min_silence_duration_ms, 300);
+        assert_eq!(params.speech_pad_ms, 100);
+        assert_eq!(params.max_speech_duration_s, f32::MAX);
+    }
+
+    #[test]
+    fn test_check_suppress_regex() {
+        assert!(check_suppress_regex("♪+|Subtitles by.*").is_ok());
+        assert!(check_suppress_regex("[()]\\(").is_ok());
+        assert!(check_suppress_regex("").is_err());
+        assert!(check_suppress_regex("(abc").is_err());
+        assert!(check_suppress_regex("abc)").is_err());
+        assert!(check_suppress_regex("[abc").is_err());
+        assert!(check_suppress_regex("abc\\").is_err());
+    }
+}
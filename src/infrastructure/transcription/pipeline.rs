@@ -0,0 +1,876 @@
+//! Backend-independent transcription pipeline
+//!
+//! Quality retries, chunking, chunk text context and merging only need a way to
+//! decode samples, so they are implemented once on top of `TranscriptionBackend`
+//! and shared by every backend.
+
+use super::backend::TranscriptionBackend;
+use super::merger::{merge_transcription_results, MergeConfig};
+use super::options::TranscribeOptions;
+use super::quality::{check_quality, QualityConfig, QualityRetry};
+use super::result::TranscriptionResult;
+use crate::infrastructure::audio::{AudioChunk, AudioSamples, ChunkConfig};
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info, warn};
+
+/// Transcribe PCM samples with a backend
+///
+/// The samples are decoded once: quality checks and re-decodes (`options.quality`)
+/// only run per chunk, since one suspicious segment would otherwise re-decode the
+/// whole file.
+///
+/// # Arguments
+///
+/// * `backend` - Backend decoding the samples
+/// * `samples` - PCM samples at 16kHz, f32 normalized to [-1.0, 1.0]
+/// * `options` - Decoding options
+///
+/// # Returns
+///
+/// Result containing the transcription result
+pub fn transcribe_samples<B: TranscriptionBackend + ?Sized>(
+    backend: &B,
+    samples: &[f32],
+    options: &TranscribeOptions,
+) -> Result<TranscriptionResult> {
+    let options = TranscribeOptions {
+        quality: None,
+        ..options.clone()
+    };
+    transcribe_samples_checked(backend, samples, &options, None)
+}
+
+/// Transcribe samples, re-decoding them with `retry_prompt` if they fail the quality checks
+fn transcribe_samples_checked<B: TranscriptionBackend + ?Sized>(
+    backend: &B,
+    samples: &[f32],
+    options: &TranscribeOptions,
+    retry_prompt: Option<&str>,
+) -> Result<TranscriptionResult> {
+    if samples.is_empty() {
+        return Err(anyhow!("No audio samples provided"));
+    }
+
+    info!("Starting transcription of {} samples", samples.len());
+    debug!("Transcription options: {:?}", options);
+
+    let result = decode_with_retries(samples, options, retry_prompt, |options| {
+        backend.transcribe(samples, options)
+    })
+    .context("Transcription failed")?;
+
+    info!(
+        "Transcription complete: {} segments, language: {}",
+        result.segments.len(),
+        result.language
+    );
+
+    Ok(result)
+}
+
+/// Transcribe a single audio chunk with a backend
+///
+/// # Arguments
+///
+/// * `backend` - Backend decoding the chunk
+/// * `chunk` - Audio chunk with samples and metadata
+/// * `options` - Decoding options
+///
+/// # Returns
+///
+/// Result containing the transcription result (timestamps are relative to chunk start)
+pub fn transcribe_chunk<B: TranscriptionBackend + ?Sized>(
+    backend: &B,
+    chunk: &AudioChunk,
+    options: &TranscribeOptions,
+) -> Result<TranscriptionResult> {
+    transcribe_chunk_checked(backend, chunk, options, options.initial_prompt.as_deref())
+}
+
+/// Transcribe a chunk, re-decoding it with `retry_prompt` if it fails the quality checks
+fn transcribe_chunk_checked<B: TranscriptionBackend + ?Sized>(
+    backend: &B,
+    chunk: &AudioChunk,
+    options: &TranscribeOptions,
+    retry_prompt: Option<&str>,
+) -> Result<TranscriptionResult> {
+    if chunk.samples.is_empty() {
+        return Err(anyhow!("Empty audio chunk provided"));
+    }
+
+    debug!(
+        "Transcribing chunk {}: {} samples ({:.1}s)",
+        chunk.index,
+        chunk.samples.len(),
+        chunk.duration_ms as f64 / 1000.0
+    );
+
+    let mut result = transcribe_samples_checked(backend, &chunk.samples, options, retry_prompt)?;
+    tag_retries(&mut result, chunk);
+    Ok(result)
+}
+
+/// Transcribe audio in chunks with a backend and merge the results
+///
+/// Chunks are decoded one after another. Each chunk gets the text context of the
+/// previous one (see `with_chunk_context`), and unless `options.language_per_chunk`
+/// is set, the language detected on the first chunk with confident speech (see
+/// `confident_language`) is used for all later chunks.
+///
+/// # Arguments
+///
+/// * `backend` - Backend decoding the chunks
+/// * `audio` - Processed audio samples
+/// * `options` - Decoding options
+/// * `chunk_config` - Configuration for chunking (duration, overlap)
+/// * `progress_callback` - Callback for progress updates (current_chunk, total_chunks)
+///
+/// # Returns
+///
+/// Result containing the merged transcription result with corrected timestamps
+pub fn transcribe_chunked<B, F>(
+    backend: &B,
+    audio: &AudioSamples,
+    options: &TranscribeOptions,
+    chunk_config: &ChunkConfig,
+    progress_callback: F,
+) -> Result<TranscriptionResult>
+where
+    B: TranscriptionBackend + ?Sized,
+    F: Fn(usize, usize),
+{
+    // Split into chunks
+    let chunks = audio.split_into_chunks(chunk_config);
+    let total_chunks = chunks.len();
+
+    if total_chunks == 0 {
+        return Err(anyhow!("No audio chunks generated"));
+    }
+
+    info!(
+        "Split audio into {} chunks ({:.1}s each, {}s overlap)",
+        total_chunks, chunk_config.chunk_duration_secs, chunk_config.overlap_secs
+    );
+
+    // Transcribe each chunk
+    let mut chunk_results: Vec<(TranscriptionResult, i64)> = Vec::with_capacity(total_chunks);
+    let mut locked_language: Option<String> = None;
+
+    // Progress of each chunk covers the audio up to the next chunk's start,
+    // so the overall progress never goes backwards across overlaps
+    let total_ms = audio.duration_ms().max(1) as f32;
+    let chunk_ends: Vec<f32> = chunks
+        .iter()
+        .skip(1)
+        .map(|next| next.start_offset_ms as f32 / total_ms)
+        .chain(std::iter::once(1.0))
+        .collect();
+
+    let mut cancelled = false;
+    for chunk in &chunks {
+        if options.is_cancelled() {
+            info!(
+                "Transcription cancelled before chunk {}/{}",
+                chunk.index + 1,
+                total_chunks
+            );
+            cancelled = true;
+            break;
+        }
+
+        progress_callback(chunk.index, total_chunks);
+
+        debug!(
+            "Transcribing chunk {}/{}: {} samples, offset {}ms",
+            chunk.index + 1,
+            total_chunks,
+            chunk.samples.len(),
+            chunk.start_offset_ms
+        );
+
+        // The context only depends on the previous chunk's stored result,
+        // so any chunk can be re-run on its own with the same options
+        let previous = chunk_results.last().map(|(result, offset)| {
+            let samples = chunks[chunk.index - 1].samples.as_slice();
+            (result, samples, chunk.start_offset_ms - offset)
+        });
+        let mut chunk_options = with_chunk_context(options, previous);
+        if let Some(progress) = &options.progress {
+            let start = chunk.start_offset_ms as f32 / total_ms;
+            chunk_options.progress = Some(progress.sub_range(start, chunk_ends[chunk.index]));
+        }
+        if let Some(on_segment) = &options.on_segment {
+            chunk_options.on_segment = Some(on_segment.with_offset(chunk.start_offset_ms));
+        }
+        if chunk_options.language.is_none() && !options.language_per_chunk {
+            chunk_options.language = locked_language.clone();
+        }
+
+        // A re-decode drops the carried chunk context, which often feeds loops
+        let result = transcribe_chunk_checked(
+            backend,
+            chunk,
+            &chunk_options,
+            options.initial_prompt.as_deref(),
+        )
+        .with_context(|| format!("Failed to transcribe chunk {}", chunk.index))?;
+
+        if options.language_per_chunk {
+            debug!("Chunk {} language: {}", chunk.index, result.language);
+        } else if locked_language.is_none() && options.language.is_none() {
+            match confident_language(&result) {
+                Some(language) => {
+                    info!(
+                        "Detected language {} on chunk {}, using it for the remaining chunks",
+                        language, chunk.index
+                    );
+                    locked_language = Some(language.to_string());
+                }
+                None => debug!(
+                    "No confident language on chunk {}, detecting again on the next one",
+                    chunk.index
+                ),
+            }
+        }
+
+        let chunk_partial = result.partial;
+        chunk_results.push((result, chunk.start_offset_ms));
+        if chunk_partial {
+            cancelled = true;
+            break;
+        }
+    }
+
+    // Merge results using the merger module
+    let merge_config = MergeConfig::from_overlap_secs(chunk_config.overlap_secs);
+    let mut merge_result = merge_transcription_results(chunk_results, merge_config);
+    merge_result.result.partial |= cancelled;
+
+    info!(
+        "Chunked transcription complete: {} segments (removed {} duplicates), language: {}",
+        merge_result.result.segments.len(),
+        merge_result.duplicates_removed,
+        merge_result.result.language
+    );
+
+    Ok(merge_result.result)
+}
+
+/// Highest mean no-speech probability of a chunk whose language can be locked
+const LANGUAGE_LOCK_MAX_NO_SPEECH_PROB: f32 = 0.5;
+
+/// Get the language of a chunk result if it is reliable enough to lock for later chunks
+///
+/// A chunk of intro music or silence often gets an arbitrary language guess, so the
+/// chunk must contain text, a known language and mostly speech.
+///
+/// # Arguments
+///
+/// * `result` - Transcription result of a chunk decoded with auto-detection
+///
+/// # Returns
+///
+/// The detected language, or `None` if the next chunk should be detected again
+pub(crate) fn confident_language(result: &TranscriptionResult) -> Option<&str> {
+    let language = result.language.as_str();
+    if language.is_empty() || language == "unknown" || result.full_text().trim().is_empty() {
+        return None;
+    }
+
+    let no_speech_prob = result
+        .segments
+        .iter()
+        .map(|segment| segment.no_speech_prob)
+        .sum::<f32>()
+        / result.segments.len() as f32;
+    (no_speech_prob <= LANGUAGE_LOCK_MAX_NO_SPEECH_PROB).then_some(language)
+}
+
+/// Disable the segment rate check when segments are split on purpose
+///
+/// With `max_len` or `split_on_word`, whisper emits several short segments per
+/// second, which would fail the check on almost every decode.
+fn segment_rate_adjusted(config: &QualityConfig, options: &TranscribeOptions) -> QualityConfig {
+    let mut config = config.clone();
+    if options.max_len > 0 || options.split_on_word {
+        config.max_segments_per_sec = f32::INFINITY;
+    }
+    config
+}
+
+/// Decode audio, decoding it again while the result fails the quality checks
+///
+/// Each re-decode raises the temperature by `retry_temperature_step` and uses
+/// `retry_prompt` as initial prompt. Progress and streamed segments are only
+/// reported for the first decode. The result with the fewest issues is kept, and
+/// the retry is recorded in its `quality_retries`. A failing re-decode ends the
+/// retries instead of failing the decode.
+///
+/// # Arguments
+///
+/// * `samples` - Audio being decoded, used for the silence check
+/// * `options` - Decoding options; checks are skipped if `options.quality` is `None`
+/// * `retry_prompt` - Initial prompt for re-decodes
+/// * `decode` - Runs the backend with the given options
+fn decode_with_retries<F>(
+    samples: &[f32],
+    options: &TranscribeOptions,
+    retry_prompt: Option<&str>,
+    mut decode: F,
+) -> Result<TranscriptionResult>
+where
+    F: FnMut(&TranscribeOptions) -> Result<TranscriptionResult>,
+{
+    let result = decode(options)?;
+    let Some(config) = &options.quality else {
+        return Ok(result);
+    };
+    if result.partial {
+        return Ok(result);
+    }
+
+    let config = &segment_rate_adjusted(config, options);
+    let issues = check_quality(&result, samples, config);
+    if issues.is_empty() || config.max_retries == 0 {
+        return Ok(result);
+    }
+
+    let issue_descriptions: Vec<String> = issues.iter().map(ToString::to_string).collect();
+    warn!(
+        "Transcription looks like a hallucination ({}), decoding again",
+        issue_descriptions.join(", ")
+    );
+
+    let mut best = (issues.len(), result);
+    let mut attempts = 0;
+    let mut resolved = false;
+    for attempt in 1..=config.max_retries {
+        if options.is_cancelled() {
+            break;
+        }
+
+        let mut retry_options = options.clone();
+        retry_options.temperature =
+            (options.temperature + config.retry_temperature_step * attempt as f32).min(1.0);
+        retry_options.initial_prompt = retry_prompt.map(str::to_string);
+        retry_options.chunk_context = None;
+        retry_options.progress = None;
+        retry_options.on_segment = None;
+
+        // The first decode is usable, so a failing retry only ends the retries
+        attempts = attempt;
+        let result = match decode(&retry_options) {
+            Ok(result) => result,
+            Err(e) => {
+                warn!(
+                    "Re-decode {} failed, keeping the best result: {:#}",
+                    attempt, e
+                );
+                break;
+            }
+        };
+        if result.partial {
+            break;
+        }
+
+        let issues = check_quality(&result, samples, config);
+        debug!(
+            "Re-decode {} at temperature {:.1}: {} issues",
+            attempt,
+            retry_options.temperature,
+            issues.len()
+        );
+        if issues.len() < best.0 {
+            best = (issues.len(), result);
+        }
+        if issues.is_empty() {
+            resolved = true;
+            break;
+        }
+    }
+
+    let (_, mut result) = best;
+    result.quality_retries.push(QualityRetry {
+        chunk_index: 0,
+        start_ms: 0,
+        attempts,
+        issues: issue_descriptions,
+        resolved,
+    });
+    Ok(result)
+}
+
+/// Decode a chunk with quality retries and attribute the retries to the chunk
+#[cfg(feature = "whisper")]
+pub(crate) fn transcribe_chunk_with_retries<F>(
+    chunk: &AudioChunk,
+    options: &TranscribeOptions,
+    decode: F,
+) -> Result<TranscriptionResult>
+where
+    F: FnMut(&TranscribeOptions) -> Result<TranscriptionResult>,
+{
+    let mut result = decode_with_retries(
+        &chunk.samples,
+        options,
+        options.initial_prompt.as_deref(),
+        decode,
+    )?;
+    tag_retries(&mut result, chunk);
+    Ok(result)
+}
+
+/// Attribute a chunk result's quality retries to the chunk
+fn tag_retries(result: &mut TranscriptionResult, chunk: &AudioChunk) {
+    for retry in &mut result.quality_retries {
+        retry.chunk_index = chunk.index;
+        retry.start_ms = chunk.start_offset_ms;
+    }
+}
+
+/// Build the decoding options for a chunk, carrying text context from the previous chunk
+///
+/// When `options.chunk_context_words` is non-zero, the last words of the previous chunk
+/// that were spoken before the current chunk starts are set as `chunk_context`. Backends
+/// place it after the initial prompt, and a prompt too long for the decoder is
+/// truncated before the context is.
+/// The context is dropped if the previous chunk fails the quality checks (`options.quality`,
+/// or the default thresholds), so a bad chunk does not poison the ones after it.
+///
+/// # Arguments
+///
+/// * `options` - Base decoding options
+/// * `previous` - Previous chunk result, the samples it was decoded from, and the
+///   current chunk's start relative to it (ms)
+///
+/// # Returns
+///
+/// Decoding options for the current chunk
+pub fn with_chunk_context(
+    options: &TranscribeOptions,
+    previous: Option<(&TranscriptionResult, &[f32], i64)>,
+) -> TranscribeOptions {
+    let mut chunk_options = options.clone();
+
+    if options.chunk_context_words == 0 {
+        return chunk_options;
+    }
+
+    let Some((previous, samples, cutoff_ms)) = previous else {
+        return chunk_options;
+    };
+
+    let config = options.quality.clone().unwrap_or_default();
+    let issues = check_quality(previous, samples, &segment_rate_adjusted(&config, options));
+    if !issues.is_empty() {
+        let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+        info!(
+            "Previous chunk looks like a hallucination ({}), resetting text context",
+            issues.join(", ")
+        );
+        return chunk_options;
+    }
+
+    if let Some(tail) = context_tail(previous, cutoff_ms, options.chunk_context_words) {
+        debug!("Carrying chunk context: '{}'", tail);
+        chunk_options.chunk_context = Some(tail);
+    }
+
+    chunk_options
+}
+
+/// Get the last `max_words` words spoken before `cutoff_ms`
+///
+/// Segments starting at or after the cutoff belong to the overlap with the next chunk
+/// and are re-transcribed there, so they are not used as context.
+fn context_tail(result: &TranscriptionResult, cutoff_ms: i64, max_words: usize) -> Option<String> {
+    let words: Vec<&str> = result
+        .segments
+        .iter()
+        .filter(|segment| segment.start_ms < cutoff_ms)
+        .flat_map(|segment| segment.text.split_whitespace())
+        .collect();
+
+    if words.is_empty() {
+        return None;
+    }
+
+    let start = words.len().saturating_sub(max_words);
+    Some(words[start..].join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::audio::WHISPER_SAMPLE_RATE;
+    use crate::infrastructure::transcription::backend::MockBackend;
+    use crate::infrastructure::transcription::format::{format_result, FormatOptions};
+    use crate::infrastructure::transcription::result::Segment;
+
+    fn make_result(segments: &[(i64, i64, &str)]) -> TranscriptionResult {
+        TranscriptionResult {
+            language: "en".to_string(),
+            segments: segments
+                .iter()
+                .enumerate()
+                .map(|(i, (start_ms, end_ms, text))| Segment {
+                    id: i as i32,
+                    start_ms: *start_ms,
+                    end_ms: *end_ms,
+                    text: text.to_string(),
+                    confidence: 0.9,
+                    language: "en".to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_empty_samples() {
+        let backend = MockBackend::new();
+        assert!(transcribe_samples(&backend, &[], &TranscribeOptions::new()).is_err());
+        assert!(backend.calls().is_empty());
+    }
+
+    #[test]
+    fn test_transcribe_chunked_end_to_end() {
+        let audio = AudioSamples {
+            samples: vec![0.1; WHISPER_SAMPLE_RATE as usize * 20],
+            original_sample_rate: WHISPER_SAMPLE_RATE,
+            original_channels: 1,
+            duration_seconds: 20.0,
+        };
+        let backend = MockBackend::new()
+            .then(make_result(&[
+                (0, 5000, " Hello world."),
+                (5000, 9500, " This is chunk one."),
+            ]))
+            .then(make_result(&[(1500, 6000, " Second chunk here.")]));
+        let options = TranscribeOptions::new().chunk_context_words(3);
+        let progress = std::cell::RefCell::new(Vec::new());
+
+        let result = transcribe_chunked(
+            &backend,
+            &audio,
+            &options,
+            &ChunkConfig::new(12, 2),
+            |current, total| progress.borrow_mut().push((current, total)),
+        )
+        .unwrap();
+
+        assert_eq!(progress.into_inner(), vec![(0, 2), (1, 2)]);
+
+        // The second chunk gets the first chunk's language and text context
+        let calls = backend.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].samples, WHISPER_SAMPLE_RATE as usize * 12);
+        assert_eq!(calls[1].options.language.as_deref(), Some("en"));
+        assert_eq!(
+            calls[1].options.chunk_context.as_deref(),
+            Some("is chunk one.")
+        );
+
+        let spans: Vec<(i64, i64)> = result
+            .segments
+            .iter()
+            .map(|segment| (segment.start_ms, segment.end_ms))
+            .collect();
+        assert_eq!(spans, vec![(0, 5000), (5000, 9500), (11500, 16000)]);
+        assert!(!result.partial);
+
+        let srt = format_result(&result, "srt", &FormatOptions::new()).unwrap();
+        assert!(srt.contains("3\n00:00:11,500 --> 00:00:16,000\n Second chunk here.\n"));
+    }
+
+    #[test]
+    fn test_language_locked_on_first_confident_chunk() {
+        let audio = AudioSamples {
+            samples: vec![0.1; WHISPER_SAMPLE_RATE as usize * 30],
+            original_sample_rate: WHISPER_SAMPLE_RATE,
+            original_channels: 1,
+            duration_seconds: 30.0,
+        };
+        let mut intro = make_result(&[(0, 10000, " ♪")]);
+        intro.segments[0].no_speech_prob = 0.9;
+        let mut german = make_result(&[(0, 5000, " Guten Morgen.")]);
+        german.language = "de".to_string();
+        let backend = MockBackend::new()
+            .then(intro)
+            .then(german)
+            .then(make_result(&[(0, 5000, " Wie geht's?")]));
+
+        transcribe_chunked(
+            &backend,
+            &audio,
+            &TranscribeOptions::new(),
+            &ChunkConfig::new(12, 2),
+            |_, _| {},
+        )
+        .unwrap();
+
+        let languages: Vec<Option<String>> = backend
+            .calls()
+            .into_iter()
+            .map(|call| call.options.language)
+            .collect();
+        assert_eq!(languages, vec![None, None, Some("de".to_string())]);
+    }
+
+    #[test]
+    fn test_confident_language() {
+        let speech = make_result(&[(0, 5000, " Hello world.")]);
+        assert_eq!(confident_language(&speech), Some("en"));
+
+        let mut unknown = speech.clone();
+        unknown.language = "unknown".to_string();
+        assert_eq!(confident_language(&unknown), None);
+
+        let mut silence = speech.clone();
+        silence.segments[0].no_speech_prob = 0.8;
+        assert_eq!(confident_language(&silence), None);
+
+        assert_eq!(confident_language(&make_result(&[(0, 5000, "  ")])), None);
+        assert_eq!(confident_language(&make_result(&[])), None);
+    }
+
+    #[test]
+    fn test_transcribe_chunked_reports_failing_chunk() {
+        let audio = AudioSamples {
+            samples: vec![0.1; WHISPER_SAMPLE_RATE as usize * 20],
+            original_sample_rate: WHISPER_SAMPLE_RATE,
+            original_channels: 1,
+            duration_seconds: 20.0,
+        };
+        let backend = MockBackend::new()
+            .then(make_result(&[(0, 5000, " Hello world.")]))
+            .then_err("decoder crashed");
+
+        let error = transcribe_chunked(
+            &backend,
+            &audio,
+            &TranscribeOptions::new(),
+            &ChunkConfig::new(12, 2),
+            |_, _| {},
+        )
+        .unwrap_err();
+
+        assert_eq!(error.to_string(), "Failed to transcribe chunk 1");
+        assert!(format!("{:#}", error).contains("decoder crashed"));
+    }
+
+    #[test]
+    fn test_decode_retries_until_quality_passes() {
+        let samples = vec![0.3; WHISPER_SAMPLE_RATE as usize * 4];
+        let options = TranscribeOptions::new()
+            .temperature(0.0)
+            .initial_prompt("previous chunk")
+            .quality(QualityConfig::default());
+        let mut seen = Vec::new();
+
+        let result = decode_with_retries(&samples, &options, None, |options| {
+            seen.push((options.temperature, options.initial_prompt.clone()));
+            Ok(if seen.len() == 1 {
+                make_result(&[(0, 4000, " Thank you. Thank you. Thank you. Thank you.")])
+            } else {
+                make_result(&[(0, 4000, " Hello there, how are you?")])
+            })
+        })
+        .unwrap();
+
+        assert_eq!(
+            seen,
+            vec![(0.0, Some("previous chunk".to_string())), (0.2, None)]
+        );
+        assert_eq!(result.full_text().trim(), "Hello there, how are you?");
+        assert_eq!(result.quality_retries.len(), 1);
+        assert_eq!(result.quality_retries[0].attempts, 1);
+        assert!(result.quality_retries[0].resolved);
+    }
+
+    #[test]
+    fn test_decode_keeps_best_result_when_retries_fail() {
+        let samples = vec![0.3; WHISPER_SAMPLE_RATE as usize * 4];
+        let options = TranscribeOptions::new().quality(QualityConfig::default().max_retries(2));
+        let mut calls = 0;
+
+        let result = decode_with_retries(&samples, &options, None, |_| {
+            calls += 1;
+            Ok(make_result(&[(
+                0,
+                4000,
+                " Thank you. Thank you. Thank you.",
+            )]))
+        })
+        .unwrap();
+
+        assert_eq!(calls, 3);
+        assert_eq!(result.quality_retries[0].attempts, 2);
+        assert!(!result.quality_retries[0].resolved);
+    }
+
+    #[test]
+    fn test_failed_retry_keeps_first_result() {
+        let looping = make_result(&[(0, 4000, " Thank you. Thank you. Thank you.")]);
+        let backend = MockBackend::new().then(looping.clone()).then_err("boom");
+        let chunk = AudioChunk {
+            samples: vec![0.3; WHISPER_SAMPLE_RATE as usize * 4],
+            index: 0,
+            start_offset_ms: 0,
+            duration_ms: 4000,
+            is_last: true,
+        };
+        let options = TranscribeOptions::new().quality(QualityConfig::default());
+
+        let result = transcribe_chunk(&backend, &chunk, &options).unwrap();
+
+        assert_eq!(backend.calls().len(), 2);
+        assert_eq!(result.full_text(), looping.full_text());
+        assert_eq!(result.quality_retries.len(), 1);
+        assert_eq!(result.quality_retries[0].attempts, 1);
+        assert!(!result.quality_retries[0].resolved);
+    }
+
+    #[test]
+    fn test_decode_without_quality_config_runs_once() {
+        let samples = vec![0.3; WHISPER_SAMPLE_RATE as usize * 4];
+        let mut calls = 0;
+        let result = decode_with_retries(&samples, &TranscribeOptions::new(), None, |_| {
+            calls += 1;
+            Ok(make_result(&[(
+                0,
+                4000,
+                " Thank you. Thank you. Thank you.",
+            )]))
+        })
+        .unwrap();
+        assert_eq!(calls, 1);
+        assert!(result.quality_retries.is_empty());
+    }
+
+    #[test]
+    fn test_transcribe_samples_does_not_retry_whole_file() {
+        let backend = MockBackend::new().then(make_result(&[(
+            0,
+            4000,
+            " Thank you. Thank you. Thank you. Thank you.",
+        )]));
+        let options = TranscribeOptions::new().quality(QualityConfig::default());
+
+        let result = transcribe_samples(&backend, &[0.3; 64000], &options).unwrap();
+
+        assert_eq!(backend.calls().len(), 1);
+        assert!(result.quality_retries.is_empty());
+    }
+
+    #[test]
+    fn test_segment_rate_check_skipped_with_max_len() {
+        let samples = vec![0.3; WHISPER_SAMPLE_RATE as usize * 2];
+        let short_segments = make_result(&[
+            (0, 300, " one"),
+            (300, 600, " two"),
+            (600, 900, " three"),
+            (900, 1200, " four"),
+            (1200, 1500, " five"),
+            (1500, 2000, " six"),
+        ]);
+        let decode_count = |options: &TranscribeOptions| {
+            let mut calls = 0;
+            decode_with_retries(&samples, options, None, |_| {
+                calls += 1;
+                Ok(short_segments.clone())
+            })
+            .unwrap();
+            calls
+        };
+
+        let options = TranscribeOptions::new().quality(QualityConfig::default());
+        assert_eq!(decode_count(&options), 3);
+        assert_eq!(decode_count(&options.clone().max_len(10)), 1);
+        assert_eq!(decode_count(&options.split_on_word(true)), 1);
+    }
+
+    #[test]
+    fn test_context_tail_respects_cutoff_and_word_limit() {
+        let result = make_result(&[
+            (0, 4000, "the quick brown fox"),
+            (4000, 8000, "jumps over the lazy dog"),
+            (9000, 10000, "overlap text"),
+        ]);
+
+        assert_eq!(
+            context_tail(&result, 9000, 4).as_deref(),
+            Some("over the lazy dog")
+        );
+        assert_eq!(
+            context_tail(&result, 9000, 100).as_deref(),
+            Some("the quick brown fox jumps over the lazy dog")
+        );
+        assert_eq!(context_tail(&result, 0, 10), None);
+    }
+
+    #[test]
+    fn test_with_chunk_context() {
+        let samples = vec![0.3; WHISPER_SAMPLE_RATE as usize * 10];
+        let previous = make_result(&[(0, 5000, "we were talking about OrangeNote")]);
+
+        let disabled = TranscribeOptions::new();
+        assert!(
+            with_chunk_context(&disabled, Some((&previous, samples.as_slice(), 10000)))
+                .chunk_context
+                .is_none()
+        );
+
+        let options = TranscribeOptions::new()
+            .initial_prompt("Glossary: whisper.cpp.")
+            .chunk_context_words(2);
+        let chunk_options =
+            with_chunk_context(&options, Some((&previous, samples.as_slice(), 10000)));
+        assert_eq!(
+            chunk_options.initial_prompt.as_deref(),
+            Some("Glossary: whisper.cpp.")
+        );
+        assert_eq!(
+            chunk_options.chunk_context.as_deref(),
+            Some("about OrangeNote")
+        );
+
+        // First chunk has no context
+        let first = with_chunk_context(&options, None);
+        assert!(first.chunk_context.is_none());
+
+        // Context resets after a hallucinated chunk
+        let looping = make_result(&[
+            (0, 1000, "Thank you."),
+            (1000, 2000, "Thank you."),
+            (2000, 3000, "Thank you."),
+        ]);
+        let reset = with_chunk_context(&options, Some((&looping, samples.as_slice(), 10000)));
+        assert!(reset.chunk_context.is_none());
+
+        // ...and after text decoded over silence
+        let silence = vec![0.0; WHISPER_SAMPLE_RATE as usize * 10];
+        let reset = with_chunk_context(&options, Some((&previous, silence.as_slice(), 10000)));
+        assert!(reset.chunk_context.is_none());
+    }
+
+    #[test]
+    fn test_chunk_context_kept_apart_from_long_prompt() {
+        let vocab: Vec<String> = (0..500).map(|i| format!("Term{}", i)).collect();
+        let options = TranscribeOptions::new()
+            .initial_prompt(format!("{}.", vocab.join(", ")))
+            .chunk_context_words(2);
+        let previous = make_result(&[(0, 5000, "we were talking about OrangeNote")]);
+        let samples = vec![0.3; WHISPER_SAMPLE_RATE as usize * 10];
+
+        // Backends truncate the prompt, so the context must not be part of it
+        let chunk_options =
+            with_chunk_context(&options, Some((&previous, samples.as_slice(), 10000)));
+        assert_eq!(chunk_options.initial_prompt, options.initial_prompt);
+        assert_eq!(
+            chunk_options.chunk_context.as_deref(),
+            Some("about OrangeNote")
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Inference progress reporting
+//!
+//! whisper.cpp reports progress of a `whisper_full` call through `progress_callback`
+//! as a percentage. `ProgressCallback` turns that into a fraction of the whole job,
+//! so that chunked transcription can report progress over the entire file.
+
+#[cfg(feature = "whisper")]
+use super::whisper::ffi;
+use std::fmt;
+#[cfg(feature = "whisper")]
+use std::os::raw::{c_int, c_void};
+use std::sync::Arc;
+
+/// Callback receiving transcription progress as a fraction of the whole job (0.0 - 1.0)
+#[derive(Clone)]
+pub struct ProgressCallback {
+    callback: Arc<dyn Fn(f32) + Send + Sync>,
+    start: f32,
+    span: f32,
+}
+
+impl ProgressCallback {
+    /// Wrap a closure receiving progress fractions
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: Fn(f32) + Send + Sync + 'static,
+    {
+        ProgressCallback {
+            callback: Arc::new(callback),
+            start: 0.0,
+            span: 1.0,
+        }
+    }
+
+    /// Report progress of the current part of the job (0.0 - 1.0)
+    pub fn report(&self, fraction: f32) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        (self.callback)((self.start + fraction * self.span).clamp(0.0, 1.0));
+    }
+
+    /// Create a callback for a sub-range `[start, end)` of this callback's range
+    ///
+    /// Progress reported through the returned callback is mapped into that range,
+    /// e.g. a chunk covering the second half of a file maps 0.5 to 0.75.
+    pub fn sub_range(&self, start: f32, end: f32) -> Self {
+        ProgressCallback {
+            callback: Arc::clone(&self.callback),
+            start: self.start + start * self.span,
+            span: (end - start).max(0.0) * self.span,
+        }
+    }
+
+    /// Pointer passed as `progress_callback_user_data`
+    ///
+    /// The callback must not move while whisper holds the pointer.
+    #[cfg(feature = "whisper")]
+    pub(crate) fn as_user_data(&self) -> *mut c_void {
+        self as *const ProgressCallback as *mut c_void
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressCallback")
+            .field("start", &self.start)
+            .field("span", &self.span)
+            .finish()
+    }
+}
+
+/// `progress_callback` for whisper.cpp; `user_data` comes from `ProgressCallback::as_user_data`
+#[cfg(feature = "whisper")]
+pub(crate) extern "C" fn progress_callback(
+    _ctx: *mut ffi::WhisperContext,
+    _state: *mut ffi::WhisperState,
+    progress: c_int,
+    user_data: *mut c_void,
+) {
+    if user_data.is_null() {
+        return;
+    }
+    let callback = unsafe { &*(user_data as *const ProgressCallback) };
+    callback.report(progress as f32 / 100.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn recording() -> (ProgressCallback, Arc<Mutex<Vec<f32>>>) {
+        let values = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&values);
+        let callback = ProgressCallback::new(move |fraction| sink.lock().unwrap().push(fraction));
+        (callback, values)
+    }
+
+    #[test]
+    fn test_sub_range_maps_progress() {
+        let (callback, values) = recording();
+
+        let second_half = callback.sub_range(0.5, 1.0);
+        second_half.report(0.0);
+        second_half.report(0.5);
+        second_half.report(1.0);
+
+        // Nested ranges compose
+        callback.sub_range(0.0, 0.5).sub_range(0.5, 1.0).report(0.5);
+
+        assert_eq!(*values.lock().unwrap(), vec![0.5, 0.75, 1.0, 0.375]);
+    }
+
+    #[test]
+    #[cfg(feature = "whisper")]
+    fn test_progress_callback_converts_percent() {
+        let (callback, values) = recording();
+        let chunk = callback.sub_range(0.0, 0.5);
+
+        progress_callback(
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            40,
+            chunk.as_user_data(),
+        );
+        progress_callback(
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            40,
+            std::ptr::null_mut(),
+        );
+
+        assert_eq!(*values.lock().unwrap(), vec![0.2]);
+    }
+}
@@ -0,0 +1,156 @@
+//! Initial prompt construction
+//!
+//! Helpers for building the whisper initial prompt from a free-form prompt and a
+//! custom vocabulary file. The prompt biases the decoder towards the spelling of
+//! product names, people's names and domain terms.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+/// Load vocabulary terms from a text file
+///
+/// The file contains one term per line. Blank lines and lines starting with `#`
+/// are ignored, and duplicate terms are kept only once.
+pub fn load_vocab_file<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read vocabulary file: {}", path.display()))?;
+
+    Ok(parse_vocab(&content))
+}
+
+/// Parse vocabulary terms from file content
+pub fn parse_vocab(content: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        let term = line.trim();
+        if term.is_empty() || term.starts_with('#') {
+            continue;
+        }
+        if !terms.iter().any(|t| t == term) {
+            terms.push(term.to_string());
+        }
+    }
+
+    terms
+}
+
+/// Build an initial prompt from an optional free-form prompt and vocabulary terms
+///
+/// Vocabulary terms are appended as a comma-separated list so that whisper sees
+/// them as previously transcribed text. Returns `None` if there is nothing to prompt with.
+pub fn build_initial_prompt(prompt: Option<&str>, vocab: &[String]) -> Option<String> {
+    let mut parts: Vec<String> = Vec::new();
+
+    if let Some(prompt) = prompt.map(str::trim).filter(|p| !p.is_empty()) {
+        parts.push(prompt.to_string());
+    }
+
+    if !vocab.is_empty() {
+        parts.push(format!("{}.", vocab.join(", ")));
+    }
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join(" "))
+    }
+}
+
+/// Truncate prompt tokens so that they fit into `max_tokens`
+///
+/// Tokens are dropped from the end. The beginning of the prompt (the user prompt
+/// and the first vocabulary terms) is kept, since it is usually the most important part.
+pub fn truncate_prompt_tokens(tokens: &[i32], max_tokens: usize) -> &[i32] {
+    &tokens[..tokens.len().min(max_tokens)]
+}
+
+/// Fit an initial prompt and chunk context into `max_tokens`
+///
+/// The chunk context is the text spoken right before the audio, so its budget is
+/// reserved first (keeping its last tokens if it alone is too long). The prompt gets
+/// the rest and is truncated with `truncate_prompt_tokens`.
+pub fn fit_prompt_tokens(prompt: &[i32], context: &[i32], max_tokens: usize) -> Vec<i32> {
+    let context = &context[context.len().saturating_sub(max_tokens)..];
+    let mut tokens = truncate_prompt_tokens(prompt, max_tokens - context.len()).to_vec();
+    tokens.extend_from_slice(context);
+    tokens
+}
+
+/// Join an initial prompt and chunk context into one prompt text
+pub fn prompt_with_context(prompt: Option<&str>, context: Option<&str>) -> Option<String> {
+    match (prompt, context) {
+        (Some(prompt), Some(context)) => Some(format!("{} {}", prompt, context)),
+        (prompt, context) => prompt.or(context).map(str::to_string),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vocab_skips_comments_and_duplicates() {
+        let content = "# product names\nOrangeNote\n\n  Kubernetes  \nOrangeNote\nИван Петров\n";
+        let terms = parse_vocab(content);
+        assert_eq!(terms, vec!["OrangeNote", "Kubernetes", "Иван Петров"]);
+    }
+
+    #[test]
+    fn test_build_initial_prompt() {
+        let vocab = vec!["OrangeNote".to_string(), "whisper.cpp".to_string()];
+
+        assert_eq!(
+            build_initial_prompt(Some("Weekly sync."), &vocab).as_deref(),
+            Some("Weekly sync. OrangeNote, whisper.cpp.")
+        );
+        assert_eq!(
+            build_initial_prompt(None, &vocab).as_deref(),
+            Some("OrangeNote, whisper.cpp.")
+        );
+        assert_eq!(
+            build_initial_prompt(Some("Weekly sync."), &[]).as_deref(),
+            Some("Weekly sync.")
+        );
+        assert_eq!(build_initial_prompt(Some("   "), &[]), None);
+        assert_eq!(build_initial_prompt(None, &[]), None);
+    }
+
+    #[test]
+    fn test_truncate_prompt_tokens() {
+        let tokens = [1, 2, 3, 4, 5];
+        assert_eq!(truncate_prompt_tokens(&tokens, 10), &tokens);
+        assert_eq!(truncate_prompt_tokens(&tokens, 2), &[1, 2]);
+        assert!(truncate_prompt_tokens(&tokens, 0).is_empty());
+    }
+
+    #[test]
+    fn test_fit_prompt_tokens_keeps_context_of_long_prompt() {
+        let vocab: Vec<i32> = (0..1000).collect();
+        let context = [2001, 2002, 2003];
+
+        let fitted = fit_prompt_tokens(&vocab, &context, 224);
+        assert_eq!(fitted.len(), 224);
+        assert_eq!(fitted[..221], vocab[..221]);
+        assert_eq!(fitted[221..], context);
+
+        // A context longer than the budget keeps its most recent tokens
+        assert_eq!(fit_prompt_tokens(&vocab, &context, 2), vec![2002, 2003]);
+        assert_eq!(fit_prompt_tokens(&[1, 2], &[], 5), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_prompt_with_context() {
+        assert_eq!(
+            prompt_with_context(Some("Glossary."), Some("about OrangeNote")).as_deref(),
+            Some("Glossary. about OrangeNote")
+        );
+        assert_eq!(
+            prompt_with_context(None, Some("about OrangeNote")).as_deref(),
+            Some("about OrangeNote")
+        );
+        assert_eq!(prompt_with_context(None, None), None);
+    }
+}
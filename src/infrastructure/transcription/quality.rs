@@ -0,0 +1,347 @@
+//! Hallucination detection for transcription results
+//!
+//! Whisper sometimes loops ("Thank you. Thank you. Thank you.") or invents text
+//! over silence. The checks here flag such results so that the transcriber can
+//! decode the audio again with different settings.
+
+use super::result::TranscriptionResult;
+use crate::infrastructure::audio::WHISPER_SAMPLE_RATE;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fmt;
+use std::io::Write;
+
+/// Thresholds for the hallucination checks and the re-decode policy
+#[derive(Debug, Clone)]
+pub struct QualityConfig {
+    /// Longest n-gram (in words) checked for back-to-back repetition
+    pub max_ngram_words: usize,
+    /// Back-to-back repetitions of an n-gram of 2 or more words that are flagged
+    pub max_ngram_repeats: usize,
+    /// Highest accepted gzip compression ratio of the text (whisper uses 2.4)
+    pub max_compression_ratio: f32,
+    /// Highest accepted number of segments per second of audio
+    pub max_segments_per_sec: f32,
+    /// RMS level below which audio under a segment counts as silence
+    pub silence_rms: f32,
+    /// Number of re-decodes after a failed check
+    pub max_retries: usize,
+    /// Temperature added per re-decode
+    pub retry_temperature_step: f32,
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        QualityConfig {
+            max_ngram_words: 6,
+            max_ngram_repeats: 3,
+            max_compression_ratio: 2.4,
+            max_segments_per_sec: 2.0,
+            silence_rms: 0.002,
+            max_retries: 2,
+            retry_temperature_step: 0.2,
+        }
+    }
+}
+
+impl QualityConfig {
+    /// Set the number of re-decodes after a failed check
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+}
+
+/// A reason to distrust a transcription result
+#[derive(Debug, Clone, PartialEq)]
+pub enum QualityIssue {
+    /// An n-gram repeated back to back
+    RepeatedNgram { ngram: String, repeats: usize },
+    /// The text compresses too well, a sign of looping output
+    HighCompressionRatio(f32),
+    /// A segment was transcribed over silent audio
+    TextOverSilence { segment_id: i32, rms: f32 },
+    /// Too many segments for the amount of audio
+    TooManySegments { per_sec: f32 },
+}
+
+impl fmt::Display for QualityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RepeatedNgram { ngram, repeats } => {
+                write!(f, "\"{}\" repeated {} times", ngram, repeats)
+            }
+            Self::HighCompressionRatio(ratio) => write!(f, "compression ratio {:.2}", ratio),
+            Self::TextOverSilence { segment_id, rms } => {
+                write!(f, "segment {} over silence (rms {:.4})", segment_id, rms)
+            }
+            Self::TooManySegments { per_sec } => {
+                write!(f, "{:.1} segments per second", per_sec)
+            }
+        }
+    }
+}
+
+/// Re-decode of a chunk that failed the quality checks
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityRetry {
+    /// Index of the chunk (0 without chunking)
+    pub chunk_index: usize,
+    /// Start of the chunk in the audio, in milliseconds
+    pub start_ms: i64,
+    /// Number of re-decodes that were run
+    pub attempts: usize,
+    /// Issues found in the first decode
+    pub issues: Vec<String>,
+    /// Whether a re-decode passed all checks
+    pub resolved: bool,
+}
+
+/// Check a transcription result for signs of hallucination
+///
+/// # Arguments
+///
+/// * `result` - Result to check, with timestamps relative to `samples`
+/// * `samples` - The 16kHz audio the result was decoded from
+/// * `config` - Thresholds
+///
+/// # Returns
+///
+/// All issues found; empty if the result looks fine
+pub fn check_quality(
+    result: &TranscriptionResult,
+    samples: &[f32],
+    config: &QualityConfig,
+) -> Vec<QualityIssue> {
+    let mut issues = Vec::new();
+    let text = result.full_text();
+
+    if let Some((ngram, repeats)) = longest_repetition(&text, config.max_ngram_words) {
+        if repeats >= config.max_ngram_repeats {
+            issues.push(QualityIssue::RepeatedNgram { ngram, repeats });
+        }
+    }
+
+    let ratio = compression_ratio(&text);
+    if ratio > config.max_compression_ratio {
+        issues.push(QualityIssue::HighCompressionRatio(ratio));
+    }
+
+    for segment in &result.segments {
+        if segment.text.trim().is_empty() {
+            continue;
+        }
+        let start = ms_to_sample(segment.start_ms).min(samples.len());
+        // Zero-length or last segments can end before they start
+        let end = ms_to_sample(segment.end_ms).min(samples.len()).max(start);
+        if let Some(rms) = rms(&samples[start..end]) {
+            if rms < config.silence_rms {
+                issues.push(QualityIssue::TextOverSilence {
+                    segment_id: segment.id,
+                    rms,
+                });
+            }
+        }
+    }
+
+    let duration_secs = samples.len() as f32 / WHISPER_SAMPLE_RATE as f32;
+    if duration_secs > 0.0 {
+        let per_sec = result.segments.len() as f32 / duration_secs;
+        if per_sec > config.max_segments_per_sec {
+            issues.push(QualityIssue::TooManySegments { per_sec });
+        }
+    }
+
+    issues
+}
+
+/// Ratio of text size to its gzip-compressed size
+///
+/// Natural text is around 1.0-2.0; looping text compresses much better. Returns 0.0
+/// for empty text.
+pub fn compression_ratio(text: &str) -> f32 {
+    if text.is_empty() {
+        return 0.0;
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let compressed = encoder
+        .write_all(text.as_bytes())
+        .and_then(|_| encoder.finish());
+    match compressed {
+        Ok(compressed) if !compressed.is_empty() => text.len() as f32 / compressed.len() as f32,
+        _ => 0.0,
+    }
+}
+
+/// Find the n-gram (2 to `max_words` words) repeated back to back most often
+///
+/// Words are compared case-insensitively and without punctuation.
+///
+/// # Returns
+///
+/// The n-gram and its number of consecutive occurrences, if any n-gram occurs twice in a row
+fn longest_repetition(text: &str, max_words: usize) -> Option<(String, usize)> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let mut best: Option<(String, usize)> = None;
+    for n in 2..=max_words.min(words.len() / 2) {
+        for start in 0..n {
+            let mut run_start = start;
+            let mut repeats = 1;
+            let mut i = start + n;
+            while i + n <= words.len() {
+                if words[i..i + n] == words[i - n..i] {
+                    repeats += 1;
+                } else {
+                    run_start = i;
+                    repeats = 1;
+                }
+                if repeats > 1 && best.as_ref().is_none_or(|(_, most)| repeats > *most) {
+                    best = Some((words[run_start..run_start + n].join(" "), repeats));
+                }
+                i += n;
+            }
+        }
+    }
+    best
+}
+
+fn ms_to_sample(ms: i64) -> usize {
+    (ms.max(0) as u64 * WHISPER_SAMPLE_RATE as u64 / 1000) as usize
+}
+
+fn rms(samples: &[f32]) -> Option<f32> {
+    if samples.is_empty() {
+        return None;
+    }
+    let sum: f32 = samples.iter().map(|s| s * s).sum();
+    Some((sum / samples.len() as f32).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::transcription::result::Segment;
+
+    fn result(segments: &[(i64, i64, &str)]) -> TranscriptionResult {
+        TranscriptionResult {
+            language: "en".to_string(),
+            segments: segments
+                .iter()
+                .enumerate()
+                .map(|(i, (start_ms, end_ms, text))| Segment {
+                    id: i as i32,
+                    start_ms: *start_ms,
+                    end_ms: *end_ms,
+                    text: text.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// One second of a 440Hz tone per entry of `loud`, silence otherwise
+    fn audio(loud: &[bool]) -> Vec<f32> {
+        loud.iter()
+            .flat_map(|&loud| {
+                (0..WHISPER_SAMPLE_RATE).map(move |i| {
+                    if loud {
+                        0.3 * (i as f32 * 440.0 * std::f32::consts::TAU / 16000.0).sin()
+                    } else {
+                        0.0
+                    }
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_longest_repetition() {
+        assert_eq!(
+            longest_repetition("Thank you. Thank you. Thank you.", 6),
+            Some(("thank you".to_string(), 3))
+        );
+        assert_eq!(
+            longest_repetition("So I said, I think we should go, I think we should go.", 6),
+            Some(("i think we should go".to_string(), 2))
+        );
+        assert_eq!(
+            longest_repetition("The quick brown fox jumps over the lazy dog", 6),
+            None
+        );
+    }
+
+    #[test]
+    fn test_compression_ratio() {
+        assert_eq!(compression_ratio(""), 0.0);
+        let natural = "Whisper is a general-purpose speech recognition model trained on a large \
+                       dataset of diverse audio. It can also translate and identify languages.";
+        assert!(compression_ratio(natural) < 2.0);
+        let looping = "I'm going to go to the store. ".repeat(20);
+        assert!(compression_ratio(&looping) > 2.4);
+    }
+
+    #[test]
+    fn test_clean_result_passes() {
+        let result = result(&[(0, 2000, " Hello there."), (2000, 4000, " How are you?")]);
+        let issues = check_quality(&result, &audio(&[true; 4]), &QualityConfig::default());
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_looping_result_flagged() {
+        let result = result(&[(0, 4000, " Thank you. Thank you. Thank you. Thank you.")]);
+        let issues = check_quality(&result, &audio(&[true; 4]), &QualityConfig::default());
+        assert!(matches!(
+            issues[0],
+            QualityIssue::RepeatedNgram { repeats: 4, .. }
+        ));
+    }
+
+    #[test]
+    fn test_text_over_silence_flagged() {
+        let result = result(&[(0, 1000, " Hello."), (2000, 3000, " Subscribe!")]);
+        let issues = check_quality(
+            &result,
+            &audio(&[true, true, false, true]),
+            &QualityConfig::default(),
+        );
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(
+            issues[0],
+            QualityIssue::TextOverSilence { segment_id: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_inverted_segment_ignored() {
+        let result = result(&[(0, 1000, " Hello."), (1500, 1200, " there.")]);
+        let issues = check_quality(&result, &audio(&[true, true]), &QualityConfig::default());
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_too_many_segments_flagged() {
+        let segments: Vec<(i64, i64, &str)> =
+            (0..6).map(|i| (i * 300, i * 300 + 300, " word")).collect();
+        let issues = check_quality(
+            &result(&segments),
+            &audio(&[true, true]),
+            &QualityConfig::default(),
+        );
+        assert!(issues
+            .iter()
+            .any(|issue| matches!(issue, QualityIssue::TooManySegments { .. })));
+    }
+}
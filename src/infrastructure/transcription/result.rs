@@ -0,0 +1,474 @@
+//! Transcription results
+//!
+//! Segments, tokens and words produced by a transcription backend, independent of
+//! the backend that produced them.
+
+use super::quality::QualityRetry;
+
+/// A single transcribed segment
+#[derive(Debug, Clone, Default)]
+pub struct Segment {
+    /// Segment index
+    pub id: i32,
+    /// Start time in milliseconds
+    pub start_ms: i64,
+    /// End time in milliseconds
+    pub end_ms: i64,
+    /// Transcribed text
+    pub text: String,
+    /// Confidence score (0.0 - 1.0): geometric mean of the text token probabilities
+    pub confidence: f32,
+    /// Mean log-probability of the text tokens (closer to 0.0 is more certain)
+    pub avg_logprob: f32,
+    /// Gzip compression ratio of the text (high values indicate repetition)
+    pub compression_ratio: f32,
+    /// Lowest probability of any text token (0.0 - 1.0)
+    pub min_token_prob: f32,
+    /// Probability that the segment's audio contains no speech (0.0 - 1.0)
+    pub no_speech_prob: f32,
+    /// Individual tokens with probabilities
+    pub tokens: Vec<Token>,
+    /// Language of the decode window or chunk this segment comes from
+    pub language: String,
+    /// Whether a different speaker talks after this segment (tinydiarize models only)
+    pub speaker_turn_next: bool,
+}
+
+impl Segment {
+    /// Format start time as HH:MM:SS.mmm
+    pub fn start_time_formatted(&self) -> String {
+        format_timestamp(self.start_ms)
+    }
+
+    /// Format end time as HH:MM:SS.mmm
+    pub fn end_time_formatted(&self) -> String {
+        format_timestamp(self.end_ms)
+    }
+
+    /// Group the segment's tokens into words with timestamps
+    pub fn words(&self) -> Vec<Word> {
+        group_words(&self.tokens)
+    }
+
+    /// Remove special tokens from the tokens and the text
+    pub fn drop_special_tokens(&mut self) {
+        self.tokens.retain(|token| !token.is_special());
+        self.text = strip_control_tokens(&self.text);
+    }
+}
+
+/// A single token with probability and timestamps
+#[derive(Debug, Clone, Default)]
+pub struct Token {
+    /// Token ID in the model's vocabulary
+    pub id: i32,
+    /// Token text
+    pub text: String,
+    /// Probability (0.0 - 1.0)
+    pub probability: f32,
+    /// Log-probability
+    pub logprob: f32,
+    /// Probability of the token's timestamp (whisper's `pt`)
+    pub timestamp_prob: f32,
+    /// Sum of the probabilities of all timestamp tokens (whisper's `ptsum`)
+    pub timestamp_prob_sum: f32,
+    /// Start time in milliseconds (whisper's `t0`)
+    pub start_ms: i64,
+    /// End time in milliseconds (whisper's `t1`)
+    pub end_ms: i64,
+    /// DTW-aligned timestamp in milliseconds, if DTW was enabled
+    pub dtw_ms: Option<i64>,
+    /// Whether this is a special or timestamp token such as `[_BEG_]` or `<|endoftext|>`
+    pub special: bool,
+}
+
+impl Token {
+    /// Whether the token is a special token, by flag or by its text
+    pub fn is_special(&self) -> bool {
+        self.special || is_control_token(&self.text)
+    }
+}
+
+/// A word assembled from one or more tokens
+#[derive(Debug, Clone, PartialEq)]
+pub struct Word {
+    /// Word text, including attached punctuation
+    pub text: String,
+    /// Start time in milliseconds
+    pub start_ms: i64,
+    /// End time in milliseconds
+    pub end_ms: i64,
+    /// Mean probability of the word's tokens (0.0 - 1.0)
+    pub probability: f32,
+    /// DTW-aligned timestamp of the word's first token in milliseconds, if available
+    pub dtw_ms: Option<i64>,
+}
+
+/// Replace heuristic token timestamps with DTW timestamps
+///
+/// Each token starts at its DTW timestamp and ends where the next aligned token
+/// starts. Tokens without a DTW timestamp keep their heuristic timing.
+fn apply_dtw_to_tokens(tokens: &mut [Token]) {
+    for i in 0..tokens.len() {
+        let Some(start_ms) = tokens[i].dtw_ms else {
+            continue;
+        };
+        let next_ms = tokens[i + 1..]
+            .iter()
+            .filter(|t| !t.is_special())
+            .find_map(|t| t.dtw_ms)
+            .filter(|&next| next >= start_ms);
+
+        tokens[i].start_ms = start_ms;
+        tokens[i].end_ms = next_ms.unwrap_or(tokens[i].end_ms.max(start_ms));
+    }
+}
+
+/// Check whether a token is a whisper control token such as `[_BEG_]` or `<|endoftext|>`
+fn is_control_token(text: &str) -> bool {
+    (text.starts_with("[_") && text.ends_with(']'))
+        || (text.starts_with("<|") && text.ends_with("|>"))
+}
+
+/// Remove whisper control tokens such as `[_BEG_]` or `<|en|>` from text
+fn strip_control_tokens(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while !rest.is_empty() {
+        let end = if rest.starts_with("[_") {
+            rest.find(']').map(|i| i + 1)
+        } else if rest.starts_with("<|") {
+            rest.find("|>").map(|i| i + 2)
+        } else {
+            None
+        };
+        match end {
+            Some(end) => rest = &rest[end..],
+            None => {
+                let c = rest.chars().next().unwrap_or_default();
+                stripped.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    stripped
+}
+
+/// Group sub-word tokens into words
+///
+/// Whisper tokens that start with a space begin a new word; all other tokens
+/// (word pieces and punctuation) are appended to the current word.
+fn group_words(tokens: &[Token]) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut token_counts: Vec<usize> = Vec::new();
+
+    for token in tokens {
+        if token.text.is_empty() || token.is_special() {
+            continue;
+        }
+
+        let starts_word = token.text.starts_with(' ') || words.is_empty();
+        if starts_word {
+            words.push(Word {
+                text: token.text.trim_start().to_string(),
+                start_ms: token.start_ms,
+                end_ms: token.end_ms,
+                probability: token.probability,
+                dtw_ms: token.dtw_ms,
+            });
+            token_counts.push(1);
+        } else if let (Some(word), Some(count)) = (words.last_mut(), token_counts.last_mut()) {
+            word.text.push_str(&token.text);
+            word.end_ms = word.end_ms.max(token.end_ms);
+            word.probability += token.probability;
+            *count += 1;
+        }
+    }
+
+    for (word, count) in words.iter_mut().zip(token_counts) {
+        word.probability /= count as f32;
+    }
+
+    words.retain(|word| !word.text.trim().is_empty());
+    words
+}
+
+/// Complete transcription result
+#[derive(Debug, Clone, Default)]
+pub struct TranscriptionResult {
+    /// Detected language
+    pub language: String,
+    /// Transcribed segments
+    pub segments: Vec<Segment>,
+    /// Whether the transcription was cancelled and only covers part of the audio
+    pub partial: bool,
+    /// Chunks that failed the hallucination checks and were decoded again
+    pub quality_retries: Vec<QualityRetry>,
+}
+
+impl TranscriptionResult {
+    /// Get full transcript as a single string
+    pub fn full_text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Get average confidence across all segments
+    pub fn average_confidence(&self) -> f32 {
+        if self.segments.is_empty() {
+            return 0.0;
+        }
+        self.segments.iter().map(|s| s.confidence).sum::<f32>() / self.segments.len() as f32
+    }
+
+    /// Get the mean of the segments' average token log-probabilities
+    pub fn average_logprob(&self) -> f32 {
+        if self.segments.is_empty() {
+            return 0.0;
+        }
+        self.segments.iter().map(|s| s.avg_logprob).sum::<f32>() / self.segments.len() as f32
+    }
+
+    /// Get the lowest token probability across all segments (1.0 without segments)
+    pub fn min_token_prob(&self) -> f32 {
+        self.segments
+            .iter()
+            .map(|s| s.min_token_prob)
+            .fold(1.0, f32::min)
+    }
+
+    /// Remove special tokens such as `[_BEG_]` and `<|endoftext|>` from all segments
+    pub fn drop_special_tokens(&mut self) {
+        for segment in &mut self.segments {
+            segment.drop_special_tokens();
+        }
+    }
+
+    /// Use DTW timestamps instead of the heuristic ones for tokens (and so words)
+    ///
+    /// Segment boundaries are left unchanged. Has no effect if the context was
+    /// created without DTW.
+    pub fn apply_dtw_timestamps(&mut self) {
+        for segment in &mut self.segments {
+            apply_dtw_to_tokens(&mut segment.tokens);
+        }
+    }
+
+    /// Get total speech time per language in milliseconds, longest first
+    pub fn language_durations(&self) -> Vec<(String, i64)> {
+        let mut durations: Vec<(String, i64)> = Vec::new();
+
+        for segment in &self.segments {
+            let duration = (segment.end_ms - segment.start_ms).max(0);
+            match durations
+                .iter_mut()
+                .find(|(lang, _)| *lang == segment.language)
+            {
+                Some((_, total)) => *total += duration,
+                None => durations.push((segment.language.clone(), duration)),
+            }
+        }
+
+        durations.sort_by_key(|(_, duration)| std::cmp::Reverse(*duration));
+        durations
+    }
+
+    /// Get a speaker number (1 or 2) for each segment from the predicted speaker turns
+    ///
+    /// tinydiarize only predicts where the speaker changes, not who is speaking, so
+    /// the labels alternate between two speakers at every turn.
+    pub fn speaker_numbers(&self) -> Vec<usize> {
+        let mut speaker = 1;
+        self.segments
+            .iter()
+            .map(|segment| {
+                let current = speaker;
+                if segment.speaker_turn_next {
+                    speaker = 3 - speaker;
+                }
+                current
+            })
+            .collect()
+    }
+}
+
+/// Format milliseconds as HH:MM:SS.mmm
+pub(crate) fn format_timestamp(ms: i64) -> String {
+    let total_seconds = ms / 1000;
+    let milliseconds = ms % 1000;
+    let seconds = total_seconds % 60;
+    let minutes = (total_seconds / 60) % 60;
+    let hours = total_seconds / 3600;
+
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        hours, minutes, seconds, milliseconds
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speaker_numbers_alternate_at_turns() {
+        let segment = |speaker_turn_next: bool| Segment {
+            speaker_turn_next,
+            ..Default::default()
+        };
+        let result = TranscriptionResult {
+            language: "en".to_string(),
+            segments: vec![segment(false), segment(true), segment(true), segment(false)],
+            ..Default::default()
+        };
+        assert_eq!(result.speaker_numbers(), vec![1, 1, 2, 1]);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "00:00:00.000");
+        assert_eq!(format_timestamp(1000), "00:00:01.000");
+        assert_eq!(format_timestamp(61000), "00:01:01.000");
+        assert_eq!(format_timestamp(3661000), "01:01:01.000");
+        assert_eq!(format_timestamp(3661500), "01:01:01.500");
+    }
+
+    fn token(text: &str, start_ms: i64, end_ms: i64, probability: f32) -> Token {
+        Token {
+            text: text.to_string(),
+            probability,
+            start_ms,
+            end_ms,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_drop_special_tokens() {
+        let mut result = TranscriptionResult {
+            segments: vec![Segment {
+                text: "[_BEG_] Hello world.<|endoftext|>".to_string(),
+                tokens: vec![
+                    Token {
+                        id: 50364,
+                        special: true,
+                        ..token("[_BEG_]", 0, 0, 1.0)
+                    },
+                    token(" Hello", 0, 400, 0.9),
+                    token(" world", 400, 800, 0.8),
+                    token(".", 800, 850, 0.9),
+                    Token {
+                        id: 50365,
+                        special: true,
+                        ..token("[_TT_50]", 1000, 1000, 0.7)
+                    },
+                    // Recognized by its text even without the flag
+                    token("<|endoftext|>", 1000, 1000, 1.0),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        result.drop_special_tokens();
+
+        let segment = &result.segments[0];
+        assert_eq!(segment.text, " Hello world.");
+        let texts: Vec<&str> = segment.tokens.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, vec![" Hello", " world", "."]);
+    }
+
+    #[test]
+    fn test_strip_control_tokens_keeps_brackets_in_text() {
+        assert_eq!(strip_control_tokens("<|en|>[_BEG_]Hi"), "Hi");
+        assert_eq!(strip_control_tokens("[laughs] a < b"), "[laughs] a < b");
+        assert_eq!(strip_control_tokens("unclosed [_BEG"), "unclosed [_BEG");
+        assert_eq!(strip_control_tokens("Привет<|endoftext|>"), "Привет");
+    }
+
+    #[test]
+    fn test_group_words() {
+        let tokens = vec![
+            token("[_BEG_]", 0, 0, 1.0),
+            token(" Hello", 0, 400, 0.9),
+            token(" Orange", 400, 700, 0.8),
+            token("Note", 700, 1000, 0.6),
+            token("!", 1000, 1050, 1.0),
+            token(" Bye", 1200, 1500, 0.5),
+            token("<|endoftext|>", 1500, 1500, 1.0),
+        ];
+        let words = group_words(&tokens);
+
+        assert_eq!(words.len(), 3);
+        assert_eq!(words[0].text, "Hello");
+        assert_eq!((words[0].start_ms, words[0].end_ms), (0, 400));
+        assert_eq!(words[1].text, "OrangeNote!");
+        assert_eq!((words[1].start_ms, words[1].end_ms), (400, 1050));
+        assert!((words[1].probability - 0.8).abs() < 1e-6);
+        assert_eq!(words[2].text, "Bye");
+    }
+
+    #[test]
+    fn test_group_words_without_leading_space() {
+        let tokens = vec![token("Привет", 0, 300, 0.9), token(" мир", 300, 600, 0.7)];
+        let words = group_words(&tokens);
+
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].text, "Привет");
+        assert_eq!(words[1].text, "мир");
+    }
+
+    #[test]
+    fn test_apply_dtw_to_tokens() {
+        let mut tokens = vec![
+            token("[_BEG_]", 0, 0, 1.0),
+            token(" Hello", 0, 500, 0.9),
+            token(" world", 500, 1000, 0.9),
+            token(" again", 1000, 1500, 0.9),
+        ];
+        tokens[1].dtw_ms = Some(120);
+        tokens[2].dtw_ms = Some(640);
+        tokens[3].dtw_ms = None;
+
+        apply_dtw_to_tokens(&mut tokens);
+
+        assert_eq!((tokens[0].start_ms, tokens[0].end_ms), (0, 0));
+        assert_eq!((tokens[1].start_ms, tokens[1].end_ms), (120, 640));
+        assert_eq!((tokens[2].start_ms, tokens[2].end_ms), (640, 1000));
+        assert_eq!((tokens[3].start_ms, tokens[3].end_ms), (1000, 1500));
+
+        let words = group_words(&tokens);
+        assert_eq!(words[0].dtw_ms, Some(120));
+        assert_eq!(words[2].dtw_ms, None);
+    }
+
+    #[test]
+    fn test_language_durations() {
+        let segment = |start_ms: i64, end_ms: i64, language: &str| Segment {
+            id: 0,
+            start_ms,
+            end_ms,
+            text: String::new(),
+            confidence: 1.0,
+            language: language.to_string(),
+            ..Default::default()
+        };
+        let result = TranscriptionResult {
+            language: "ru".to_string(),
+            segments: vec![
+                segment(0, 4000, "ru"),
+                segment(4000, 10000, "en"),
+                segment(10000, 13000, "ru"),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            result.language_durations(),
+            vec![("ru".to_string(), 7000), ("en".to_string(), 6000)]
+        );
+    }
+}
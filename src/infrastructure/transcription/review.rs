@@ -0,0 +1,205 @@
+//! Low-confidence review
+//!
+//! Flags the words and segments whisper was unsure about, so editors can check
+//! only those parts of a transcript. Words are flagged by their mean token
+//! probability, segments by their confidence or by containing a flagged word.
+
+use super::result::{format_timestamp, Segment, TranscriptionResult, Word};
+
+/// A segment that needs review
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewItem {
+    /// ID of the segment
+    pub segment_id: i32,
+    /// Start time in milliseconds
+    pub start_ms: i64,
+    /// End time in milliseconds
+    pub end_ms: i64,
+    /// Segment text
+    pub text: String,
+    /// Segment confidence (0.0 - 1.0)
+    pub confidence: f32,
+    /// Words below the threshold
+    pub uncertain_words: Vec<Word>,
+}
+
+impl ReviewItem {
+    /// Format start time as HH:MM:SS.mmm
+    pub fn start_time_formatted(&self) -> String {
+        format_timestamp(self.start_ms)
+    }
+
+    /// Format end time as HH:MM:SS.mmm
+    pub fn end_time_formatted(&self) -> String {
+        format_timestamp(self.end_ms)
+    }
+}
+
+/// Get the words of a segment with a probability below `threshold`
+pub fn uncertain_words(segment: &Segment, threshold: f32) -> Vec<Word> {
+    segment
+        .words()
+        .into_iter()
+        .filter(|word| word.probability < threshold)
+        .collect()
+}
+
+/// Check whether a segment has a confidence or a word below `threshold`
+pub fn needs_review(segment: &Segment, threshold: f32) -> bool {
+    segment.confidence < threshold || !uncertain_words(segment, threshold).is_empty()
+}
+
+/// Collect the segments that need review, in order
+///
+/// # Arguments
+///
+/// * `result` - Transcription to review
+/// * `threshold` - Probability (0.0 - 1.0) below which words and segments are flagged
+///
+/// # Returns
+///
+/// One item per flagged segment
+pub fn review_items(result: &TranscriptionResult, threshold: f32) -> Vec<ReviewItem> {
+    result
+        .segments
+        .iter()
+        .filter(|segment| needs_review(segment, threshold))
+        .map(|segment| ReviewItem {
+            segment_id: segment.id,
+            start_ms: segment.start_ms,
+            end_ms: segment.end_ms,
+            text: segment.text.trim().to_string(),
+            confidence: segment.confidence,
+            uncertain_words: uncertain_words(segment, threshold),
+        })
+        .collect()
+}
+
+/// Get the segment text with uncertain words wrapped in markers
+///
+/// Words are located in the segment text in order, so spacing and punctuation of
+/// the text are kept. A word that cannot be found is left unmarked.
+///
+/// # Arguments
+///
+/// * `segment` - Segment whose text is marked
+/// * `threshold` - Probability below which words are marked
+/// * `open` - Text inserted before an uncertain word (e.g. `[?`)
+/// * `close` - Text inserted after an uncertain word (e.g. `?]`)
+pub fn mark_uncertain_words(segment: &Segment, threshold: f32, open: &str, close: &str) -> String {
+    let mut marked = String::with_capacity(segment.text.len());
+    let mut rest = segment.text.as_str();
+
+    for word in segment.words() {
+        if word.probability >= threshold {
+            continue;
+        }
+        let Some(start) = find_word(rest, &word.text) else {
+            continue;
+        };
+        let end = start + word.text.len();
+        marked.push_str(&rest[..start]);
+        marked.push_str(open);
+        marked.push_str(&rest[start..end]);
+        marked.push_str(close);
+        rest = &rest[end..];
+    }
+
+    marked.push_str(rest);
+    marked
+}
+
+/// Find `word` in `text` as a whole word, i.e. between whitespace or the text bounds
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    if word.is_empty() {
+        return None;
+    }
+    text.match_indices(word)
+        .map(|(start, _)| start)
+        .find(|&start| {
+            let end = start + word.len();
+            let before = text[..start].chars().next_back();
+            let after = text[end..].chars().next();
+            before.is_none_or(char::is_whitespace) && after.is_none_or(char::is_whitespace)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::transcription::result::Token;
+
+    fn segment(id: i32, text: &str, tokens: &[(&str, f32)]) -> Segment {
+        let tokens: Vec<Token> = tokens
+            .iter()
+            .map(|(text, probability)| Token {
+                text: text.to_string(),
+                probability: *probability,
+                ..Default::default()
+            })
+            .collect();
+        Segment {
+            id,
+            start_ms: id as i64 * 1000,
+            end_ms: id as i64 * 1000 + 1000,
+            text: text.to_string(),
+            confidence: 0.9,
+            tokens,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_mark_uncertain_words() {
+        let seg = segment(
+            0,
+            " Ask Kubernetes, then the API.",
+            &[
+                (" Ask", 0.9),
+                (" Kuber", 0.3),
+                ("netes", 0.4),
+                (",", 0.5),
+                (" then", 0.95),
+                (" the", 0.2),
+                (" API", 0.9),
+                (".", 0.9),
+            ],
+        );
+
+        assert_eq!(
+            mark_uncertain_words(&seg, 0.5, "[?", "?]"),
+            " Ask [?Kubernetes,?] then [?the?] API."
+        );
+        assert_eq!(
+            mark_uncertain_words(&seg, 0.1, "[?", "?]"),
+            " Ask Kubernetes, then the API."
+        );
+    }
+
+    #[test]
+    fn test_review_items() {
+        let result = TranscriptionResult {
+            segments: vec![
+                segment(
+                    0,
+                    " Fine here.",
+                    &[(" Fine", 0.9), (" here", 0.9), (".", 0.9)],
+                ),
+                segment(1, " Mumbled words", &[(" Mumbled", 0.2), (" words", 0.8)]),
+                Segment {
+                    confidence: 0.4,
+                    ..segment(2, " Low segment", &[(" Low", 0.8), (" segment", 0.8)])
+                },
+            ],
+            ..Default::default()
+        };
+
+        let items = review_items(&result, 0.5);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].segment_id, 1);
+        assert_eq!(items[0].text, "Mumbled words");
+        assert_eq!(items[0].uncertain_words[0].text, "Mumbled");
+        assert_eq!(items[1].segment_id, 2);
+        assert!(items[1].uncertain_words.is_empty());
+    }
+}
@@ -0,0 +1,181 @@
+//! Live segment streaming
+//!
+//! whisper.cpp calls `new_segment_callback` whenever it finalizes segments. A
+//! `SegmentCallback` receives those segments as soon as they are available, with
+//! timestamps already shifted to the position in the whole file.
+
+use super::result::Segment;
+#[cfg(feature = "whisper")]
+use super::whisper::context::{lang_str, ResultSource};
+#[cfg(feature = "whisper")]
+use super::whisper::ffi;
+use std::fmt;
+#[cfg(feature = "whisper")]
+use std::os::raw::{c_int, c_void};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+/// Callback receiving each segment as soon as whisper finalizes it
+///
+/// In chunked transcription, segments that start before the end of the last emitted
+/// segment are skipped, so the overlap between chunks is not streamed twice. The
+/// final merged result may still differ slightly from the streamed segments.
+#[derive(Clone)]
+pub struct SegmentCallback {
+    callback: Arc<dyn Fn(&Segment) + Send + Sync>,
+    offset_ms: i64,
+    last_end_ms: Arc<AtomicI64>,
+}
+
+impl SegmentCallback {
+    /// Wrap a closure receiving segments
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: Fn(&Segment) + Send + Sync + 'static,
+    {
+        SegmentCallback {
+            callback: Arc::new(callback),
+            offset_ms: 0,
+            last_end_ms: Arc::new(AtomicI64::new(i64::MIN)),
+        }
+    }
+
+    /// Create a callback for audio starting at `offset_ms` within the file
+    ///
+    /// Clones share the overlap tracking, so chunks of one file must use callbacks
+    /// derived from the same `SegmentCallback`.
+    pub fn with_offset(&self, offset_ms: i64) -> Self {
+        SegmentCallback {
+            callback: Arc::clone(&self.callback),
+            offset_ms,
+            last_end_ms: Arc::clone(&self.last_end_ms),
+        }
+    }
+
+    /// Emit a segment with timestamps relative to this callback's offset
+    pub fn emit(&self, mut segment: Segment) {
+        segment.start_ms += self.offset_ms;
+        segment.end_ms += self.offset_ms;
+        for token in &mut segment.tokens {
+            token.start_ms += self.offset_ms;
+            token.end_ms += self.offset_ms;
+            token.dtw_ms = token.dtw_ms.map(|ms| ms + self.offset_ms);
+        }
+
+        if segment.start_ms < self.last_end_ms.load(Ordering::SeqCst) {
+            return;
+        }
+        self.last_end_ms.store(segment.end_ms, Ordering::SeqCst);
+
+        (self.callback)(&segment);
+    }
+
+    /// Pointer passed as `new_segment_callback_user_data`
+    ///
+    /// The callback must not move while whisper holds the pointer.
+    #[cfg(feature = "whisper")]
+    pub(crate) fn as_user_data(&self) -> *mut c_void {
+        self as *const SegmentCallback as *mut c_void
+    }
+}
+
+impl fmt::Debug for SegmentCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SegmentCallback")
+            .field("offset_ms", &self.offset_ms)
+            .finish()
+    }
+}
+
+/// `new_segment_callback` for whisper.cpp; `user_data` comes from `SegmentCallback::as_user_data`
+///
+/// The `n_new` most recent segments are the newly finalized ones.
+#[cfg(feature = "whisper")]
+pub(crate) extern "C" fn new_segment_callback(
+    ctx: *mut ffi::WhisperContext,
+    state: *mut ffi::WhisperState,
+    n_new: c_int,
+    user_data: *mut c_void,
+) {
+    if user_data.is_null() || ctx.is_null() {
+        return;
+    }
+    let callback = unsafe { &*(user_data as *const SegmentCallback) };
+
+    // whisper_full passes the context's default state, whisper_full_with_state its own
+    let source = if state.is_null() {
+        ResultSource::Context(ctx)
+    } else {
+        ResultSource::State(ctx, state)
+    };
+
+    unsafe {
+        let n_segments = source.n_segments();
+        let language = lang_str(source.lang_id());
+        for i in (n_segments - n_new).max(0)..n_segments {
+            callback.emit(source.read_segment(i, &language));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn segment(start_ms: i64, end_ms: i64, text: &str) -> Segment {
+        Segment {
+            id: 0,
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+            confidence: 1.0,
+            language: "en".to_string(),
+            ..Default::default()
+        }
+    }
+
+    type Seen = Arc<Mutex<Vec<(i64, i64, String)>>>;
+
+    fn recording() -> (SegmentCallback, Seen) {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let callback = SegmentCallback::new(move |segment| {
+            sink.lock()
+                .unwrap()
+                .push((segment.start_ms, segment.end_ms, segment.text.clone()));
+        });
+        (callback, seen)
+    }
+
+    #[test]
+    fn test_emit_applies_offset() {
+        let (callback, seen) = recording();
+
+        callback
+            .with_offset(60000)
+            .emit(segment(1000, 2500, "Hello"));
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![(61000, 62500, "Hello".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_emit_skips_chunk_overlap() {
+        let (callback, seen) = recording();
+
+        // First chunk ends at 10s, second chunk starts at 8s (2s overlap)
+        let first = callback.with_offset(0);
+        first.emit(segment(0, 5000, "one"));
+        first.emit(segment(5000, 9500, "two"));
+
+        let second = callback.with_offset(8000);
+        second.emit(segment(0, 1500, "two again"));
+        second.emit(segment(1500, 4000, "three"));
+
+        let texts: Vec<String> = seen.lock().unwrap().iter().map(|s| s.2.clone()).collect();
+        assert_eq!(texts, vec!["one", "two", "three"]);
+    }
+}
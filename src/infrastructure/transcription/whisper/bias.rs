@@ -0,0 +1,143 @@
+//! Hotword biasing through whisper's logits filter callback
+//!
+//! Boosted words are tokenized once per run and a logit bias is added to their
+//! tokens while decoding, before sampling. Unlike an initial prompt, the bias does
+//! not use up prompt tokens and applies to every decode window.
+
+use super::ffi;
+use std::os::raw::{c_float, c_int, c_void};
+
+/// Logit bias for the token sequences of boosted words
+///
+/// The first token of each word is always boosted. Later tokens of a word are
+/// only boosted right after the preceding tokens of that word were decoded, so a
+/// long word does not pull unrelated text towards its sub-word pieces.
+#[derive(Debug, Clone)]
+pub struct TokenBias {
+    words: Vec<Vec<ffi::WhisperToken>>,
+    bias: f32,
+}
+
+impl TokenBias {
+    /// Create a bias from tokenized words
+    ///
+    /// # Arguments
+    ///
+    /// * `words` - Token sequences of the boosted words (empty sequences are ignored)
+    /// * `bias` - Value added to the logits of the boosted tokens
+    pub fn new(words: Vec<Vec<ffi::WhisperToken>>, bias: f32) -> Self {
+        TokenBias {
+            words: words.into_iter().filter(|word| !word.is_empty()).collect(),
+            bias,
+        }
+    }
+
+    /// Whether there is nothing to boost
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Add the bias to the logits of the tokens that may come next
+    ///
+    /// # Arguments
+    ///
+    /// * `history` - Tokens decoded so far in the current segment
+    /// * `logits` - Logits for the next token, indexed by token id
+    pub fn apply(&self, history: &[ffi::WhisperToken], logits: &mut [f32]) {
+        for word in &self.words {
+            let next = word[matched_prefix(history, word)];
+            if let Some(logit) = usize::try_from(next)
+                .ok()
+                .and_then(|index| logits.get_mut(index))
+            {
+                *logit += self.bias;
+            }
+        }
+    }
+
+    /// Get the opaque user data pointer passed to `logits_filter_callback`
+    pub(crate) fn as_user_data(&self) -> *mut c_void {
+        self as *const TokenBias as *mut c_void
+    }
+}
+
+/// Length of the longest proper prefix of `word` that `history` ends with
+fn matched_prefix(history: &[ffi::WhisperToken], word: &[ffi::WhisperToken]) -> usize {
+    (1..word.len())
+        .rev()
+        .find(|&len| history.ends_with(&word[..len]))
+        .unwrap_or(0)
+}
+
+/// Logits filter callback for whisper.cpp
+///
+/// `user_data` must point to a `TokenBias` that outlives the `whisper_full` call.
+pub(crate) extern "C" fn logits_filter_callback(
+    ctx: *mut ffi::WhisperContext,
+    _state: *mut ffi::WhisperState,
+    tokens: *const ffi::WhisperTokenData,
+    n_tokens: c_int,
+    logits: *mut c_float,
+    user_data: *mut c_void,
+) {
+    if user_data.is_null() || logits.is_null() {
+        return;
+    }
+
+    let bias = unsafe { &*(user_data as *const TokenBias) };
+    let n_vocab = unsafe { ffi::whisper_n_vocab(ctx) }.max(0) as usize;
+    let logits = unsafe { std::slice::from_raw_parts_mut(logits, n_vocab) };
+
+    let history: Vec<ffi::WhisperToken> = if tokens.is_null() || n_tokens <= 0 {
+        Vec::new()
+    } else {
+        unsafe { std::slice::from_raw_parts(tokens, n_tokens as usize) }
+            .iter()
+            .map(|token| token.id)
+            .collect()
+    };
+
+    bias.apply(&history, logits);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_tokens_are_boosted() {
+        let bias = TokenBias::new(vec![vec![2, 3], vec![5], vec![]], 1.5);
+        let mut logits = vec![0.0; 8];
+
+        bias.apply(&[], &mut logits);
+
+        assert_eq!(logits, vec![0.0, 0.0, 1.5, 0.0, 0.0, 1.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_continuation_token_boosted_after_prefix() {
+        let bias = TokenBias::new(vec![vec![2, 3, 4]], 1.0);
+
+        let mut logits = vec![0.0; 8];
+        bias.apply(&[7, 2], &mut logits);
+        assert_eq!(logits[3], 1.0);
+        assert_eq!(logits[2], 0.0);
+
+        let mut logits = vec![0.0; 8];
+        bias.apply(&[2, 3], &mut logits);
+        assert_eq!(logits[4], 1.0);
+
+        // After the complete word, the word can start again
+        let mut logits = vec![0.0; 8];
+        bias.apply(&[2, 3, 4], &mut logits);
+        assert_eq!(logits[2], 1.0);
+    }
+
+    #[test]
+    fn test_out_of_vocab_tokens_ignored() {
+        let bias = TokenBias::new(vec![vec![100], vec![-1]], 1.0);
+        let mut logits = vec![0.0; 4];
+        bias.apply(&[], &mut logits);
+        assert_eq!(logits, vec![0.0; 4]);
+    }
+}
@@ -4,17 +4,48 @@
 //! It handles memory management, error handling, and provides convenient methods
 //! for transcription and result extraction.
 
+use super::bias::{logits_filter_callback, TokenBias};
 use super::ffi;
+use super::language::{rank_languages, LanguageProbability};
+use super::model_manager::ModelSize;
+use super::utf8::Utf8TokenDecoder;
+use crate::infrastructure::transcription::cancel::{abort_callback, CancellationToken};
+use crate::infrastructure::transcription::grammar::Grammar;
+use crate::infrastructure::transcription::options::TranscribeOptions;
+use crate::infrastructure::transcription::progress::{progress_callback, ProgressCallback};
+use crate::infrastructure::transcription::prompt::fit_prompt_tokens;
+use crate::infrastructure::transcription::quality::compression_ratio;
+use crate::infrastructure::transcription::result::{Segment, Token, TranscriptionResult};
+use crate::infrastructure::transcription::stream::{new_segment_callback, SegmentCallback};
 use anyhow::{anyhow, Result};
+use log::warn;
 use std::ffi::{CStr, CString};
 use std::os::raw::c_float;
 use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 /// Safe wrapper around whisper context
+///
+/// The context can be shared between threads (e.g. in an `Arc`) so that one loaded
+/// model serves several transcriptions. Runs on the context's built-in default state
+/// (`transcribe`, `detect_language`) are serialized; use `create_state` or a
+/// `WhisperStatePool` for transcriptions that run concurrently.
 pub struct WhisperContextWrapper {
     ctx: *mut ffi::WhisperContext,
+    /// Guards the default state that `whisper_full` and language detection write into
+    default_state: Mutex<()>,
 }
 
+// SAFETY: the context pointer is owned by the wrapper and freed only in `Drop`.
+// whisper.cpp splits a context into the immutable model (weights, vocabulary,
+// alignment heads) and mutable decoding state. The model is only read after
+// initialization, so it may be used from several threads at once. Mutable state is
+// either the context's default state, which every method touching it accesses under
+// `default_state`, or a separate `whisper_state` owned by exactly one `WhisperState`
+// and used through `&mut`. Tokenization and `whisper_n_text_ctx` only read the model.
+unsafe impl Send for WhisperContextWrapper {}
+unsafe impl Sync for WhisperContextWrapper {}
+
 impl WhisperContextWrapper {
     /// Create a new whisper context from a model file
     ///
@@ -40,7 +71,46 @@ impl WhisperContextWrapper {
                     "Failed to initialize whisper context from model file"
                 ));
             }
-            Ok(WhisperContextWrapper { ctx })
+            Ok(WhisperContextWrapper::from_raw(ctx))
+        }
+    }
+
+    /// Create a new whisper context with DTW token alignment enabled
+    ///
+    /// DTW (dynamic time warping) over the model's cross-attention alignment heads
+    /// gives much tighter token timestamps than whisper's heuristic ones. The
+    /// alignment heads depend on the model, so the model size must be known.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_path` - Path to the whisper.cpp model file
+    /// * `model` - Model size, used to select the alignment heads preset
+    ///
+    /// # Returns
+    ///
+    /// Result containing the initialized context, or an error if initialization failed
+    pub fn new_with_dtw<P: AsRef<Path>>(model_path: P, model: ModelSize) -> Result<Self> {
+        let path_str = model_path
+            .as_ref()
+            .to_str()
+            .ok_or_else(|| anyhow!("Invalid model path"))?;
+
+        let c_path = CString::new(path_str)?;
+
+        unsafe {
+            let mut params = ffi::whisper_context_default_params();
+            params.dtw_token_timestamps = true;
+            params.dtw_aheads_preset = model.dtw_aheads_preset();
+            // DTW needs the attention weights, which flash attention does not produce
+            params.flash_attn = false;
+
+            let ctx = ffi::whisper_init_from_file_with_params(c_path.as_ptr(), params);
+            if ctx.is_null() {
+                return Err(anyhow!(
+                    "Failed to initialize whisper context with DTW from model file"
+                ));
+            }
+            Ok(WhisperContextWrapper::from_raw(ctx))
         }
     }
 
@@ -59,22 +129,44 @@ impl WhisperContextWrapper {
             if ctx.is_null() {
                 return Err(anyhow!("Failed to initialize whisper context from buffer"));
             }
-            Ok(WhisperContextWrapper { ctx })
+            Ok(WhisperContextWrapper::from_raw(ctx))
         }
     }
 
+    /// Wrap a freshly initialized, non-null context pointer
+    fn from_raw(ctx: *mut ffi::WhisperContext) -> Self {
+        WhisperContextWrapper {
+            ctx,
+            default_state: Mutex::new(()),
+        }
+    }
+
+    /// Lock the context's default state
+    ///
+    /// A panic while the lock was held cannot leave the state inconsistent (every
+    /// run starts from scratch), so a poisoned lock is recovered.
+    fn lock_default_state(&self) -> MutexGuard<'_, ()> {
+        self.default_state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
     /// Get the raw FFI context pointer (for advanced usage)
+    ///
+    /// Callers using the pointer directly must follow the same rules as the
+    /// wrapper: never touch the default state while another thread may use it.
     pub fn as_ptr(&self) -> *mut ffi::WhisperContext {
         self.ctx
     }
 
-    /// Transcribe audio samples
+    /// Transcribe audio samples on the context's default state
+    ///
+    /// Concurrent calls on a shared context wait for each other.
     ///
     /// # Arguments
     ///
     /// * `samples` - Float samples at 16kHz
-    /// * `language` - Optional language code (e.g., "en", "ru"). None for auto-detect
-    /// * `translate` - Whether to translate to English
+    /// * `options` - Decoding options (language, translation, sampling strategy, etc.)
     ///
     /// # Returns
     ///
@@ -82,188 +174,600 @@ impl WhisperContextWrapper {
     pub fn transcribe(
         &self,
         samples: &[c_float],
-        language: Option<&str>,
-        translate: bool,
+        options: &TranscribeOptions,
     ) -> Result<TranscriptionResult> {
-        let mut params = unsafe { ffi::whisper_full_default_params(ffi::WHISPER_SAMPLING_GREEDY) };
+        self.run_full(None, samples, options)
+    }
 
-        params.translate = translate;
-        params.print_progress = false;
-        params.print_realtime = false;
-        params.print_timestamps = true;
-        params.token_timestamps = true;
+    /// Create a new decoding state sharing this context's model
+    ///
+    /// Each state holds its own buffers, so transcriptions on different states can
+    /// run concurrently while the model weights are loaded only once. The state keeps
+    /// the context alive.
+    pub fn create_state(self: &Arc<Self>) -> Result<WhisperState> {
+        let state = unsafe { ffi::whisper_init_state(self.ctx) };
+        if state.is_null() {
+            return Err(anyhow!("Failed to initialize whisper state"));
+        }
+        Ok(WhisperState {
+            context: Arc::clone(self),
+            state,
+        })
+    }
+
+    /// Run `whisper_full` on the context's default state or on the given state
+    fn run_full(
+        &self,
+        state: Option<*mut ffi::WhisperState>,
+        samples: &[c_float],
+        options: &TranscribeOptions,
+    ) -> Result<TranscriptionResult> {
+        options.validate()?;
+
+        let mut full_params = FullParams::new(options)?;
+
+        if options.initial_prompt.is_some() || options.chunk_context.is_some() {
+            let tokens = self.fit_prompt(
+                options.initial_prompt.as_deref(),
+                options.chunk_context.as_deref(),
+            )?;
+            full_params.set_prompt_tokens(tokens);
+        }
+
+        if !options.boost_words.is_empty() {
+            let bias = self.token_bias(&options.boost_words, options.boost_weight)?;
+            if !bias.is_empty() {
+                full_params.set_token_bias(bias);
+            }
+        }
+
+        // Held until the results are read, so that no other run overwrites them
+        let _default_state = state.is_none().then(|| self.lock_default_state());
+
+        let ret = unsafe {
+            match state {
+                Some(state) => ffi::whisper_full_with_state(
+                    self.ctx,
+                    state,
+                    full_params.params.clone(),
+                    samples.as_ptr(),
+                    samples.len() as i32,
+                ),
+                None => ffi::whisper_full(
+                    self.ctx,
+                    full_params.params.clone(),
+                    samples.as_ptr(),
+                    samples.len() as i32,
+                ),
+            }
+        };
+
+        // An aborted run keeps the segments decoded before the abort
+        let cancelled = options.is_cancelled();
+        if ret != 0 && !cancelled {
+            return Err(anyhow!("Transcription failed with code {}", ret));
+        }
 
-        // Disable VAD to avoid requiring VAD model
-        params.vad = false;
+        // whisper.cpp does not always report the final 100%
+        if let Some(progress) = &options.progress {
+            if !cancelled {
+                progress.report(1.0);
+            }
+        }
 
-        // Set language if provided
-        let lang_c_string;
-        if let Some(lang) = language {
-            lang_c_string = CString::new(lang)?;
-            params.language = lang_c_string.as_ptr();
+        let source = match state {
+            Some(state) => ResultSource::State(self.ctx, state),
+            None => ResultSource::Context(self.ctx),
+        };
+        let mut result = unsafe { source.read_result() };
+        if cancelled {
+            warn!(
+                "Transcription cancelled, keeping {} completed segments",
+                result.segments.len()
+            );
+            result.partial = true;
         }
+        if options.prefer_dtw_timestamps {
+            result.apply_dtw_timestamps();
+        }
+        if options.drop_special_tokens {
+            result.drop_special_tokens();
+        }
+
+        Ok(result)
+    }
+
+    /// Detect the spoken language of audio samples
+    ///
+    /// Only the first 30 seconds of `samples` are used by whisper. This runs the
+    /// encoder and a single decoder step, without a full transcription.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Float samples at 16kHz
+    /// * `threads` - Number of threads
+    /// * `top_n` - Maximum number of languages to return
+    ///
+    /// # Returns
+    ///
+    /// Result containing the most probable languages, most probable first
+    pub fn detect_language(
+        &self,
+        samples: &[c_float],
+        threads: usize,
+        top_n: usize,
+    ) -> Result<Vec<LanguageProbability>> {
+        let _default_state = self.lock_default_state();
 
         unsafe {
-            let ret = ffi::whisper_full(self.ctx, params, samples.as_ptr(), samples.len() as i32);
+            if ffi::whisper_is_multilingual(self.ctx) == 0 {
+                return Err(anyhow!(
+                    "Language detection requires a multilingual model (not an .en model)"
+                ));
+            }
+
+            let ret = ffi::whisper_pcm_to_mel(
+                self.ctx,
+                samples.as_ptr(),
+                samples.len() as i32,
+                threads as i32,
+            );
             if ret != 0 {
-                return Err(anyhow!("Transcription failed with code {}", ret));
+                return Err(anyhow!("Failed to compute mel spectrogram (code {})", ret));
             }
+
+            let n_langs = (ffi::whisper_lang_max_id() + 1).max(0) as usize;
+            let mut probs = vec![0.0f32; n_langs];
+            let ret =
+                ffi::whisper_lang_auto_detect(self.ctx, 0, threads as i32, probs.as_mut_ptr());
+            if ret < 0 {
+                return Err(anyhow!("Language detection failed with code {}", ret));
+            }
+
+            Ok(rank_languages(&probs, top_n)
+                .into_iter()
+                .map(|(id, probability)| LanguageProbability {
+                    language: lang_str(id as i32),
+                    probability,
+                })
+                .collect())
         }
+    }
 
-        self.extract_results()
+    /// Count the tokens whisper produces for the given text
+    pub fn token_count(&self, text: &str) -> Result<usize> {
+        Ok(self.tokenize(text)?.len())
+    }
+
+    /// Tokenize text into whisper token ids
+    pub fn tokenize(&self, text: &str) -> Result<Vec<i32>> {
+        let c_text = CString::new(text)?;
+        // Every token covers at least one byte, so the buffer always fits and
+        // whisper never has to report (and log) a too small buffer
+        let mut tokens = vec![0; text.len()];
+        let ret = unsafe {
+            ffi::whisper_tokenize(
+                self.ctx,
+                c_text.as_ptr(),
+                tokens.as_mut_ptr(),
+                tokens.len() as i32,
+            )
+        };
+        if ret < 0 {
+            return Err(anyhow!("Failed to tokenize text (code {})", ret));
+        }
+        tokens.truncate(ret as usize);
+        Ok(tokens)
+    }
+
+    /// Build the logit bias for boosted words
+    ///
+    /// Each word is tokenized as it appears mid-sentence (with a leading space) and
+    /// at the start of a segment (without one).
+    fn token_bias(&self, words: &[String], weight: f32) -> Result<TokenBias> {
+        let mut sequences = Vec::new();
+        for word in words.iter().map(|word| word.trim()) {
+            if word.is_empty() {
+                continue;
+            }
+            sequences.push(self.tokenize(&format!(" {}", word))?);
+            sequences.push(self.tokenize(word)?);
+        }
+        sequences.dedup();
+        Ok(TokenBias::new(sequences, weight))
+    }
+
+    /// Maximum number of prompt tokens whisper uses (half of the text context)
+    pub fn max_prompt_tokens(&self) -> usize {
+        let n_text_ctx = unsafe { ffi::whisper_n_text_ctx(self.ctx) };
+        (n_text_ctx.max(0) as usize) / 2
+    }
+
+    /// Tokenize the initial prompt and chunk context and fit them to the decoder's prompt budget
+    fn fit_prompt(&self, prompt: Option<&str>, context: Option<&str>) -> Result<Vec<i32>> {
+        let max_tokens = self.max_prompt_tokens();
+        let prompt = match prompt {
+            Some(prompt) => self.tokenize(prompt)?,
+            None => Vec::new(),
+        };
+        // Tokenized as if it followed the prompt in one text
+        let context = match context {
+            Some(context) if prompt.is_empty() => self.tokenize(context)?,
+            Some(context) => self.tokenize(&format!(" {}", context))?,
+            None => Vec::new(),
+        };
+        let fitted = fit_prompt_tokens(&prompt, &context, max_tokens);
+
+        let total = prompt.len() + context.len();
+        if fitted.len() < total {
+            warn!(
+                "Initial prompt truncated to fit {} tokens ({} of {} tokens kept)",
+                max_tokens,
+                fitted.len(),
+                total
+            );
+        }
+
+        Ok(fitted)
     }
 
     /// Extract transcription results from the context
     ///
+    /// Reads the default state, i.e. the results of the most recent `transcribe` call
+    /// on this context from any thread.
+    ///
     /// # Returns
     ///
     /// Result containing the transcription data
     pub fn extract_results(&self) -> Result<TranscriptionResult> {
+        let _default_state = self.lock_default_state();
+        Ok(unsafe { ResultSource::Context(self.ctx).read_result() })
+    }
+}
+
+impl Drop for WhisperContextWrapper {
+    fn drop(&mut self) {
         unsafe {
-            let n_segments = ffi::whisper_full_n_segments(self.ctx);
-            let lang_id = ffi::whisper_full_lang_id(self.ctx);
-            let lang_name_ptr = ffi::whisper_lang_str(lang_id);
-            let language = if lang_name_ptr.is_null() {
-                "unknown".to_string()
+            ffi::whisper_free(self.ctx);
+        }
+    }
+}
+
+/// Where to read the results of a `whisper_full` run from
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ResultSource {
+    /// The context's default state (after `whisper_full`)
+    Context(*mut ffi::WhisperContext),
+    /// A separate state (after `whisper_full_with_state`)
+    State(*mut ffi::WhisperContext, *mut ffi::WhisperState),
+}
+
+impl ResultSource {
+    /// Read all segments of the last run
+    ///
+    /// # Safety
+    ///
+    /// The pointers must be valid and no run may be in progress on the state.
+    pub(crate) unsafe fn read_result(self) -> TranscriptionResult {
+        let language = lang_str(self.lang_id());
+        let segments = (0..self.n_segments())
+            .map(|i| self.read_segment(i, &language))
+            .collect();
+
+        TranscriptionResult {
+            language,
+            segments,
+            ..Default::default()
+        }
+    }
+
+    /// Language ID detected by the last run
+    pub(crate) unsafe fn lang_id(self) -> i32 {
+        match self {
+            Self::Context(ctx) => ffi::whisper_full_lang_id(ctx),
+            Self::State(_, state) => ffi::whisper_full_lang_id_from_state(state),
+        }
+    }
+
+    /// Number of segments produced by the last run
+    pub(crate) unsafe fn n_segments(self) -> i32 {
+        match self {
+            Self::Context(ctx) => ffi::whisper_full_n_segments(ctx),
+            Self::State(_, state) => ffi::whisper_full_n_segments_from_state(state),
+        }
+    }
+
+    /// Read segment `i` of the last run
+    ///
+    /// # Safety
+    ///
+    /// The pointers must be valid and `i` a valid segment index.
+    pub(crate) unsafe fn read_segment(self, i: i32, language: &str) -> Segment {
+        let (text_ptr, t0, t1, no_speech_prob, speaker_turn_next, n_tokens) = match self {
+            Self::Context(ctx) => (
+                ffi::whisper_full_get_segment_text(ctx, i),
+                ffi::whisper_full_get_segment_t0(ctx, i),
+                ffi::whisper_full_get_segment_t1(ctx, i),
+                ffi::whisper_full_get_segment_no_speech_prob(ctx, i),
+                ffi::whisper_full_get_segment_speaker_turn_next(ctx, i),
+                ffi::whisper_full_n_tokens(ctx, i),
+            ),
+            Self::State(_, state) => (
+                ffi::whisper_full_get_segment_text_from_state(state, i),
+                ffi::whisper_full_get_segment_t0_from_state(state, i),
+                ffi::whisper_full_get_segment_t1_from_state(state, i),
+                ffi::whisper_full_get_segment_no_speech_prob_from_state(state, i),
+                ffi::whisper_full_get_segment_speaker_turn_next_from_state(state, i),
+                ffi::whisper_full_n_tokens_from_state(state, i),
+            ),
+        };
+
+        let text = if text_ptr.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(text_ptr).to_string_lossy().to_string()
+        };
+
+        let token_eot = match self {
+            Self::Context(ctx) | Self::State(ctx, _) => ffi::whisper_token_eot(ctx),
+        };
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut text_token_probs = Vec::new();
+        let mut decoder = Utf8TokenDecoder::new();
+        for j in 0..n_tokens {
+            let (token_text_ptr, token_data) = match self {
+                Self::Context(ctx) => (
+                    ffi::whisper_full_get_token_text(ctx, i, j),
+                    ffi::whisper_full_get_token_data(ctx, i, j),
+                ),
+                Self::State(ctx, state) => (
+                    ffi::whisper_full_get_token_text_from_state(ctx, state, i, j),
+                    ffi::whisper_full_get_token_data_from_state(state, i, j),
+                ),
+            };
+            // Tokens may end inside a multi-byte character, so decode across tokens
+            let token_text = if token_text_ptr.is_null() {
+                String::new()
             } else {
-                CStr::from_ptr(lang_name_ptr).to_string_lossy().to_string()
+                decoder.push(CStr::from_ptr(token_text_ptr).to_bytes())
             };
 
-            let mut segments = Vec::new();
-            for i in 0..n_segments {
-                let text_ptr = ffi::whisper_full_get_segment_text(self.ctx, i);
-                let text = if text_ptr.is_null() {
-                    String::new()
-                } else {
-                    CStr::from_ptr(text_ptr).to_string_lossy().to_string()
-                };
-
-                // t0 and t1 are in centiseconds (100ths of a second), convert to milliseconds
-                let t0 = ffi::whisper_full_get_segment_t0(self.ctx, i) * 10;
-                let t1 = ffi::whisper_full_get_segment_t1(self.ctx, i) * 10;
-                // no_speech_prob is inverted - higher means more likely silence
-                // so confidence = 1.0 - no_speech_prob
-                let no_speech_prob = ffi::whisper_full_get_segment_no_speech_prob(self.ctx, i);
-                let p = 1.0 - no_speech_prob;
-                let n_tokens = ffi::whisper_full_n_tokens(self.ctx, i);
-
-                let mut tokens = Vec::new();
-                for j in 0..n_tokens {
-                    let token_text_ptr = ffi::whisper_full_get_token_text(self.ctx, i, j);
-                    let token_text = if token_text_ptr.is_null() {
-                        String::new()
-                    } else {
-                        CStr::from_ptr(token_text_ptr).to_string_lossy().to_string()
-                    };
-                    let token_p = ffi::whisper_full_get_token_p(self.ctx, i, j);
-
-                    tokens.push(Token {
-                        text: token_text,
-                        probability: token_p,
-                    });
-                }
-
-                segments.push(Segment {
-                    id: i,
-                    start_ms: t0,
-                    end_ms: t1,
-                    text,
-                    confidence: p,
-                    tokens,
-                });
+            let special = token_data.id >= token_eot;
+            if !special {
+                text_token_probs.push((token_data.plog, token_data.p));
             }
 
-            Ok(TranscriptionResult { language, segments })
+            tokens.push(Token {
+                id: token_data.id,
+                text: token_text,
+                probability: token_data.p,
+                logprob: token_data.plog,
+                timestamp_prob: token_data.pt,
+                timestamp_prob_sum: token_data.ptsum,
+                // Token timestamps are in centiseconds, like segment timestamps
+                start_ms: token_data.t0 * 10,
+                end_ms: token_data.t1 * 10,
+                // t_dtw is -1 unless the context was created with DTW enabled
+                dtw_ms: (token_data.t_dtw >= 0).then_some(token_data.t_dtw * 10),
+                special,
+            });
+        }
+        if let Some(last) = tokens.last_mut() {
+            last.text.push_str(&decoder.finish());
+        }
+
+        let (avg_logprob, min_token_prob) = token_prob_stats(&text_token_probs);
+        Segment {
+            id: i,
+            // t0 and t1 are in centiseconds (100ths of a second), convert to milliseconds
+            start_ms: t0 * 10,
+            end_ms: t1 * 10,
+            compression_ratio: compression_ratio(&text),
+            text,
+            confidence: avg_logprob.exp(),
+            avg_logprob,
+            min_token_prob,
+            no_speech_prob,
+            tokens,
+            language: language.to_string(),
+            speaker_turn_next,
         }
     }
 }
 
-impl Drop for WhisperContextWrapper {
-    fn drop(&mut self) {
-        unsafe {
-            ffi::whisper_free(self.ctx);
+/// Mean log-probability and lowest probability of a segment's text tokens
+///
+/// # Arguments
+///
+/// * `probs` - `(log-probability, probability)` of each text token
+///
+/// # Returns
+///
+/// `(0.0, 1.0)` for a segment without text tokens
+fn token_prob_stats(probs: &[(f32, f32)]) -> (f32, f32) {
+    if probs.is_empty() {
+        return (0.0, 1.0);
+    }
+    let avg_logprob = probs.iter().map(|(plog, _)| plog).sum::<f32>() / probs.len() as f32;
+    let min_prob = probs.iter().map(|(_, p)| *p).fold(1.0, f32::min);
+    (avg_logprob, min_prob)
+}
+
+/// Get the language code for a whisper language ID ("unknown" if invalid)
+pub(crate) fn lang_str(id: i32) -> String {
+    unsafe {
+        let ptr = ffi::whisper_lang_str(id);
+        if ptr.is_null() {
+            "unknown".to_string()
+        } else {
+            CStr::from_ptr(ptr).to_string_lossy().to_string()
         }
     }
 }
 
-/// A single transcribed segment
-#[derive(Debug, Clone)]
-pub struct Segment {
-    /// Segment index
-    pub id: i32,
-    /// Start time in milliseconds
-    pub start_ms: i64,
-    /// End time in milliseconds
-    pub end_ms: i64,
-    /// Transcribed text
-    pub text: String,
-    /// Confidence score (0.0 - 1.0)
-    pub confidence: f32,
-    /// Individual tokens with probabilities
-    pub tokens: Vec<Token>,
+/// Decoding state sharing the model of a `WhisperContextWrapper`
+///
+/// Created by `WhisperContextWrapper::create_state`. Runs on different states may
+/// execute concurrently; the state is freed when dropped.
+pub struct WhisperState {
+    context: Arc<WhisperContextWrapper>,
+    state: *mut ffi::WhisperState,
 }
 
-impl Segment {
-    /// Format start time as HH:MM:SS.mmm
-    pub fn start_time_formatted(&self) -> String {
-        format_timestamp(self.start_ms)
+// SAFETY: a state is only used through `&mut self`, so it is never accessed from two
+// threads at once. The shared context is only read during `whisper_full_with_state`
+// (model weights and vocabulary), which whisper.cpp supports from multiple threads
+// as long as each thread uses its own state.
+unsafe impl Send for WhisperState {}
+
+impl WhisperState {
+    /// Context whose model this state decodes with
+    pub fn context(&self) -> &Arc<WhisperContextWrapper> {
+        &self.context
     }
 
-    /// Format end time as HH:MM:SS.mmm
-    pub fn end_time_formatted(&self) -> String {
-        format_timestamp(self.end_ms)
+    /// Transcribe audio samples on this state
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Float samples at 16kHz
+    /// * `options` - Decoding options (language, translation, sampling strategy, etc.)
+    ///
+    /// # Returns
+    ///
+    /// Result containing the transcription results or an error
+    pub fn transcribe(
+        &mut self,
+        samples: &[c_float],
+        options: &TranscribeOptions,
+    ) -> Result<TranscriptionResult> {
+        self.context.run_full(Some(self.state), samples, options)
     }
 }
 
-/// A single token with probability
-#[derive(Debug, Clone)]
-pub struct Token {
-    /// Token text
-    pub text: String,
-    /// Probability (0.0 - 1.0)
-    pub probability: f32,
+impl Drop for WhisperState {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::whisper_free_state(self.state);
+        }
+    }
 }
 
-/// Complete transcription result
-#[derive(Debug, Clone)]
-pub struct TranscriptionResult {
-    /// Detected language
-    pub language: String,
-    /// Transcribed segments
-    pub segments: Vec<Segment>,
+/// Whisper full params together with the C strings they point into
+///
+/// The raw pointers in `params` borrow from the owned fields, so this struct
+/// must stay alive until `whisper_full` returns.
+struct FullParams {
+    params: ffi::WhisperFullParams,
+    _language: CString,
+    _prompt_tokens: Option<Vec<i32>>,
+    _vad_model_path: Option<CString>,
+    _suppress_regex: Option<CString>,
+    _token_bias: Option<Box<TokenBias>>,
+    _grammar: Option<(Grammar, Vec<*const ffi::WhisperGrammarElement>)>,
+    _cancellation: Option<CancellationToken>,
+    _progress: Option<Box<ProgressCallback>>,
+    _on_segment: Option<Box<SegmentCallback>>,
 }
 
-impl TranscriptionResult {
-    /// Get full transcript as a single string
-    pub fn full_text(&self) -> String {
-        self.segments
-            .iter()
-            .map(|s| s.text.as_str())
-            .collect::<Vec<_>>()
-            .join(" ")
-    }
+impl FullParams {
+    fn new(options: &TranscribeOptions) -> Result<Self> {
+        let mut params = unsafe { ffi::whisper_full_default_params(options.strategy()) };
+
+        params.print_progress = false;
+        params.print_realtime = false;
+        params.print_timestamps = true;
+        params.token_timestamps = true;
 
-    /// Get average confidence across all segments
-    pub fn average_confidence(&self) -> f32 {
-        if self.segments.is_empty() {
-            return 0.0;
+        options.apply_to(&mut params);
+
+        // The default params use "en"; whisper only auto-detects with "auto"
+        let language = CString::new(options.language.as_deref().unwrap_or("auto"))?;
+        params.language = language.as_ptr();
+
+        // VAD needs the Silero model; it stays disabled unless one is configured
+        let vad_model_path = match &options.vad {
+            Some(vad) => {
+                let path = vad.model_path.to_str().ok_or_else(|| {
+                    anyhow!("Invalid VAD model path: {}", vad.model_path.display())
+                })?;
+                Some(CString::new(path)?)
+            }
+            None => None,
+        };
+        if let Some(path) = &vad_model_path {
+            params.vad_model_path = path.as_ptr();
+        }
+
+        let suppress_regex = options
+            .suppress_regex
+            .as_deref()
+            .map(CString::new)
+            .transpose()?;
+        if let Some(regex) = &suppress_regex {
+            params.suppress_regex = regex.as_ptr();
+        }
+
+        // whisper reads the rules through an array of pointers to each rule's elements;
+        // both live on the heap, so they stay valid when `FullParams` is moved
+        let grammar = options.grammar.clone().map(|grammar| {
+            let rules: Vec<*const ffi::WhisperGrammarElement> =
+                grammar.rules().iter().map(|rule| rule.as_ptr()).collect();
+            (grammar, rules)
+        });
+        if let Some((_, rules)) = &grammar {
+            params.grammar_rules = rules.as_ptr();
+            params.n_grammar_rules = rules.len();
+        }
+
+        // The callback reads the token's flag; the clone below keeps it alive
+        let cancellation = options.cancellation.clone();
+        if let Some(token) = &cancellation {
+            params.abort_callback = Some(abort_callback);
+            params.abort_callback_user_data = token.as_user_data();
+        }
+
+        // Boxed so the pointer stays valid when `FullParams` is moved
+        let progress = options.progress.clone().map(Box::new);
+        if let Some(callback) = &progress {
+            params.progress_callback = Some(progress_callback);
+            params.progress_callback_user_data = callback.as_user_data();
         }
-        self.segments.iter().map(|s| s.confidence).sum::<f32>() / self.segments.len() as f32
+
+        let on_segment = options.on_segment.clone().map(Box::new);
+        if let Some(callback) = &on_segment {
+            params.new_segment_callback = Some(new_segment_callback);
+            params.new_segment_callback_user_data = callback.as_user_data();
+        }
+
+        Ok(FullParams {
+            params,
+            _language: language,
+            _prompt_tokens: None,
+            _vad_model_path: vad_model_path,
+            _suppress_regex: suppress_regex,
+            _token_bias: None,
+            _grammar: grammar,
+            _cancellation: cancellation,
+            _progress: progress,
+            _on_segment: on_segment,
+        })
+    }
+
+    fn set_token_bias(&mut self, bias: TokenBias) {
+        // Boxed so the pointer stays valid when `FullParams` is moved
+        let bias = Box::new(bias);
+        self.params.logits_filter_callback = Some(logits_filter_callback);
+        self.params.logits_filter_callback_user_data = bias.as_user_data();
+        self._token_bias = Some(bias);
     }
-}
 
-/// Format milliseconds as HH:MM:SS.mmm
-fn format_timestamp(ms: i64) -> String {
-    let total_seconds = ms / 1000;
-    let milliseconds = ms % 1000;
-    let seconds = total_seconds % 60;
-    let minutes = (total_seconds / 60) % 60;
-    let hours = total_seconds / 3600;
-
-    format!(
-        "{:02}:{:02}:{:02}.{:03}",
-        hours, minutes, seconds, milliseconds
-    )
+    fn set_prompt_tokens(&mut self, tokens: Vec<i32>) {
+        // The heap buffer stays put when `FullParams` is moved
+        self.params.prompt_tokens = tokens.as_ptr();
+        self.params.prompt_n_tokens = tokens.len() as i32;
+        self._prompt_tokens = Some(tokens);
+    }
 }
 
 #[cfg(test)]
@@ -271,11 +775,23 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_format_timestamp() {
-        assert_eq!(format_timestamp(0), "00:00:00.000");
-        assert_eq!(format_timestamp(1000), "00:00:01.000");
-        assert_eq!(format_timestamp(61000), "00:01:01.000");
-        assert_eq!(format_timestamp(3661000), "01:01:01.000");
-        assert_eq!(format_timestamp(3661500), "01:01:01.500");
+    fn test_context_and_state_can_cross_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        fn assert_send<T: Send>() {}
+        assert_send_sync::<WhisperContextWrapper>();
+        assert_send_sync::<Arc<WhisperContextWrapper>>();
+        assert_send::<WhisperState>();
+    }
+
+    #[test]
+    fn test_token_prob_stats() {
+        assert_eq!(token_prob_stats(&[]), (0.0, 1.0));
+
+        let probs = [(0.5f32.ln(), 0.5), (0.9f32.ln(), 0.9), (0.2f32.ln(), 0.2)];
+        let (avg_logprob, min_prob) = token_prob_stats(&probs);
+        assert!((avg_logprob - (0.5f32 * 0.9 * 0.2).ln() / 3.0).abs() < 1e-6);
+        assert_eq!(min_prob, 0.2);
+        // Confidence is the geometric mean of the probabilities
+        assert!((avg_logprob.exp() - 0.09f32.cbrt()).abs() < 1e-6);
     }
 }
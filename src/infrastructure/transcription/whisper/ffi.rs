@@ -25,6 +25,47 @@ pub type WhisperToken = i32;
 pub const WHISPER_SAMPLING_GREEDY: c_int = 0;
 pub const WHISPER_SAMPLING_BEAM_SEARCH: c_int = 1;
 
+/// DTW alignment heads presets (`whisper_alignment_heads_preset`)
+pub const WHISPER_AHEADS_NONE: c_int = 0;
+pub const WHISPER_AHEADS_N_TOP_MOST: c_int = 1;
+pub const WHISPER_AHEADS_CUSTOM: c_int = 2;
+pub const WHISPER_AHEADS_TINY_EN: c_int = 3;
+pub const WHISPER_AHEADS_TINY: c_int = 4;
+pub const WHISPER_AHEADS_BASE_EN: c_int = 5;
+pub const WHISPER_AHEADS_BASE: c_int = 6;
+pub const WHISPER_AHEADS_SMALL_EN: c_int = 7;
+pub const WHISPER_AHEADS_SMALL: c_int = 8;
+pub const WHISPER_AHEADS_MEDIUM_EN: c_int = 9;
+pub const WHISPER_AHEADS_MEDIUM: c_int = 10;
+pub const WHISPER_AHEADS_LARGE_V1: c_int = 11;
+pub const WHISPER_AHEADS_LARGE_V2: c_int = 12;
+pub const WHISPER_AHEADS_LARGE_V3: c_int = 13;
+pub const WHISPER_AHEADS_LARGE_V3_TURBO: c_int = 14;
+
+/// Custom alignment heads (used with `WHISPER_AHEADS_CUSTOM`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WhisperAheads {
+    pub n_heads: usize,
+    pub heads: *const c_void,
+}
+
+/// Context initialization parameters
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WhisperContextParams {
+    pub use_gpu: bool,
+    pub flash_attn: bool,
+    pub gpu_device: c_int,
+
+    // DTW token-level timestamps
+    pub dtw_token_timestamps: bool,
+    pub dtw_aheads_preset: c_int,
+    pub dtw_n_top: c_int,
+    pub dtw_aheads: WhisperAheads,
+    pub dtw_mem_size: usize,
+}
+
 /// Greedy sampling parameters
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -52,8 +93,12 @@ pub struct WhisperVadParams {
     pub samples_overlap: c_float,
 }
 
-/// Grammar element (opaque)
-pub type WhisperGrammarElement = c_void;
+// Grammar elements are defined next to the GBNF parser, which is also used without whisper
+pub use crate::infrastructure::transcription::grammar::{
+    WhisperGrammarElement, WHISPER_GRETYPE_ALT, WHISPER_GRETYPE_CHAR, WHISPER_GRETYPE_CHAR_ALT,
+    WHISPER_GRETYPE_CHAR_NOT, WHISPER_GRETYPE_CHAR_RNG_UPPER, WHISPER_GRETYPE_END,
+    WHISPER_GRETYPE_RULE_REF,
+};
 
 /// Callback types
 pub type WhisperNewSegmentCallback =
@@ -211,6 +256,15 @@ extern "C" {
     /// Initialize whisper context from file
     pub fn whisper_init_from_file(path: *const c_char) -> *mut WhisperContext;
 
+    /// Get default context parameters
+    pub fn whisper_context_default_params() -> WhisperContextParams;
+
+    /// Initialize whisper context from file with context parameters
+    pub fn whisper_init_from_file_with_params(
+        path: *const c_char,
+        params: WhisperContextParams,
+    ) -> *mut WhisperContext;
+
     /// Initialize whisper context from buffer
     pub fn whisper_init_from_buffer(
         buffer: *const c_void,
@@ -255,6 +309,9 @@ extern "C" {
     /// Get segment no-speech probability (higher = more likely silence/noise)
     pub fn whisper_full_get_segment_no_speech_prob(ctx: *mut WhisperContext, i: c_int) -> c_float;
 
+    /// Whether the next segment is predicted as a speaker turn (tinydiarize models only)
+    pub fn whisper_full_get_segment_speaker_turn_next(ctx: *mut WhisperContext, i: c_int) -> bool;
+
     /// Get number of tokens in segment
     pub fn whisper_full_n_tokens(ctx: *mut WhisperContext, i: c_int) -> c_int;
 
@@ -295,11 +352,109 @@ extern "C" {
     /// Get language ID by name
     pub fn whisper_lang_id(lang: *const c_char) -> c_int;
 
-    /// Create new state (for multithreading)
-    pub fn whisper_state_new(ctx: *const WhisperContext) -> *mut WhisperState;
+    /// Convert text into tokens
+    ///
+    /// Returns the number of tokens on success, or a negative number whose absolute
+    /// value is the number of tokens that would have been returned if `n_max_tokens`
+    /// is too small.
+    pub fn whisper_tokenize(
+        ctx: *mut WhisperContext,
+        text: *const c_char,
+        tokens: *mut WhisperToken,
+        n_max_tokens: c_int,
+    ) -> c_int;
 
-    /// Free state
-    pub fn whisper_state_free(state: *mut WhisperState);
+    /// Get the end-of-text token; all special and timestamp tokens have larger IDs
+    pub fn whisper_token_eot(ctx: *mut WhisperContext) -> WhisperToken;
+
+    /// Get the vocabulary size (length of the logits array)
+    pub fn whisper_n_vocab(ctx: *mut WhisperContext) -> c_int;
+
+    /// Get the size of the text context (max number of decoder tokens)
+    pub fn whisper_n_text_ctx(ctx: *mut WhisperContext) -> c_int;
+
+    /// Check whether the model supports multiple languages
+    pub fn whisper_is_multilingual(ctx: *mut WhisperContext) -> c_int;
+
+    /// Largest language ID (number of languages - 1)
+    pub fn whisper_lang_max_id() -> c_int;
+
+    /// Convert PCM samples to a log mel spectrogram stored in the context
+    pub fn whisper_pcm_to_mel(
+        ctx: *mut WhisperContext,
+        samples: *const c_float,
+        n_samples: c_int,
+        n_threads: c_int,
+    ) -> c_int;
+
+    /// Run the encoder on the mel spectrogram starting at `offset` (in mel frames)
+    pub fn whisper_encode(ctx: *mut WhisperContext, offset: c_int, n_threads: c_int) -> c_int;
+
+    /// Detect the language of the mel spectrogram starting at `offset_ms`
+    ///
+    /// Runs the encoder itself and fills `lang_probs` (of size `whisper_lang_max_id() + 1`)
+    /// with the probability of each language. Returns the most probable language ID,
+    /// or a negative value on failure.
+    pub fn whisper_lang_auto_detect(
+        ctx: *mut WhisperContext,
+        offset_ms: c_int,
+        n_threads: c_int,
+        lang_probs: *mut c_float,
+    ) -> c_int;
+
+    /// Create a new decoding state sharing the context's model (for multithreading)
+    pub fn whisper_init_state(ctx: *mut WhisperContext) -> *mut WhisperState;
+
+    /// Free a decoding state
+    pub fn whisper_free_state(state: *mut WhisperState);
+
+    /// Get language ID detected by the last run on a state
+    pub fn whisper_full_lang_id_from_state(state: *mut WhisperState) -> c_int;
+
+    /// Get number of segments produced by the last run on a state
+    pub fn whisper_full_n_segments_from_state(state: *mut WhisperState) -> c_int;
+
+    /// Get segment text from a state
+    pub fn whisper_full_get_segment_text_from_state(
+        state: *mut WhisperState,
+        i: c_int,
+    ) -> *const c_char;
+
+    /// Get segment start time in centiseconds from a state
+    pub fn whisper_full_get_segment_t0_from_state(state: *mut WhisperState, i: c_int) -> i64;
+
+    /// Get segment end time in centiseconds from a state
+    pub fn whisper_full_get_segment_t1_from_state(state: *mut WhisperState, i: c_int) -> i64;
+
+    /// Get segment no-speech probability from a state
+    pub fn whisper_full_get_segment_no_speech_prob_from_state(
+        state: *mut WhisperState,
+        i: c_int,
+    ) -> c_float;
+
+    /// Whether the next segment is predicted as a speaker turn, from a state
+    pub fn whisper_full_get_segment_speaker_turn_next_from_state(
+        state: *mut WhisperState,
+        i: c_int,
+    ) -> bool;
+
+    /// Get number of tokens in a segment from a state
+    pub fn whisper_full_n_tokens_from_state(state: *mut WhisperState, i: c_int) -> c_int;
+
+    /// Get token text from a state
+    pub fn whisper_full_get_token_text_from_state(
+        ctx: *mut WhisperContext,
+        state: *mut WhisperState,
+        i_segment: c_int,
+        i_token: c_int,
+    ) -> *const c_char;
+
+    /// Get token data from a state
+    pub fn whisper_full_get_token_data_from_state(
+        state: *mut WhisperState,
+        i_segment: c_int,
+        i_token: c_int,
+    ) -> WhisperTokenData;
 
     /// Print system information
     pub fn whisper_print_system_info() -> *const c_char;
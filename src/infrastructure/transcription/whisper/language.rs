@@ -0,0 +1,172 @@
+//! Language identification
+//!
+//! Types and helpers for whisper's language auto-detection. Detection runs only the
+//! encoder and a single decoder step, so it is much cheaper than a full transcription
+//! and can be used to route files before transcribing them.
+
+/// A language together with its detection probability
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageProbability {
+    /// Language code (e.g., "en", "ru")
+    pub language: String,
+    /// Probability (0.0 - 1.0)
+    pub probability: f32,
+}
+
+/// Language detection result for a single audio window
+#[derive(Debug, Clone)]
+pub struct WindowLanguages {
+    /// Window start in milliseconds
+    pub start_ms: i64,
+    /// Window duration in milliseconds
+    pub duration_ms: i64,
+    /// Top languages for this window, most probable first
+    pub languages: Vec<LanguageProbability>,
+}
+
+/// Language detection result for a file
+#[derive(Debug, Clone)]
+pub struct LanguageDetection {
+    /// Top languages averaged over all windows, most probable first
+    pub languages: Vec<LanguageProbability>,
+    /// Per-window results
+    pub windows: Vec<WindowLanguages>,
+}
+
+impl LanguageDetection {
+    /// Most probable language, if any
+    pub fn best(&self) -> Option<&LanguageProbability> {
+        self.languages.first()
+    }
+}
+
+/// Rank language IDs by probability
+///
+/// # Arguments
+///
+/// * `probs` - Probability for each language ID, as filled by `whisper_lang_auto_detect`
+/// * `top_n` - Maximum number of languages to return
+///
+/// # Returns
+///
+/// `(language_id, probability)` pairs, most probable first
+pub fn rank_languages(probs: &[f32], top_n: usize) -> Vec<(usize, f32)> {
+    let mut ranked: Vec<(usize, f32)> = probs.iter().copied().enumerate().collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked.truncate(top_n);
+    ranked
+}
+
+/// Average language probabilities over several windows
+///
+/// Languages missing from a window count as probability 0 for that window.
+///
+/// # Arguments
+///
+/// * `windows` - Full language probability lists for each window
+/// * `top_n` - Maximum number of languages to return
+///
+/// # Returns
+///
+/// Averaged probabilities, most probable first
+pub fn average_languages(
+    windows: &[Vec<LanguageProbability>],
+    top_n: usize,
+) -> Vec<LanguageProbability> {
+    if windows.is_empty() {
+        return Vec::new();
+    }
+
+    let mut totals: Vec<LanguageProbability> = Vec::new();
+    for window in windows {
+        for entry in window {
+            match totals.iter_mut().find(|t| t.language == entry.language) {
+                Some(total) => total.probability += entry.probability,
+                None => totals.push(entry.clone()),
+            }
+        }
+    }
+
+    for total in &mut totals {
+        total.probability /= windows.len() as f32;
+    }
+
+    totals.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+    totals.truncate(top_n);
+    totals
+}
+
+/// Compute start offsets of evenly spaced detection windows
+///
+/// A single window starts at the beginning of the audio. Several windows are spread
+/// so that the first starts at the beginning and the last ends at the end of the audio.
+///
+/// # Arguments
+///
+/// * `total_samples` - Total number of samples in the audio
+/// * `window_samples` - Number of samples in each window
+/// * `count` - Number of windows
+///
+/// # Returns
+///
+/// Start offsets in samples (deduplicated, so short audio yields fewer windows)
+pub fn window_offsets(total_samples: usize, window_samples: usize, count: usize) -> Vec<usize> {
+    if count == 0 || total_samples == 0 {
+        return Vec::new();
+    }
+
+    let last_start = total_samples.saturating_sub(window_samples);
+    if count == 1 || last_start == 0 {
+        return vec![0];
+    }
+
+    let mut offsets: Vec<usize> = (0..count).map(|i| last_start * i / (count - 1)).collect();
+    offsets.dedup();
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lang(language: &str, probability: f32) -> LanguageProbability {
+        LanguageProbability {
+            language: language.to_string(),
+            probability,
+        }
+    }
+
+    #[test]
+    fn test_rank_languages() {
+        let probs = [0.1, 0.6, 0.05, 0.25];
+        assert_eq!(rank_languages(&probs, 2), vec![(1, 0.6), (3, 0.25)]);
+        assert_eq!(rank_languages(&probs, 10).len(), 4);
+    }
+
+    #[test]
+    fn test_average_languages() {
+        let windows = vec![
+            vec![lang("ru", 0.8), lang("en", 0.2)],
+            vec![lang("en", 0.6), lang("ru", 0.3), lang("uk", 0.1)],
+        ];
+        let averaged = average_languages(&windows, 2);
+
+        assert_eq!(averaged.len(), 2);
+        assert_eq!(averaged[0].language, "ru");
+        assert!((averaged[0].probability - 0.55).abs() < 1e-6);
+        assert_eq!(averaged[1].language, "en");
+        assert!((averaged[1].probability - 0.4).abs() < 1e-6);
+
+        assert!(average_languages(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn test_window_offsets() {
+        assert_eq!(window_offsets(100, 10, 1), vec![0]);
+        assert_eq!(window_offsets(100, 10, 4), vec![0, 30, 60, 90]);
+        // Audio shorter than a window yields a single window
+        assert_eq!(window_offsets(5, 10, 3), vec![0]);
+        assert!(window_offsets(0, 10, 3).is_empty());
+        assert!(window_offsets(100, 10, 0).is_empty());
+    }
+}
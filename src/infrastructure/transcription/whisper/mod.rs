@@ -12,29 +12,45 @@
 //! whisper = ["reqwest", "indicatif"]
 //! ```
 
-#[cfg(feature = "whisper")]
 pub mod ffi;
 
-#[cfg(feature = "whisper")]
+pub mod bias;
+
 pub mod context;
 
-#[cfg(feature = "whisper")]
-pub mod merger;
+pub mod language;
 
-#[cfg(feature = "whisper")]
 pub mod model_manager;
 
-#[cfg(feature = "whisper")]
+pub mod pool;
+
 pub mod transcriber;
 
-#[cfg(feature = "whisper")]
-pub use context::{Segment, Token, TranscriptionResult, WhisperContextWrapper};
+pub mod utf8;
+
+pub use bias::TokenBias;
 
-#[cfg(feature = "whisper")]
-pub use merger::{merge_transcription_results, MergeConfig, MergeResult};
+pub use context::{WhisperContextWrapper, WhisperState};
 
-#[cfg(feature = "whisper")]
-pub use model_manager::{ModelSize, ModelSource, WhisperModelManager};
+pub use language::{LanguageDetection, LanguageProbability, WindowLanguages};
+
+pub use model_manager::{ModelSize, ModelSource, WhisperModelManager, VAD_MODEL_FILENAME};
+
+pub use pool::{PooledState, WhisperStatePool};
 
-#[cfg(feature = "whisper")]
 pub use transcriber::WhisperTranscriber;
+
+// Backend-independent items, re-exported for code written against this module
+pub use super::cancel::CancellationToken;
+pub use super::grammar::{Grammar, DEFAULT_START_RULE};
+pub use super::merger::{merge_transcription_results, MergeConfig, MergeResult};
+pub use super::options::{check_suppress_regex, TranscribeOptions, VadOptions};
+pub use super::pipeline::with_chunk_context;
+pub use super::progress::ProgressCallback;
+pub use super::prompt::{build_initial_prompt, load_vocab_file};
+pub use super::quality::{check_quality, QualityConfig, QualityIssue, QualityRetry};
+pub use super::result::{Segment, Token, TranscriptionResult, Word};
+pub use super::review::{
+    mark_uncertain_words, needs_review, review_items, uncertain_words, ReviewItem,
+};
+pub use super::stream::SegmentCallback;
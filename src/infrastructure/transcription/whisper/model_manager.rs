@@ -3,6 +3,7 @@
 //! Handles downloading, caching, and verifying whisper.cpp models.
 //! Models are cached in `~/.cache/orangenote/models/` for reuse across sessions.
 
+use super::ffi;
 use anyhow::{anyhow, Context, Result};
 use std::fs;
 use std::io::Write;
@@ -11,6 +12,12 @@ use std::path::{Path, PathBuf};
 #[cfg(feature = "whisper")]
 use futures::stream::StreamExt;
 
+/// Filename of the ggml Silero VAD model used by whisper.cpp's built-in VAD
+pub const VAD_MODEL_FILENAME: &str = "ggml-silero-v5.1.2.bin";
+
+/// Approximate size of the VAD model in MB
+pub const VAD_MODEL_SIZE_MB: u32 = 1;
+
 /// Available whisper model sizes
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ModelSize {
@@ -23,6 +30,8 @@ pub enum ModelSize {
     Medium,
     MediumEn,
     Large,
+    /// small.en fine-tuned by tinydiarize to predict speaker turns
+    SmallEnTdrz,
 }
 
 impl ModelSize {
@@ -38,6 +47,7 @@ impl ModelSize {
             Self::Medium => "ggml-medium.bin",
             Self::MediumEn => "ggml-medium.en.bin",
             Self::Large => "ggml-large.bin",
+            Self::SmallEnTdrz => "ggml-small.en-tdrz.bin",
         }
     }
 
@@ -53,6 +63,7 @@ impl ModelSize {
             Self::Medium => "medium",
             Self::MediumEn => "medium.en",
             Self::Large => "large",
+            Self::SmallEnTdrz => "small.en-tdrz",
         }
     }
 
@@ -61,13 +72,35 @@ impl ModelSize {
         match self {
             Self::Tiny | Self::TinyEn => 39,
             Self::Base | Self::BaseEn => 140,
-            Self::Small | Self::SmallEn => 466,
+            Self::Small | Self::SmallEn | Self::SmallEnTdrz => 466,
             Self::Medium | Self::MediumEn => 1500,
             Self::Large => 3000,
         }
     }
 
+    /// Get the DTW alignment heads preset for this model
+    pub fn dtw_aheads_preset(&self) -> i32 {
+        match self {
+            Self::Tiny => ffi::WHISPER_AHEADS_TINY,
+            Self::TinyEn => ffi::WHISPER_AHEADS_TINY_EN,
+            Self::Base => ffi::WHISPER_AHEADS_BASE,
+            Self::BaseEn => ffi::WHISPER_AHEADS_BASE_EN,
+            Self::Small => ffi::WHISPER_AHEADS_SMALL,
+            Self::SmallEn | Self::SmallEnTdrz => ffi::WHISPER_AHEADS_SMALL_EN,
+            Self::Medium => ffi::WHISPER_AHEADS_MEDIUM,
+            Self::MediumEn => ffi::WHISPER_AHEADS_MEDIUM_EN,
+            // ggml-large.bin is the original large (v1) model
+            Self::Large => ffi::WHISPER_AHEADS_LARGE_V1,
+        }
+    }
+
+    /// Whether the model can predict speaker turns (tinydiarize)
+    pub fn is_tdrz(&self) -> bool {
+        matches!(self, Self::SmallEnTdrz)
+    }
+
     /// Parse from string (e.g., "tiny", "base", "tiny.en")
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Result<Self> {
         match s.to_lowercase().as_str() {
             "tiny" => Ok(Self::Tiny),
@@ -79,8 +112,9 @@ impl ModelSize {
             "medium" => Ok(Self::Medium),
             "medium.en" => Ok(Self::MediumEn),
             "large" => Ok(Self::Large),
+            "small.en-tdrz" => Ok(Self::SmallEnTdrz),
             _ => Err(anyhow!(
-                "Unknown model: {}. Available: tiny, tiny.en, base, base.en, small, small.en, medium, medium.en, large",
+                "Unknown model: {}. Available: tiny, tiny.en, base, base.en, small, small.en, medium, medium.en, large, small.en-tdrz",
                 s
             )),
         }
@@ -92,6 +126,10 @@ impl ModelSize {
 pub struct ModelSource {
     /// Base URL for model downloads
     pub base_url: String,
+    /// Base URL for the VAD model download
+    pub vad_base_url: String,
+    /// Base URL for tinydiarize model downloads
+    pub tdrz_base_url: String,
     /// Name of the source for display
     pub name: &'static str,
 }
@@ -101,13 +139,39 @@ impl ModelSource {
     pub fn huggingface() -> Self {
         Self {
             base_url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main".to_string(),
+            vad_base_url: "https://huggingface.co/ggml-org/whisper-vad/resolve/main".to_string(),
+            tdrz_base_url: "https://huggingface.co/akashmjn/tinydiarize-whisper.cpp/resolve/main"
+                .to_string(),
             name: "HuggingFace",
         }
     }
 
+    /// Local directory source (models are copied from `dir`)
+    ///
+    /// Useful for offline installs and tests.
+    pub fn local_dir<P: AsRef<Path>>(dir: P) -> Self {
+        let base_url = format!("file://{}", dir.as_ref().display());
+        Self {
+            vad_base_url: base_url.clone(),
+            tdrz_base_url: base_url.clone(),
+            base_url,
+            name: "local directory",
+        }
+    }
+
     /// Construct full download URL for a model
     pub fn download_url(&self, model: ModelSize) -> String {
-        format!("{}/{}", self.base_url, model.filename())
+        let base_url = if model.is_tdrz() {
+            &self.tdrz_base_url
+        } else {
+            &self.base_url
+        };
+        format!("{}/{}", base_url, model.filename())
+    }
+
+    /// Construct full download URL for the VAD model
+    pub fn vad_download_url(&self) -> String {
+        format!("{}/{}", self.vad_base_url, VAD_MODEL_FILENAME)
     }
 }
 
@@ -169,6 +233,32 @@ impl WhisperModelManager {
         self.get_model_path(model).exists()
     }
 
+    /// Get path to the cached VAD model
+    pub fn get_vad_model_path(&self) -> PathBuf {
+        self.cache_dir.join(VAD_MODEL_FILENAME)
+    }
+
+    /// Check if the VAD model is cached locally
+    pub fn is_vad_cached(&self) -> bool {
+        self.get_vad_model_path().exists()
+    }
+
+    /// Get or download the Silero VAD model
+    ///
+    /// Returns the path to the model file, downloading it into the model cache first
+    /// if needed.
+    pub async fn get_or_download_vad(&self) -> Result<PathBuf> {
+        let vad_path = self.get_vad_model_path();
+
+        if vad_path.exists() {
+            return Ok(vad_path);
+        }
+
+        self.download_vad_model().await?;
+
+        Ok(vad_path)
+    }
+
     /// Get or download a model
     ///
     /// Returns the path to the model file. If the model is already cached,
@@ -197,26 +287,47 @@ impl WhisperModelManager {
         let model_path = self.get_model_path(model);
         let url = self.source.download_url(model);
 
-        println!(
+        eprintln!(
             "Downloading {} model ({} MB) from {}...",
             model.display_name(),
             model.size_mb(),
             self.source.name
         );
 
-        self.download_model_impl(&url, &model_path, model).await
+        self.download_model_impl(&url, &model_path, model.display_name())
+            .await
+    }
+
+    /// Download the Silero VAD model from the configured source
+    #[cfg(feature = "whisper")]
+    pub async fn download_vad_model(&self) -> Result<()> {
+        fs::create_dir_all(&self.cache_dir).context("Failed to create model cache directory")?;
+
+        let vad_path = self.get_vad_model_path();
+        let url = self.source.vad_download_url();
+
+        eprintln!(
+            "Downloading VAD model ({} MB) from {}...",
+            VAD_MODEL_SIZE_MB, self.source.name
+        );
+
+        self.download_model_impl(&url, &vad_path, "VAD").await
     }
 
     /// Download a model implementation (async)
     #[cfg(feature = "whisper")]
-    async fn download_model_impl(
-        &self,
-        url: &str,
-        destination: &Path,
-        model: ModelSize,
-    ) -> Result<()> {
+    async fn download_model_impl(&self, url: &str, destination: &Path, name: &str) -> Result<()> {
         use indicatif::{ProgressBar, ProgressStyle};
 
+        // Local sources are copied directly
+        if let Some(source_path) = url.strip_prefix("file://") {
+            fs::copy(source_path, destination).context(format!(
+                "Failed to copy {} model from {}",
+                name, source_path
+            ))?;
+            return Ok(());
+        }
+
         // Create HTTP client
         let client = reqwest::Client::new();
 
@@ -259,7 +370,7 @@ impl WhisperModelManager {
 
         pb.finish_with_message(format!(
             "✓ Downloaded {} model to {}",
-            model.display_name(),
+            name,
             destination.display()
         ));
 
@@ -287,6 +398,7 @@ impl WhisperModelManager {
             (ModelSize::Medium, ModelSize::Medium.size_mb()),
             (ModelSize::MediumEn, ModelSize::MediumEn.size_mb()),
             (ModelSize::Large, ModelSize::Large.size_mb()),
+            (ModelSize::SmallEnTdrz, ModelSize::SmallEnTdrz.size_mb()),
         ]
     }
 
@@ -313,6 +425,10 @@ impl WhisperModelManager {
             }
         }
 
+        if let Ok(metadata) = fs::metadata(self.get_vad_model_path()) {
+            total += metadata.len();
+        }
+
         Ok(total / 1024 / 1024)
     }
 
@@ -331,7 +447,7 @@ impl WhisperModelManager {
         fs::remove_file(&model_path)
             .context(format!("Failed to remove model {}", model.display_name()))?;
 
-        println!("✓ Removed model: {}", model.display_name());
+        eprintln!("✓ Removed model: {}", model.display_name());
 
         Ok(())
     }
@@ -344,7 +460,7 @@ impl WhisperModelManager {
 
         fs::remove_dir_all(&self.cache_dir).context("Failed to clear model cache")?;
 
-        println!("✓ Cleared model cache at {}", self.cache_dir.display());
+        eprintln!("✓ Cleared model cache at {}", self.cache_dir.display());
 
         Ok(())
     }
@@ -395,7 +511,7 @@ mod tests {
     #[test]
     fn test_available_models_count() {
         let models = WhisperModelManager::list_available_models();
-        assert_eq!(models.len(), 9); // tiny, tiny.en, base, base.en, small, small.en, medium, medium.en, large
+        assert_eq!(models.len(), 10); // tiny, tiny.en, base, base.en, small, small.en, medium, medium.en, large, small.en-tdrz
     }
 
     #[test]
@@ -405,6 +521,22 @@ mod tests {
         assert_eq!(ModelSize::Large.size_mb(), 3000);
     }
 
+    #[test]
+    fn test_dtw_aheads_presets() {
+        assert_eq!(
+            ModelSize::Tiny.dtw_aheads_preset(),
+            ffi::WHISPER_AHEADS_TINY
+        );
+        assert_eq!(
+            ModelSize::MediumEn.dtw_aheads_preset(),
+            ffi::WHISPER_AHEADS_MEDIUM_EN
+        );
+        // Every model has a real preset
+        for (model, _) in WhisperModelManager::list_available_models() {
+            assert!(model.dtw_aheads_preset() > ffi::WHISPER_AHEADS_CUSTOM);
+        }
+    }
+
     #[test]
     fn test_huggingface_url() {
         let source = ModelSource::huggingface();
@@ -413,6 +545,52 @@ mod tests {
         assert!(url.contains("ggml-tiny.bin"));
     }
 
+    #[test]
+    fn test_tdrz_model() {
+        let model = ModelSize::from_str("small.en-tdrz").unwrap();
+        assert_eq!(model, ModelSize::SmallEnTdrz);
+        assert!(model.is_tdrz());
+        assert!(!ModelSize::SmallEn.is_tdrz());
+
+        let url = ModelSource::huggingface().download_url(model);
+        assert!(url.contains("tinydiarize"));
+        assert!(url.ends_with("ggml-small.en-tdrz.bin"));
+    }
+
+    #[test]
+    fn test_vad_download_url() {
+        let source = ModelSource::huggingface();
+        let url = source.vad_download_url();
+        assert!(url.contains("whisper-vad"));
+        assert!(url.ends_with(VAD_MODEL_FILENAME));
+    }
+
+    #[tokio::test]
+    async fn test_get_or_download_vad_from_local_dir() {
+        let root = std::env::temp_dir().join(format!("orangenote_vad_{}", std::process::id()));
+        let source_dir = root.join("source");
+        let cache_dir = root.join("cache");
+        fs::create_dir_all(&source_dir).unwrap();
+        fs::write(source_dir.join(VAD_MODEL_FILENAME), b"silero").unwrap();
+
+        let manager = WhisperModelManager::with_cache_and_source(
+            cache_dir.clone(),
+            ModelSource::local_dir(&source_dir),
+        );
+        assert!(!manager.is_vad_cached());
+
+        let path = manager.get_or_download_vad().await.unwrap();
+        assert_eq!(path, cache_dir.join(VAD_MODEL_FILENAME));
+        assert_eq!(fs::read(&path).unwrap(), b"silero");
+        assert!(manager.is_vad_cached());
+
+        // Cached copy is reused even if the source disappears
+        fs::remove_dir_all(&source_dir).unwrap();
+        assert_eq!(manager.get_or_download_vad().await.unwrap(), path);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
     #[test]
     fn test_custom_cache_dir() {
         let cache_dir = PathBuf::from("/tmp/test_cache");
@@ -0,0 +1,239 @@
+//! Pool of whisper decoding states sharing one loaded model
+//!
+//! A whisper model is large (up to several GB) but a decoding state is comparatively
+//! small. `WhisperStatePool` lets concurrent requests share one
+//! `WhisperContextWrapper` and hands each request its own `WhisperState`, creating
+//! states lazily up to a maximum and reusing them afterwards.
+
+use super::context::{WhisperContextWrapper, WhisperState};
+use anyhow::Result;
+use log::debug;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+/// Pool handing out per-request whisper states for one shared model
+pub struct WhisperStatePool {
+    context: Arc<WhisperContextWrapper>,
+    slots: Mutex<Slots<WhisperState>>,
+    returned: Condvar,
+}
+
+impl WhisperStatePool {
+    /// Create a pool for a shared context
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - Loaded model shared by all states
+    /// * `max_states` - Maximum number of states alive at the same time (at least 1)
+    ///
+    /// # Returns
+    ///
+    /// The pool; no state is created until the first `acquire`
+    pub fn new(context: Arc<WhisperContextWrapper>, max_states: usize) -> Self {
+        WhisperStatePool {
+            context,
+            slots: Mutex::new(Slots::new(max_states.max(1))),
+            returned: Condvar::new(),
+        }
+    }
+
+    /// Get the shared context
+    pub fn context(&self) -> &Arc<WhisperContextWrapper> {
+        &self.context
+    }
+
+    /// Maximum number of states alive at the same time
+    pub fn max_states(&self) -> usize {
+        self.lock_slots().max
+    }
+
+    /// Number of states currently handed out
+    pub fn in_use(&self) -> usize {
+        self.lock_slots().in_use()
+    }
+
+    /// Get a state, waiting until one is returned if all are in use
+    ///
+    /// # Returns
+    ///
+    /// Result containing a state that goes back to the pool when dropped, or an
+    /// error if a new state could not be created
+    pub fn acquire(&self) -> Result<PooledState<'_>> {
+        let mut slots = self.lock_slots();
+        loop {
+            match slots.take() {
+                Take::Idle(state) => return Ok(self.pooled(state)),
+                Take::Create => {
+                    drop(slots);
+                    return self.create();
+                }
+                Take::Full => {
+                    slots = self
+                        .returned
+                        .wait(slots)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                }
+            }
+        }
+    }
+
+    /// Get a state without waiting
+    ///
+    /// # Returns
+    ///
+    /// Result containing a state, `None` if all states are in use, or an error if a
+    /// new state could not be created
+    pub fn try_acquire(&self) -> Result<Option<PooledState<'_>>> {
+        let mut slots = self.lock_slots();
+        match slots.take() {
+            Take::Idle(state) => Ok(Some(self.pooled(state))),
+            Take::Create => {
+                drop(slots);
+                self.create().map(Some)
+            }
+            Take::Full => Ok(None),
+        }
+    }
+
+    /// Create a state for a slot reserved by `Slots::take`
+    fn create(&self) -> Result<PooledState<'_>> {
+        match self.context.create_state() {
+            Ok(state) => {
+                debug!("Created whisper state ({} in use)", self.in_use());
+                Ok(self.pooled(state))
+            }
+            Err(e) => {
+                self.lock_slots().cancel_create();
+                self.returned.notify_one();
+                Err(e.context("Failed to create pooled whisper state"))
+            }
+        }
+    }
+
+    fn pooled(&self, state: WhisperState) -> PooledState<'_> {
+        PooledState {
+            pool: self,
+            state: Some(state),
+        }
+    }
+
+    fn release(&self, state: WhisperState) {
+        self.lock_slots().put(state);
+        self.returned.notify_one();
+    }
+
+    fn lock_slots(&self) -> MutexGuard<'_, Slots<WhisperState>> {
+        self.slots
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A state borrowed from a `WhisperStatePool`, returned to the pool when dropped
+pub struct PooledState<'a> {
+    pool: &'a WhisperStatePool,
+    state: Option<WhisperState>,
+}
+
+impl Deref for PooledState<'_> {
+    type Target = WhisperState;
+
+    fn deref(&self) -> &WhisperState {
+        self.state.as_ref().expect("pooled state already returned")
+    }
+}
+
+impl DerefMut for PooledState<'_> {
+    fn deref_mut(&mut self) -> &mut WhisperState {
+        self.state.as_mut().expect("pooled state already returned")
+    }
+}
+
+impl Drop for PooledState<'_> {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            self.pool.release(state);
+        }
+    }
+}
+
+/// Outcome of asking the pool bookkeeping for a state
+#[derive(Debug, PartialEq)]
+enum Take<T> {
+    /// Reuse an idle state
+    Idle(T),
+    /// A slot was reserved; the caller creates the state
+    Create,
+    /// All states are in use
+    Full,
+}
+
+/// Idle states and the number of states alive, independent of whisper
+#[derive(Debug)]
+struct Slots<T> {
+    idle: Vec<T>,
+    created: usize,
+    max: usize,
+}
+
+impl<T> Slots<T> {
+    fn new(max: usize) -> Self {
+        Slots {
+            idle: Vec::new(),
+            created: 0,
+            max,
+        }
+    }
+
+    fn take(&mut self) -> Take<T> {
+        if let Some(item) = self.idle.pop() {
+            Take::Idle(item)
+        } else if self.created < self.max {
+            self.created += 1;
+            Take::Create
+        } else {
+            Take::Full
+        }
+    }
+
+    fn put(&mut self, item: T) {
+        self.idle.push(item);
+    }
+
+    /// Release a slot reserved by `take` whose state could not be created
+    fn cancel_create(&mut self) {
+        self.created = self.created.saturating_sub(1);
+    }
+
+    fn in_use(&self) -> usize {
+        self.created - self.idle.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slots_create_up_to_max_then_reuse() {
+        let mut slots = Slots::new(2);
+        assert_eq!(slots.take(), Take::Create);
+        assert_eq!(slots.take(), Take::Create);
+        assert_eq!(slots.take(), Take::Full);
+        assert_eq!(slots.in_use(), 2);
+
+        slots.put("a");
+        assert_eq!(slots.in_use(), 1);
+        assert_eq!(slots.take(), Take::Idle("a"));
+        assert_eq!(slots.take(), Take::Full);
+    }
+
+    #[test]
+    fn test_slots_failed_create_frees_slot() {
+        let mut slots: Slots<()> = Slots::new(1);
+        assert_eq!(slots.take(), Take::Create);
+        slots.cancel_create();
+        assert_eq!(slots.in_use(), 0);
+        assert_eq!(slots.take(), Take::Create);
+    }
+}
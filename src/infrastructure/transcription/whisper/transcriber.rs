@@ -3,20 +3,34 @@
 //! This module provides the high-level `WhisperTranscriber` that orchestrates
 //! audio processing and transcription using whisper.cpp.
 
-use super::context::TranscriptionResult;
-use super::merger::{merge_transcription_results, MergeConfig};
-use crate::infrastructure::audio::{AudioChunk, AudioProcessor, ChunkConfig};
+use super::language::{average_languages, window_offsets, LanguageDetection, WindowLanguages};
+use crate::infrastructure::audio::{AudioChunk, AudioProcessor, ChunkConfig, WHISPER_SAMPLE_RATE};
+use crate::infrastructure::transcription::backend::TranscriptionBackend;
+use crate::infrastructure::transcription::merger::{merge_transcription_results, MergeConfig};
+use crate::infrastructure::transcription::options::TranscribeOptions;
+use crate::infrastructure::transcription::pipeline::{self, transcribe_chunk_with_retries};
+use crate::infrastructure::transcription::result::TranscriptionResult;
 use anyhow::{anyhow, Context, Result};
-use log::{debug, info};
+use log::{debug, info, warn};
+use std::collections::BTreeMap;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
 
 use super::context::WhisperContextWrapper;
 use super::model_manager::{ModelSize, WhisperModelManager};
+use super::pool::WhisperStatePool;
 
 /// Main transcription engine combining audio processing and whisper inference
+///
+/// Cloning a transcriber shares the loaded model. Without a state pool, transcriptions
+/// through clones of one transcriber run one at a time; with `with_state_pool` they run
+/// concurrently on separate whisper states.
+#[derive(Clone)]
 pub struct WhisperTranscriber {
     model_path: std::path::PathBuf,
-    context: WhisperContextWrapper,
+    context: Arc<WhisperContextWrapper>,
+    states: Option<Arc<WhisperStatePool>>,
     threads: usize,
 }
 
@@ -51,11 +65,81 @@ impl WhisperTranscriber {
             threads
         );
 
-        Ok(WhisperTranscriber {
-            model_path,
+        Ok(Self::from_context(model_path, Arc::new(context), threads))
+    }
+
+    /// Create a new transcriber with DTW token alignment enabled
+    ///
+    /// # Arguments
+    ///
+    /// * `model_path` - Path to the whisper.cpp model file
+    /// * `threads` - Number of threads for transcription
+    /// * `model` - Model size, used to select the DTW alignment heads
+    ///
+    /// # Returns
+    ///
+    /// Result containing the initialized transcriber, or an error if initialization failed
+    pub fn new_with_dtw<P: AsRef<Path>>(
+        model_path: P,
+        threads: usize,
+        model: ModelSize,
+    ) -> Result<Self> {
+        let model_path = model_path.as_ref().to_path_buf();
+
+        info!(
+            "Initializing WhisperTranscriber with DTW alignment ({}) and model: {}",
+            model.display_name(),
+            model_path.display()
+        );
+
+        if !model_path.exists() {
+            return Err(anyhow!("Model file not found: {}", model_path.display()));
+        }
+
+        let context = WhisperContextWrapper::new_with_dtw(&model_path, model)
+            .context("Failed to initialize whisper context with DTW")?;
+
+        Ok(Self::from_context(model_path, Arc::new(context), threads))
+    }
+
+    /// Create a transcriber for an already loaded, shared context
+    ///
+    /// # Arguments
+    ///
+    /// * `model_path` - Path the context's model was loaded from
+    /// * `context` - Shared whisper context
+    /// * `threads` - Number of threads for transcription
+    ///
+    /// # Returns
+    ///
+    /// The transcriber, sharing the model with every other user of `context`
+    pub fn from_context<P: AsRef<Path>>(
+        model_path: P,
+        context: Arc<WhisperContextWrapper>,
+        threads: usize,
+    ) -> Self {
+        WhisperTranscriber {
+            model_path: model_path.as_ref().to_path_buf(),
             context,
+            states: None,
             threads,
-        })
+        }
+    }
+
+    /// Run transcriptions on states from a pool instead of the context's default state
+    ///
+    /// Concurrent calls (from clones of this transcriber or through a shared
+    /// reference) then run in parallel, up to `max_states` at a time.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_states` - Maximum number of concurrent transcriptions
+    pub fn with_state_pool(mut self, max_states: usize) -> Self {
+        self.states = Some(Arc::new(WhisperStatePool::new(
+            Arc::clone(&self.context),
+            max_states,
+        )));
+        self
     }
 
     /// Create a transcriber from a model manager, automatically handling model download if needed
@@ -87,13 +171,118 @@ impl WhisperTranscriber {
         Self::new(model_path, threads)
     }
 
+    /// Create a DTW-enabled transcriber from a model manager, downloading the model if needed
+    ///
+    /// # Arguments
+    ///
+    /// * `model_manager` - Model manager instance
+    /// * `model_size` - Model size enum
+    /// * `threads` - Number of threads for transcription
+    ///
+    /// # Returns
+    ///
+    /// Result containing the initialized transcriber
+    pub async fn from_model_manager_with_dtw(
+        model_manager: &WhisperModelManager,
+        model_size: ModelSize,
+        threads: usize,
+    ) -> Result<Self> {
+        let model_path = model_manager
+            .get_or_download(model_size)
+            .await
+            .context("Failed to get model")?;
+
+        Self::new_with_dtw(model_path, threads, model_size)
+    }
+
+    /// Detect the spoken language of an audio file without transcribing it
+    ///
+    /// With a single window only the beginning of the file is analyzed. With several
+    /// windows, they are spread evenly across the file and their probabilities averaged,
+    /// which is more robust for files starting with music or silence.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio_path` - Path to the audio file
+    /// * `window_secs` - Duration of each analyzed window in seconds (at most 30 are used)
+    /// * `windows` - Number of windows to analyze
+    /// * `top_n` - Maximum number of languages to report
+    ///
+    /// # Returns
+    ///
+    /// Result containing the averaged and per-window language probabilities
+    pub fn detect_language_file<P: AsRef<Path>>(
+        &self,
+        audio_path: P,
+        window_secs: u32,
+        windows: usize,
+        top_n: usize,
+    ) -> Result<LanguageDetection> {
+        let audio_path = audio_path.as_ref();
+        info!(
+            "Detecting language of {} ({} x {}s windows)",
+            audio_path.display(),
+            windows,
+            window_secs
+        );
+
+        if window_secs == 0 || windows == 0 {
+            return Err(anyhow!("Window duration and count must be at least 1"));
+        }
+
+        let audio_samples =
+            AudioProcessor::process(audio_path).context("Failed to process audio file")?;
+        let samples = &audio_samples.samples;
+
+        let window_samples = window_secs as usize * WHISPER_SAMPLE_RATE as usize;
+        let offsets = window_offsets(samples.len(), window_samples, windows);
+        if offsets.is_empty() {
+            return Err(anyhow!("Audio file contains no samples"));
+        }
+
+        let mut results = Vec::with_capacity(offsets.len());
+        for offset in offsets {
+            let end = (offset + window_samples).min(samples.len());
+            let languages = self
+                .context
+                .detect_language(&samples[offset..end], self.threads, usize::MAX)
+                .with_context(|| format!("Failed to detect language at sample {}", offset))?;
+
+            let start_ms = offset as i64 * 1000 / WHISPER_SAMPLE_RATE as i64;
+            let duration_ms = (end - offset) as i64 * 1000 / WHISPER_SAMPLE_RATE as i64;
+            if let Some(best) = languages.first() {
+                debug!(
+                    "Window at {}ms: {} ({:.2})",
+                    start_ms, best.language, best.probability
+                );
+            }
+
+            results.push(WindowLanguages {
+                start_ms,
+                duration_ms,
+                languages,
+            });
+        }
+
+        let all: Vec<_> = results.iter().map(|w| w.languages.clone()).collect();
+        let languages = average_languages(&all, top_n);
+
+        for window in &mut results {
+            window.languages.truncate(top_n);
+        }
+
+        Ok(LanguageDetection {
+            languages,
+            windows: results,
+        })
+    }
+
     /// Transcribe an audio file
     ///
     /// # Arguments
     ///
     /// * `audio_path` - Path to the audio file
-    /// * `language` - Optional language code (e.g., "en", "ru"). None for auto-detect
-    /// * `translate` - Whether to translate to English
+    /// * `options` - Decoding options (language, translation, sampling strategy, etc.)
     ///
     /// # Returns
     ///
@@ -101,15 +290,14 @@ impl WhisperTranscriber {
     pub fn transcribe_file<P: AsRef<Path>>(
         &self,
         audio_path: P,
-        language: Option<&str>,
-        translate: bool,
+        options: &TranscribeOptions,
     ) -> Result<TranscriptionResult> {
         let audio_path = audio_path.as_ref();
         info!(
             "Transcribing audio file: {} (language: {:?}, translate: {})",
             audio_path.display(),
-            language,
-            translate
+            options.language,
+            options.translate
         );
 
         // Step 1: Process audio file to PCM samples at 16kHz mono
@@ -123,7 +311,7 @@ impl WhisperTranscriber {
         );
 
         // Step 2: Transcribe the samples
-        self.transcribe_samples(&audio_samples.samples, language, translate)
+        self.transcribe_samples(&audio_samples.samples, options)
     }
 
     /// Transcribe an audio file with chunking support for long files
@@ -132,11 +320,17 @@ impl WhisperTranscriber {
     /// separately, and merges the results. This helps avoid whisper.cpp issues
     /// with very long audio files (hallucinations, repeated noise labels).
     ///
+    /// Without a fixed language, the language detected on the first chunk with confident
+    /// speech is used for the later chunks, unless `options.language_per_chunk` is set. Each segment keeps the
+    /// language of the chunk it was decoded in.
+    ///
+    /// If `options.cancellation` is cancelled, the segments completed so far are
+    /// merged and returned with `partial` set instead of failing.
+    ///
     /// # Arguments
     ///
     /// * `audio_path` - Path to the audio file
-    /// * `language` - Optional language code (e.g., "en", "ru"). None for auto-detect
-    /// * `translate` - Whether to translate to English
+    /// * `options` - Decoding options (language, translation, sampling strategy, etc.)
     /// * `chunk_config` - Configuration for chunking (duration, overlap)
     /// * `progress_callback` - Callback for progress updates (current_chunk, total_chunks)
     ///
@@ -151,10 +345,10 @@ impl WhisperTranscriber {
     ///     chunk_duration_secs: 300, // 5 minutes
     ///     overlap_secs: 5,
     /// };
+    /// let options = TranscribeOptions::new().language("en");
     /// let result = transcriber.transcribe_file_chunked(
     ///     "long_podcast.mp3",
-    ///     Some("en"),
-    ///     false,
+    ///     &options,
     ///     &config,
     ///     |current, total| println!("Processing chunk {}/{}", current + 1, total),
     /// )?;
@@ -162,8 +356,7 @@ impl WhisperTranscriber {
     pub fn transcribe_file_chunked<P, F>(
         &self,
         audio_path: P,
-        language: Option<&str>,
-        translate: bool,
+        options: &TranscribeOptions,
         chunk_config: &ChunkConfig,
         progress_callback: F,
     ) -> Result<TranscriptionResult>
@@ -189,7 +382,61 @@ impl WhisperTranscriber {
             audio_samples.duration_seconds
         );
 
-        // Step 2: Split into chunks
+        // Step 2: Transcribe the chunks and merge the results
+        pipeline::transcribe_chunked(
+            self,
+            &audio_samples,
+            options,
+            chunk_config,
+            progress_callback,
+        )
+    }
+
+    /// Transcribe an audio file with chunking, running several chunks concurrently
+    ///
+    /// Creates `parallel_chunks` whisper states sharing this transcriber's model and
+    /// distributes the chunks among them. The transcriber's threads are divided
+    /// between the states. Results are merged in chunk order, so the output matches
+    /// sequential chunked transcription except that chunk text context
+    /// (`options.chunk_context_words`) is not carried over.
+    ///
+    /// Streamed segments and chunk progress are reported in chunk order as soon as
+    /// all earlier chunks are done.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio_path` - Path to the audio file
+    /// * `options` - Decoding options (language, translation, sampling strategy, etc.)
+    /// * `chunk_config` - Configuration for chunking (duration, overlap)
+    /// * `parallel_chunks` - Number of chunks transcribed at the same time
+    /// * `progress_callback` - Callback for progress updates (completed_chunk, total_chunks)
+    ///
+    /// # Returns
+    ///
+    /// Result containing the merged transcription result with corrected timestamps
+    pub fn transcribe_file_chunked_parallel<P, F>(
+        &self,
+        audio_path: P,
+        options: &TranscribeOptions,
+        chunk_config: &ChunkConfig,
+        parallel_chunks: usize,
+        progress_callback: F,
+    ) -> Result<TranscriptionResult>
+    where
+        P: AsRef<Path>,
+        F: Fn(usize, usize),
+    {
+        let audio_path = audio_path.as_ref();
+        info!(
+            "Transcribing audio file with {} parallel chunks: {} (chunk_size={}s, overlap={}s)",
+            parallel_chunks,
+            audio_path.display(),
+            chunk_config.chunk_duration_secs,
+            chunk_config.overlap_secs
+        );
+
+        let audio_samples =
+            AudioProcessor::process(audio_path).context("Failed to process audio file")?;
         let chunks = audio_samples.split_into_chunks(chunk_config);
         let total_chunks = chunks.len();
 
@@ -197,38 +444,155 @@ impl WhisperTranscriber {
             return Err(anyhow!("No audio chunks generated"));
         }
 
+        let (n_states, threads_per_state) = balance_threads(
+            options.threads.unwrap_or(self.threads),
+            parallel_chunks.min(total_chunks),
+        );
         info!(
-            "Split audio into {} chunks ({:.1}s each, {}s overlap)",
-            total_chunks, chunk_config.chunk_duration_secs, chunk_config.overlap_secs
+            "Split audio into {} chunks, running {} at a time with {} threads each",
+            total_chunks, n_states, threads_per_state
         );
 
-        // Step 3: Transcribe each chunk
-        let mut chunk_results: Vec<(TranscriptionResult, i64)> = Vec::with_capacity(total_chunks);
-
-        for chunk in chunks {
-            progress_callback(chunk.index, total_chunks);
-
-            debug!(
-                "Transcribing chunk {}/{}: {} samples, offset {}ms",
-                chunk.index + 1,
-                total_chunks,
-                chunk.samples.len(),
-                chunk.start_offset_ms
-            );
+        if options.chunk_context_words > 0 {
+            warn!("Chunk text context is not used with parallel chunk transcription");
+        }
 
-            let result = self
-                .transcribe_chunk(&chunk, language, translate)
+        // Callbacks are driven from this thread in chunk order, not from the workers
+        let mut base_options = options.clone();
+        base_options.threads = Some(threads_per_state);
+        base_options.chunk_context_words = 0;
+        base_options.progress = None;
+        base_options.on_segment = None;
+
+        let mut states = (0..n_states)
+            .map(|_| self.context.create_state())
+            .collect::<Result<Vec<_>>>()
+            .context("Failed to create whisper states")?;
+
+        let total_ms = audio_samples.duration_ms().max(1) as f32;
+        let mut completed: BTreeMap<usize, TranscriptionResult> = BTreeMap::new();
+        let mut next_to_report = 0;
+        let mut report = |completed: &BTreeMap<usize, TranscriptionResult>| {
+            while let Some(result) = completed.get(&next_to_report) {
+                let chunk = &chunks[next_to_report];
+                progress_callback(chunk.index, total_chunks);
+                if let Some(on_segment) = &options.on_segment {
+                    let on_segment = on_segment.with_offset(chunk.start_offset_ms);
+                    for segment in &result.segments {
+                        on_segment.emit(segment.clone());
+                    }
+                }
+                if let Some(progress) = &options.progress {
+                    let end_ms = chunks
+                        .get(next_to_report + 1)
+                        .map_or(total_ms, |next| next.start_offset_ms as f32);
+                    progress.report(end_ms / total_ms);
+                }
+                next_to_report += 1;
+            }
+        };
+
+        // Without a fixed language, chunks are decoded one by one until one has a
+        // confident detection, which then decides the language of the remaining chunks
+        let mut first_chunk = 0;
+        if base_options.language.is_none() && !options.language_per_chunk {
+            let state = &mut states[0];
+            while first_chunk < total_chunks && !base_options.is_cancelled() {
+                let chunk = &chunks[first_chunk];
+                let result = transcribe_chunk_with_retries(chunk, &base_options, |options| {
+                    state.transcribe(&chunk.samples, options)
+                })
                 .with_context(|| format!("Failed to transcribe chunk {}", chunk.index))?;
+                first_chunk += 1;
+
+                let language = pipeline::confident_language(&result).map(str::to_string);
+                completed.insert(chunk.index, result);
+                report(&completed);
+                match language {
+                    Some(language) => {
+                        info!(
+                            "Detected language {} on chunk {}, using it for the remaining chunks",
+                            language, chunk.index
+                        );
+                        base_options.language = Some(language);
+                        break;
+                    }
+                    None => debug!(
+                        "No confident language on chunk {}, detecting again on the next one",
+                        chunk.index
+                    ),
+                }
+            }
+        }
 
-            chunk_results.push((result, chunk.start_offset_ms));
+        let next_chunk = AtomicUsize::new(first_chunk);
+        let failed = AtomicBool::new(false);
+        let mut first_error: Option<anyhow::Error> = None;
+
+        std::thread::scope(|scope| {
+            let (tx, rx) = mpsc::channel();
+
+            for state in states.iter_mut() {
+                let tx = tx.clone();
+                let (chunks, base_options) = (&chunks, &base_options);
+                let (next_chunk, failed) = (&next_chunk, &failed);
+
+                scope.spawn(move || loop {
+                    let index = next_chunk.fetch_add(1, Ordering::SeqCst);
+                    if index >= chunks.len()
+                        || base_options.is_cancelled()
+                        || failed.load(Ordering::SeqCst)
+                    {
+                        break;
+                    }
+
+                    debug!("Transcribing chunk {}/{}", index + 1, chunks.len());
+                    let chunk = &chunks[index];
+                    let result = transcribe_chunk_with_retries(chunk, base_options, |options| {
+                        state.transcribe(&chunk.samples, options)
+                    });
+                    if tx.send((index, result)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(tx);
+
+            for (index, result) in rx {
+                match result {
+                    Ok(result) => {
+                        completed.insert(index, result);
+                        report(&completed);
+                    }
+                    Err(e) => {
+                        failed.store(true, Ordering::SeqCst);
+                        if first_error.is_none() {
+                            first_error =
+                                Some(e.context(format!("Failed to transcribe chunk {}", index)));
+                        }
+                    }
+                }
+            }
+        });
+
+        if let Some(e) = first_error {
+            return Err(e);
         }
 
-        // Step 4: Merge results using the merger module
+        let completed = contiguous_chunks(completed);
+        let cancelled =
+            completed.len() < total_chunks || completed.iter().any(|(_, result)| result.partial);
+        let chunk_results: Vec<(TranscriptionResult, i64)> = completed
+            .into_iter()
+            .map(|(index, result)| (result, chunks[index].start_offset_ms))
+            .collect();
+
         let merge_config = MergeConfig::from_overlap_secs(chunk_config.overlap_secs);
-        let merge_result = merge_transcription_results(chunk_results, merge_config);
+        let mut merge_result = merge_transcription_results(chunk_results, merge_config);
+        merge_result.result.partial |= cancelled;
 
         info!(
-            "Chunked transcription complete: {} segments (removed {} duplicates), language: {}",
+            "Parallel chunked transcription complete: {} segments (removed {} duplicates), language: {}",
             merge_result.result.segments.len(),
             merge_result.duplicates_removed,
             merge_result.result.language
@@ -242,8 +606,7 @@ impl WhisperTranscriber {
     /// # Arguments
     ///
     /// * `chunk` - Audio chunk with samples and metadata
-    /// * `language` - Optional language code. None for auto-detect
-    /// * `translate` - Whether to translate to English
+    /// * `options` - Decoding options (language, translation, sampling strategy, etc.)
     ///
     /// # Returns
     ///
@@ -251,22 +614,9 @@ impl WhisperTranscriber {
     pub fn transcribe_chunk(
         &self,
         chunk: &AudioChunk,
-        language: Option<&str>,
-        translate: bool,
+        options: &TranscribeOptions,
     ) -> Result<TranscriptionResult> {
-        if chunk.samples.is_empty() {
-            return Err(anyhow!("Empty audio chunk provided"));
-        }
-
-        debug!(
-            "Transcribing chunk {}: {} samples ({:.1}s)",
-            chunk.index,
-            chunk.samples.len(),
-            chunk.duration_ms as f64 / 1000.0
-        );
-
-        // Transcribe the chunk's samples
-        self.transcribe_samples(&chunk.samples, language, translate)
+        pipeline::transcribe_chunk(self, chunk, options)
     }
 
     /// Transcribe PCM samples directly
@@ -274,8 +624,8 @@ impl WhisperTranscriber {
     /// # Arguments
     ///
     /// * `samples` - PCM samples at 16kHz, f32 normalized to [-1.0, 1.0]
-    /// * `language` - Optional language code. None for auto-detect
-    /// * `translate` - Whether to translate to English
+    /// * `options` - Decoding options. The transcriber's thread count is used
+    ///   unless `options.threads` is set
     ///
     /// # Returns
     ///
@@ -283,86 +633,146 @@ impl WhisperTranscriber {
     pub fn transcribe_samples(
         &self,
         samples: &[f32],
-        language: Option<&str>,
-        translate: bool,
+        options: &TranscribeOptions,
     ) -> Result<TranscriptionResult> {
-        if samples.is_empty() {
-            return Err(anyhow!("No audio samples provided"));
-        }
+        pipeline::transcribe_samples(self, samples, options)
+    }
 
-        info!("Starting transcription of {} samples", samples.len());
+    /// Get the model path being used
+    pub fn model_path(&self) -> &Path {
+        &self.model_path
+    }
 
-        // Configure transcription parameters
-        let mut params =
-            unsafe { super::ffi::whisper_full_default_params(super::ffi::WHISPER_SAMPLING_GREEDY) };
+    /// Get the number of threads
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
 
-        params.n_threads = self.threads as i32;
-        params.translate = translate;
-        params.print_progress = false;
-        params.print_realtime = false;
-        params.print_timestamps = true;
-        params.token_timestamps = true;
+    /// Get the shared whisper context
+    pub fn context(&self) -> &Arc<WhisperContextWrapper> {
+        &self.context
+    }
 
-        // Disable VAD to avoid requiring VAD model
-        params.vad = false;
+    /// Get the state pool, if transcriptions run on pooled states
+    pub fn state_pool(&self) -> Option<&Arc<WhisperStatePool>> {
+        self.states.as_ref()
+    }
+}
 
-        // Set language if provided
-        let lang_c_string;
-        if let Some(lang) = language {
-            lang_c_string = std::ffi::CString::new(lang)?;
-            params.language = lang_c_string.as_ptr();
+impl TranscriptionBackend for WhisperTranscriber {
+    fn transcribe(
+        &self,
+        samples: &[f32],
+        options: &TranscribeOptions,
+    ) -> Result<TranscriptionResult> {
+        let mut options = options.clone();
+        if options.threads.is_none() {
+            options.threads = Some(self.threads);
         }
 
-        debug!(
-            "Transcription params: threads={}, translate={}, language={:?}",
-            params.n_threads, params.translate, language
-        );
+        match &self.states {
+            Some(states) => states.acquire()?.transcribe(samples, &options),
+            None => self.context.transcribe(samples, &options),
+        }
+        .context("Whisper transcription failed")
+    }
+}
 
-        // Run transcription
-        unsafe {
-            let ret = super::ffi::whisper_full(
-                self.context.as_ptr(),
-                params,
-                samples.as_ptr(),
-                samples.len() as i32,
-            );
-
-            if ret != 0 {
-                return Err(anyhow!("Transcription failed with code: {}", ret));
-            }
+/// Divide threads between parallel whisper states
+///
+/// # Arguments
+///
+/// * `threads` - Total number of threads
+/// * `parallel` - Requested number of concurrent states
+///
+/// # Returns
+///
+/// `(states, threads_per_state)`, never more states than threads and at least one of each
+fn balance_threads(threads: usize, parallel: usize) -> (usize, usize) {
+    let threads = threads.max(1);
+    let states = parallel.clamp(1, threads);
+    (states, threads / states)
+}
+
+/// Keep the chunks that form an unbroken run from the start of the audio
+///
+/// After a cancellation, parallel workers may have finished later chunks while an
+/// earlier one is still missing; merging those would leave a hole in the transcript.
+///
+/// # Arguments
+///
+/// * `completed` - Finished chunk results keyed by chunk index
+///
+/// # Returns
+///
+/// Chunks from index 0 up to the first missing chunk, or up to and including the
+/// first partial chunk
+fn contiguous_chunks(
+    completed: BTreeMap<usize, TranscriptionResult>,
+) -> Vec<(usize, TranscriptionResult)> {
+    let mut chunks = Vec::with_capacity(completed.len());
+    for (index, result) in completed {
+        if index != chunks.len() {
+            break;
         }
+        let partial = result.partial;
+        chunks.push((index, result));
+        if partial {
+            break;
+        }
+    }
+    chunks
+}
 
-        // Extract results
-        let result = self
-            .context
-            .extract_results()
-            .context("Failed to extract transcription results")?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        info!(
-            "Transcription complete: {} segments, language: {}",
-            result.segments.len(),
-            result.language
-        );
+    fn chunk_result(partial: bool) -> TranscriptionResult {
+        TranscriptionResult {
+            partial,
+            ..TranscriptionResult::default()
+        }
+    }
 
-        Ok(result)
+    fn indices(chunks: &[(usize, TranscriptionResult)]) -> Vec<usize> {
+        chunks.iter().map(|(index, _)| *index).collect()
     }
 
-    /// Get the model path being used
-    pub fn model_path(&self) -> &Path {
-        &self.model_path
+    #[test]
+    fn test_contiguous_chunks_stops_at_gap() {
+        // Cancelled while chunk 2 was still decoding
+        let completed: BTreeMap<_, _> = [0, 1, 3]
+            .into_iter()
+            .map(|index| (index, chunk_result(false)))
+            .collect();
+        assert_eq!(indices(&contiguous_chunks(completed)), vec![0, 1]);
+
+        let completed: BTreeMap<_, _> = [1, 2]
+            .into_iter()
+            .map(|index| (index, chunk_result(false)))
+            .collect();
+        assert!(contiguous_chunks(completed).is_empty());
     }
 
-    /// Get the number of threads
-    pub fn threads(&self) -> usize {
-        self.threads
+    #[test]
+    fn test_contiguous_chunks_includes_partial_chunk() {
+        let completed: BTreeMap<_, _> = [(0, false), (1, true), (2, false)]
+            .into_iter()
+            .map(|(index, partial)| (index, chunk_result(partial)))
+            .collect();
+        let chunks = contiguous_chunks(completed);
+        assert_eq!(indices(&chunks), vec![0, 1]);
+        assert!(chunks[1].1.partial);
     }
-}
 
-#[cfg(test)]
-mod tests {
     #[test]
-    fn test_empty_samples() {
-        // This test would require a valid model, so we skip it in basic tests
-        // but the error handling is tested through Result return type
+    fn test_balance_threads() {
+        assert_eq!(balance_threads(32, 4), (4, 8));
+        assert_eq!(balance_threads(8, 3), (3, 2));
+        // Never more states than threads
+        assert_eq!(balance_threads(2, 8), (2, 1));
+        assert_eq!(balance_threads(4, 0), (1, 4));
+        assert_eq!(balance_threads(0, 2), (1, 1));
     }
 }
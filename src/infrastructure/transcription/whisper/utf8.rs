@@ -0,0 +1,130 @@
+//! UTF-8 reconstruction of token texts
+//!
+//! Whisper uses a byte-level BPE vocabulary, so a single Cyrillic, CJK or emoji
+//! character is often split across two or more tokens. Decoding each token's bytes
+//! on its own yields U+FFFD replacement characters; `Utf8TokenDecoder` instead
+//! carries incomplete sequences over to the next token, so each character is
+//! attributed to the token that completes it.
+
+/// Decoder turning the raw bytes of consecutive tokens into text
+#[derive(Debug, Default)]
+pub struct Utf8TokenDecoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8TokenDecoder {
+    /// Create a decoder with no pending bytes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode the bytes of the next token
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - Raw bytes of the token
+    ///
+    /// # Returns
+    ///
+    /// The characters completed by this token; trailing bytes of an incomplete
+    /// character are kept for the next token. Invalid bytes become U+FFFD.
+    pub fn push(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+
+        let mut text = String::new();
+        let mut rest: &[u8] = &self.pending;
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    rest = &[];
+                    break;
+                }
+                Err(e) => {
+                    let (valid, after) = rest.split_at(e.valid_up_to());
+                    // Only checked by `from_utf8` above
+                    text.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                    match e.error_len() {
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            rest = &after[len..];
+                        }
+                        // Incomplete character at the end, wait for more bytes
+                        None => {
+                            rest = after;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.pending = rest.to_vec();
+        text
+    }
+
+    /// Flush bytes of a character that was never completed
+    ///
+    /// # Returns
+    ///
+    /// Replacement characters for the pending bytes, or an empty string
+    pub fn finish(&mut self) -> String {
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Split `text` into tokens at the given byte offsets and decode them
+    fn decode_split(text: &str, splits: &[usize]) -> Vec<String> {
+        let bytes = text.as_bytes();
+        let mut bounds = vec![0];
+        bounds.extend_from_slice(splits);
+        bounds.push(bytes.len());
+
+        let mut decoder = Utf8TokenDecoder::new();
+        let mut texts: Vec<String> = bounds
+            .windows(2)
+            .map(|w| decoder.push(&bytes[w[0]..w[1]]))
+            .collect();
+        if let Some(last) = texts.last_mut() {
+            last.push_str(&decoder.finish());
+        }
+        texts
+    }
+
+    #[test]
+    fn test_russian_split_inside_characters() {
+        // "Привет" is 12 bytes, two per letter
+        let texts = decode_split(" Привет", &[2, 6, 8]);
+        assert_eq!(texts, vec![" ", "Пр", "и", "вет"]);
+        assert_eq!(texts.concat(), " Привет");
+    }
+
+    #[test]
+    fn test_japanese_split_inside_characters() {
+        // Three bytes per character
+        let texts = decode_split("こんにちは", &[1, 2, 4, 9]);
+        assert_eq!(texts, vec!["", "", "こ", "んに", "ちは"]);
+    }
+
+    #[test]
+    fn test_emoji_split_across_tokens() {
+        // 👋 is four bytes
+        let texts = decode_split(" hi 👋!", &[5, 6, 8]);
+        assert_eq!(texts, vec![" hi ", "", "👋", "!"]);
+    }
+
+    #[test]
+    fn test_invalid_and_unfinished_bytes_are_replaced() {
+        let mut decoder = Utf8TokenDecoder::new();
+        assert_eq!(decoder.push(&[b'a', 0xFF, b'b']), "a\u{FFFD}b");
+        assert_eq!(decoder.push(&[0xD0]), "");
+        assert_eq!(decoder.finish(), "\u{FFFD}");
+        assert_eq!(decoder.finish(), "");
+    }
+}
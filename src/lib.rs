@@ -10,10 +10,22 @@ pub use infrastructure::audio::{
     ChunkConfig, WHISPER_SAMPLE_RATE,
 };
 
+pub use infrastructure::{
+    CancellationToken, FormatOptions, Grammar, MockBackend, ProgressCallback, QualityConfig,
+    ReviewItem, Segment, SegmentCallback, Token, TranscribeOptions, TranscriptionBackend,
+    TranscriptionResult, VadOptions, Word,
+};
+
+#[cfg(feature = "http")]
+pub use infrastructure::HttpBackend;
+
+#[cfg(feature = "serve")]
+pub use infrastructure::ServerConfig;
+
 #[cfg(feature = "whisper")]
 pub use infrastructure::{
-    ModelSize, ModelSource, Segment, Token, TranscriptionResult, WhisperContextWrapper,
-    WhisperModelManager, WhisperTranscriber,
+    LanguageDetection, LanguageProbability, ModelSize, ModelSource, PooledState,
+    WhisperContextWrapper, WhisperModelManager, WhisperState, WhisperStatePool, WhisperTranscriber,
 };
 
 /// Library version